@@ -0,0 +1,86 @@
+// Compares the plain interpreter with the predecoded engine on a long running program.
+// Run with `cargo bench`.
+
+use std::time::{Duration, Instant};
+
+use lc_3::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    cpu::{Engine, RunState, CPU},
+};
+
+// Bubble sorts a descending array of 1000 words, roughly 5.5 million instructions
+const PROGRAM: &str = "
+        .ORIG x3000
+        LEA R6, ARRAY
+        LD R1, COUNT
+FILL    ADD R2, R1, #0
+        STR R2, R6, #0
+        ADD R6, R6, #1
+        ADD R1, R1, #-1
+        BRp FILL
+        LD R1, COUNT
+OUTER   ADD R1, R1, #-1
+        BRz DONE
+        LEA R6, ARRAY
+        ADD R5, R1, #0
+INNER   LDR R2, R6, #0
+        LDR R3, R6, #1
+        NOT R4, R3
+        ADD R4, R4, #1
+        ADD R4, R2, R4
+        BRnz NEXT
+        STR R3, R6, #0
+        STR R2, R6, #1
+NEXT    ADD R6, R6, #1
+        ADD R5, R5, #-1
+        BRp INNER
+        BR OUTER
+DONE    HALT
+COUNT   .FILL #1000
+ARRAY   .BLKW #1000
+        .END
+";
+
+const ROUNDS: usize = 5;
+
+fn measure(program: &Program, engine: Engine) -> (Duration, u64) {
+    let mut best: Duration = Duration::MAX;
+    let mut instructions: u64 = 0;
+
+    for _ in 0..ROUNDS {
+        let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(&[])));
+        cpu.set_engine(engine);
+        cpu.load_image(&program.to_object()).unwrap();
+
+        let start: Instant = Instant::now();
+        assert_eq!(cpu.run(None), RunState::Halted);
+        best = best.min(start.elapsed());
+        instructions = cpu.instruction_count();
+    }
+
+    (best, instructions)
+}
+
+fn main() {
+    let program: Program = assemble(PROGRAM).unwrap();
+    let mut results: Vec<Duration> = Vec::new();
+
+    for engine in [Engine::Interpreter, Engine::Predecoded] {
+        let (elapsed, instructions) = measure(&program, engine);
+
+        println!(
+            "{:<12} {:>10} instructions in {:>8.2} ms ({:.1} MIPS)",
+            format!("{:?}", engine),
+            instructions,
+            elapsed.as_secs_f64() * 1000.0,
+            instructions as f64 / elapsed.as_secs_f64() / 1_000_000.0
+        );
+        results.push(elapsed);
+    }
+
+    println!(
+        "Predecoded speedup: {:.2}x",
+        results[0].as_secs_f64() / results[1].as_secs_f64()
+    );
+}
@@ -0,0 +1,75 @@
+// Generated from ffi/src/lib.rs by `LC_3_WRITE_HEADER=1 cargo test -p lc_3_ffi`, do not edit
+
+#ifndef LC_3_H
+#define LC_3_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+// States returned by lc3_step, lc3_run and lc3_state
+#define LC3_RUNNING 0
+#define LC3_HALTED 1
+#define LC3_INPUT_EXHAUSTED 2 // An input callback returned -1
+#define LC3_FAULT 3 // An exception with no OS image loaded to handle it
+#define LC3_ERROR (-1) // Bad arguments, or the VM failed unexpectedly
+
+// Returns the next input byte, or -1 when there is none
+typedef int32_t (*lc3_input_fn)(void *user_data);
+
+typedef void (*lc3_output_fn)(void *user_data, uint8_t byte);
+
+// Opaque handle owning a machine
+typedef struct lc3_cpu lc3_cpu;
+
+// Creates a machine without input that discards its output until lc3_set_console is called.
+// Free it with lc3_cpu_free
+lc3_cpu *lc3_cpu_new(void);
+
+void lc3_cpu_free(lc3_cpu *cpu);
+
+// Sets the callbacks behind the keyboard, display and the I/O traps. `user_data` is passed
+// back to them unchanged
+void lc3_set_console(lc3_cpu *cpu, lc3_input_fn input, lc3_output_fn output, void *user_data);
+
+// Loads an object image (.obj bytes or the .hex and .bin text formats) and points the PC at
+// its origin. Returns 0, or LC3_ERROR for a malformed image
+int32_t lc3_load_image(lc3_cpu *cpu, const uint8_t *data, size_t length);
+
+// Executes one instruction and returns the state afterwards
+int32_t lc3_step(lc3_cpu *cpu);
+
+// Runs until the machine stops or `budget` instructions have executed, 0 meaning no budget
+int32_t lc3_run(lc3_cpu *cpu, uint64_t budget);
+
+int32_t lc3_state(const lc3_cpu *cpu);
+
+// Lets a machine that ran out of input continue once the input callback has more
+void lc3_resume(lc3_cpu *cpu);
+
+uint64_t lc3_instruction_count(const lc3_cpu *cpu);
+
+// R0 to R7, anything else reads as 0
+uint16_t lc3_get_register(const lc3_cpu *cpu, uint8_t index);
+
+void lc3_set_register(lc3_cpu *cpu, uint8_t index, uint16_t value);
+
+uint16_t lc3_get_pc(const lc3_cpu *cpu);
+
+void lc3_set_pc(lc3_cpu *cpu, uint16_t address);
+
+uint16_t lc3_get_psr(const lc3_cpu *cpu);
+
+// Memory is read and written without device side effects, like a debugger would
+uint16_t lc3_read_memory(const lc3_cpu *cpu, uint16_t address);
+
+void lc3_write_memory(lc3_cpu *cpu, uint16_t address, uint16_t value);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,222 @@
+// C interface to the virtual machine, built as liblc3. lc_3.h declares it and is generated
+// from this file by `LC_3_WRITE_HEADER=1 cargo test -p lc_3_ffi`.
+//
+// Every function taking an `lc3_cpu *` expects a pointer returned by lc3_cpu_new that has not
+// been freed, and does nothing useful for a null pointer. Buffers must hold at least the
+// length passed with them.
+#![allow(clippy::missing_safety_doc)]
+
+use std::{
+    ffi::c_void,
+    panic::{self, AssertUnwindSafe},
+    ptr, slice,
+};
+
+use lc_3::{
+    console::Console,
+    cpu::{RunState, CPU},
+};
+
+// States returned by lc3_step, lc3_run and lc3_state
+pub const LC3_RUNNING: i32 = 0;
+pub const LC3_HALTED: i32 = 1;
+pub const LC3_INPUT_EXHAUSTED: i32 = 2; // An input callback returned -1
+pub const LC3_FAULT: i32 = 3; // An exception with no OS image loaded to handle it
+pub const LC3_ERROR: i32 = -1; // Bad arguments, or the VM failed unexpectedly
+
+// Returns the next input byte, or -1 when there is none
+pub type Lc3InputFn = extern "C" fn(user_data: *mut c_void) -> i32;
+pub type Lc3OutputFn = extern "C" fn(user_data: *mut c_void, byte: u8);
+
+// Opaque handle owning a machine
+pub struct Lc3Cpu {
+    cpu: CPU,
+}
+
+// Hands console traffic to the embedder's callbacks, a missing callback reads nothing and
+// discards output
+struct CallbackConsole {
+    input: Option<Lc3InputFn>,
+    output: Option<Lc3OutputFn>,
+    user_data: *mut c_void,
+}
+
+impl Console for CallbackConsole {
+    fn read_byte(&mut self) -> Option<u8> {
+        let byte: i32 = (self.input?)(self.user_data);
+
+        u8::try_from(byte).ok()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        if let Some(output) = self.output {
+            output(self.user_data, byte);
+        }
+    }
+}
+
+fn state_code(state: RunState) -> i32 {
+    match state {
+        RunState::Running => LC3_RUNNING,
+        RunState::Halted => LC3_HALTED,
+        RunState::InputExhausted => LC3_INPUT_EXHAUSTED,
+        RunState::Fault(_) => LC3_FAULT,
+    }
+}
+
+// A panic must not unwind into C, it becomes LC3_ERROR instead
+fn guard(run: impl FnOnce() -> RunState) -> i32 {
+    match panic::catch_unwind(AssertUnwindSafe(run)) {
+        Ok(state) => state_code(state),
+        Err(_) => LC3_ERROR,
+    }
+}
+
+// Creates a machine without input that discards its output until lc3_set_console is called.
+// Free it with lc3_cpu_free
+#[no_mangle]
+pub extern "C" fn lc3_cpu_new() -> *mut Lc3Cpu {
+    let console: CallbackConsole = CallbackConsole {
+        input: None,
+        output: None,
+        user_data: ptr::null_mut(),
+    };
+
+    Box::into_raw(Box::new(Lc3Cpu {
+        cpu: CPU::with_console(Box::new(console)),
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_cpu_free(cpu: *mut Lc3Cpu) {
+    if !cpu.is_null() {
+        drop(Box::from_raw(cpu));
+    }
+}
+
+// Sets the callbacks behind the keyboard, display and the I/O traps. `user_data` is passed
+// back to them unchanged
+#[no_mangle]
+pub unsafe extern "C" fn lc3_set_console(
+    cpu: *mut Lc3Cpu,
+    input: Option<Lc3InputFn>,
+    output: Option<Lc3OutputFn>,
+    user_data: *mut c_void,
+) {
+    if let Some(cpu) = cpu.as_mut() {
+        cpu.cpu.memory_mut().set_console(Box::new(CallbackConsole {
+            input,
+            output,
+            user_data,
+        }));
+    }
+}
+
+// Loads an object image (.obj bytes or the .hex and .bin text formats) and points the PC at
+// its origin. Returns 0, or LC3_ERROR for a malformed image
+#[no_mangle]
+pub unsafe extern "C" fn lc3_load_image(cpu: *mut Lc3Cpu, data: *const u8, length: usize) -> i32 {
+    let Some(cpu) = cpu.as_mut() else {
+        return LC3_ERROR;
+    };
+    if data.is_null() {
+        return LC3_ERROR;
+    }
+
+    match cpu.cpu.load_image(slice::from_raw_parts(data, length)) {
+        Ok(_) => 0,
+        Err(_) => LC3_ERROR,
+    }
+}
+
+// Executes one instruction and returns the state afterwards
+#[no_mangle]
+pub unsafe extern "C" fn lc3_step(cpu: *mut Lc3Cpu) -> i32 {
+    match cpu.as_mut() {
+        Some(cpu) => guard(|| cpu.cpu.step()),
+        None => LC3_ERROR,
+    }
+}
+
+// Runs until the machine stops or `budget` instructions have executed, 0 meaning no budget
+#[no_mangle]
+pub unsafe extern "C" fn lc3_run(cpu: *mut Lc3Cpu, budget: u64) -> i32 {
+    let limit: Option<u64> = (budget != 0).then_some(budget);
+
+    match cpu.as_mut() {
+        Some(cpu) => guard(|| cpu.cpu.run(limit)),
+        None => LC3_ERROR,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_state(cpu: *const Lc3Cpu) -> i32 {
+    match cpu.as_ref() {
+        Some(cpu) => state_code(cpu.cpu.state()),
+        None => LC3_ERROR,
+    }
+}
+
+// Lets a machine that ran out of input continue once the input callback has more
+#[no_mangle]
+pub unsafe extern "C" fn lc3_resume(cpu: *mut Lc3Cpu) {
+    if let Some(cpu) = cpu.as_mut() {
+        cpu.cpu.resume();
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_instruction_count(cpu: *const Lc3Cpu) -> u64 {
+    cpu.as_ref().map_or(0, |cpu| cpu.cpu.instruction_count())
+}
+
+// R0 to R7, anything else reads as 0
+#[no_mangle]
+pub unsafe extern "C" fn lc3_get_register(cpu: *const Lc3Cpu, index: u8) -> u16 {
+    match cpu.as_ref() {
+        Some(cpu) if index < 8 => cpu.cpu.registers()[index as usize],
+        _ => 0,
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_set_register(cpu: *mut Lc3Cpu, index: u8, value: u16) {
+    if let Some(cpu) = cpu.as_mut().filter(|_| index < 8) {
+        cpu.cpu.set_register(index as usize, value);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_get_pc(cpu: *const Lc3Cpu) -> u16 {
+    cpu.as_ref().map_or(0, |cpu| cpu.cpu.program_counter())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_set_pc(cpu: *mut Lc3Cpu, address: u16) {
+    if let Some(cpu) = cpu.as_mut() {
+        cpu.cpu.set_program_counter(address);
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_get_psr(cpu: *const Lc3Cpu) -> u16 {
+    cpu.as_ref()
+        .map_or(0, |cpu| cpu.cpu.processor_status_register())
+}
+
+// Memory is read and written without device side effects, like a debugger would
+#[no_mangle]
+pub unsafe extern "C" fn lc3_read_memory(cpu: *const Lc3Cpu, address: u16) -> u16 {
+    cpu.as_ref().map_or(0, |cpu| cpu.cpu.memory().peek(address))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn lc3_write_memory(cpu: *mut Lc3Cpu, address: u16, value: u16) {
+    if let Some(cpu) = cpu.as_mut() {
+        cpu.cpu.memory_mut().poke(address, value);
+    }
+}
+
+#[cfg(test)]
+#[path = "./lib_test.rs"]
+mod lib_test;
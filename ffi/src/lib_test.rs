@@ -0,0 +1,277 @@
+use std::{collections::VecDeque, ffi::c_void, ptr};
+
+use lc_3::assembler::assemble;
+
+use crate::{
+    lc3_cpu_free, lc3_cpu_new, lc3_get_pc, lc3_get_register, lc3_instruction_count, lc3_load_image,
+    lc3_read_memory, lc3_resume, lc3_run, lc3_set_console, lc3_set_pc, lc3_set_register, lc3_state,
+    lc3_step, lc3_write_memory, Lc3Cpu, LC3_ERROR, LC3_FAULT, LC3_HALTED, LC3_INPUT_EXHAUSTED,
+    LC3_RUNNING,
+};
+
+// Relative to the package, where tests run
+const HEADER_PATH: &str = "lc_3.h";
+
+// C declaration of a Rust type used by the interface
+fn c_type(rust: &str) -> &'static str {
+    match rust {
+        "i32" => "int32_t",
+        "u8" => "uint8_t",
+        "u16" => "uint16_t",
+        "u64" => "uint64_t",
+        "usize" => "size_t",
+        "*mut c_void" => "void *",
+        "*const u8" => "const uint8_t *",
+        "*mut Lc3Cpu" => "lc3_cpu *",
+        "*const Lc3Cpu" => "const lc3_cpu *",
+        "Lc3InputFn" | "Option<Lc3InputFn>" => "lc3_input_fn",
+        "Lc3OutputFn" | "Option<Lc3OutputFn>" => "lc3_output_fn",
+        _ => panic!("no C type for '{}'", rust),
+    }
+}
+
+// `type name`, without a space after a pointer's star
+fn declaration(c_type: &str, name: &str) -> String {
+    match c_type.ends_with('*') {
+        true => format!("{}{}", c_type, name),
+        false => format!("{} {}", c_type, name),
+    }
+}
+
+// C parameter list of a Rust one such as `cpu: *mut Lc3Cpu, index: u8`
+fn parameters(list: &str) -> String {
+    let parameters: Vec<String> = list
+        .split(',')
+        .map(str::trim)
+        .filter(|parameter| !parameter.is_empty())
+        .map(|parameter| {
+            let (name, rust) = parameter.split_once(':').unwrap();
+            declaration(c_type(rust.trim()), name.trim())
+        })
+        .collect();
+
+    match parameters.is_empty() {
+        true => String::from("void"),
+        false => parameters.join(", "),
+    }
+}
+
+// Splits `name(parameters) -> result` into its parts, the result being `void` if there is none
+fn signature(text: &str) -> (&str, &str, &str) {
+    let (name, rest) = text.split_once('(').unwrap();
+    let (list, result) = rest.rsplit_once(')').unwrap();
+    let result: &str = match result.trim().strip_prefix("->") {
+        Some(result) => c_type(result.trim()),
+        None => "void",
+    };
+
+    (name.trim(), list, result)
+}
+
+// Writes the C header for the exported items of lib.rs, carrying their comments over
+fn header(source: &str) -> String {
+    let mut text: String = String::from(
+        "// Generated from ffi/src/lib.rs by `LC_3_WRITE_HEADER=1 cargo test -p lc_3_ffi`, do not edit\n\n\
+         #ifndef LC_3_H\n#define LC_3_H\n\n#include <stddef.h>\n#include <stdint.h>\n\n\
+         #ifdef __cplusplus\nextern \"C\" {\n#endif\n",
+    );
+    let mut comments: Vec<&str> = Vec::new();
+    let mut lines = source.lines();
+
+    while let Some(line) = lines.next() {
+        let line: &str = line.trim();
+
+        if line.starts_with("//") {
+            comments.push(line);
+            continue;
+        }
+        if line == "#[no_mangle]" {
+            continue;
+        }
+
+        let item: Option<String> = if let Some(constant) = line.strip_prefix("pub const ") {
+            let (name, rest) = constant.split_once(':').unwrap();
+            let (value, comment) = rest.split_once("//").unwrap_or((rest, ""));
+            let value: &str = value
+                .split_once('=')
+                .unwrap()
+                .1
+                .trim()
+                .trim_end_matches(';');
+
+            // Negative values are parenthesized so the macro expands safely next to an operator
+            let mut define: String = match value.starts_with('-') {
+                true => format!("#define {} ({})", name, value),
+                false => format!("#define {} {}", name, value),
+            };
+            if !comment.is_empty() {
+                define.push_str(&format!(" //{}", comment));
+            }
+            Some(define)
+        } else if let Some(alias) = line.strip_prefix("pub type ") {
+            let (name, function) = alias.split_once(" = extern \"C\" fn").unwrap();
+            let (_, list, result) = signature(function.trim_end_matches(';'));
+
+            Some(format!(
+                "typedef {}(*{})({});",
+                declaration(result, ""),
+                c_type(name),
+                parameters(list)
+            ))
+        } else if line == "pub struct Lc3Cpu {" {
+            Some(String::from("typedef struct lc3_cpu lc3_cpu;"))
+        } else if line.starts_with("pub extern \"C\" fn") || line.starts_with("pub unsafe extern") {
+            // Signatures may be wrapped over several lines
+            let mut function: String = line.to_string();
+            while !function.contains('{') {
+                function.push_str(lines.next().unwrap().trim());
+            }
+            let function: &str = function.split_once(" fn ").unwrap().1;
+            let (name, list, result) = signature(function.split_once('{').unwrap().0);
+
+            Some(format!(
+                "{}({});",
+                declaration(result, name),
+                parameters(list)
+            ))
+        } else {
+            None
+        };
+
+        match item {
+            Some(item) => {
+                // Constants in a group share the comment above the first one
+                if !comments.is_empty() || !item.starts_with("#define") {
+                    text.push('\n');
+                }
+                for comment in comments.drain(..) {
+                    text.push_str(comment);
+                    text.push('\n');
+                }
+                text.push_str(&item);
+                text.push('\n');
+            }
+            None => comments.clear(),
+        }
+    }
+
+    text.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif\n");
+    text
+}
+
+#[test]
+fn test_header_is_current() {
+    let generated: String = header(include_str!("./lib.rs"));
+
+    if std::env::var_os("LC_3_WRITE_HEADER").is_some() {
+        std::fs::write(HEADER_PATH, &generated).unwrap();
+    }
+
+    assert!(generated.contains("\n#define LC3_ERROR (-1) //"));
+
+    let current: String = std::fs::read_to_string(HEADER_PATH).unwrap();
+    assert!(
+        current == generated,
+        "{} is out of date, regenerate it with `LC_3_WRITE_HEADER=1 cargo test -p lc_3_ffi`",
+        HEADER_PATH
+    );
+}
+
+// Input and output of a machine driven through the C interface
+#[derive(Default)]
+struct Terminal {
+    input: VecDeque<u8>,
+    output: Vec<u8>,
+}
+
+extern "C" fn input(user_data: *mut c_void) -> i32 {
+    let terminal: &mut Terminal = unsafe { &mut *(user_data as *mut Terminal) };
+
+    terminal.input.pop_front().map_or(-1, i32::from)
+}
+
+extern "C" fn output(user_data: *mut c_void, byte: u8) {
+    let terminal: &mut Terminal = unsafe { &mut *(user_data as *mut Terminal) };
+
+    terminal.output.push(byte);
+}
+
+#[test]
+fn test_console_callbacks() {
+    let image: Vec<u8> = assemble(
+        "
+        .ORIG x3000
+LOOP    GETC
+        OUT
+        ADD R1, R1, #1
+        ADD R2, R0, #-10
+        BRnp LOOP
+        HALT
+        .END
+        ",
+    )
+    .unwrap()
+    .to_object();
+    let mut terminal: Terminal = Terminal::default();
+    terminal.input.extend(b"ab");
+
+    unsafe {
+        let cpu: *mut Lc3Cpu = lc3_cpu_new();
+        let user_data: *mut c_void = &mut terminal as *mut Terminal as *mut c_void;
+
+        lc3_set_console(cpu, Some(input), Some(output), user_data);
+        assert_eq!(lc3_load_image(cpu, image.as_ptr(), image.len()), 0);
+        assert_eq!(lc3_get_pc(cpu), 0x3000);
+
+        // Out of input after two keys, then finishing once a newline arrives
+        assert_eq!(lc3_run(cpu, 0), LC3_INPUT_EXHAUSTED);
+        assert_eq!(lc3_get_register(cpu, 1), 2);
+        (*(user_data as *mut Terminal)).input.push_back(b'\n');
+        lc3_resume(cpu);
+        assert_eq!(lc3_run(cpu, 0), LC3_HALTED);
+        assert_eq!(lc3_state(cpu), LC3_HALTED);
+
+        lc3_cpu_free(cpu);
+    }
+
+    assert_eq!(terminal.output, b"ab\n\nHALT\n");
+}
+
+#[test]
+fn test_registers_memory_and_budget() {
+    // BRnzp to itself
+    let image: [u8; 4] = [0x30, 0x00, 0x0F, 0xFF];
+
+    unsafe {
+        let cpu: *mut Lc3Cpu = lc3_cpu_new();
+
+        assert_eq!(lc3_load_image(cpu, image.as_ptr(), 1), LC3_ERROR);
+        assert_eq!(lc3_load_image(cpu, ptr::null(), 0), LC3_ERROR);
+        assert_eq!(lc3_load_image(cpu, image.as_ptr(), image.len()), 0);
+
+        assert_eq!(lc3_run(cpu, 100), LC3_RUNNING);
+        assert_eq!(lc3_step(cpu), LC3_RUNNING);
+        assert_eq!(lc3_instruction_count(cpu), 101);
+
+        lc3_set_register(cpu, 7, 0xBEEF);
+        lc3_set_register(cpu, 8, 0x1234);
+        assert_eq!(lc3_get_register(cpu, 7), 0xBEEF);
+        assert_eq!(lc3_get_register(cpu, 8), 0);
+
+        lc3_write_memory(cpu, 0x4000, 0xCAFE);
+        assert_eq!(lc3_read_memory(cpu, 0x4000), 0xCAFE);
+
+        // A trap nobody handles is the program's fault, not the VM's
+        lc3_write_memory(cpu, 0x4001, 0xF026);
+        lc3_set_pc(cpu, 0x4001);
+        assert_eq!(lc3_run(cpu, 10), LC3_FAULT);
+        assert_eq!(lc3_get_pc(cpu), 0x4001);
+
+        lc3_cpu_free(cpu);
+
+        // Null handles are refused rather than dereferenced
+        assert_eq!(lc3_run(ptr::null_mut(), 1), LC3_ERROR);
+        assert_eq!(lc3_state(ptr::null()), LC3_ERROR);
+        lc3_cpu_free(ptr::null_mut());
+    }
+}
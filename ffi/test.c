@@ -0,0 +1,95 @@
+// Drives the library through lc_3.h the way an embedder would, run it with `make test`
+#include <stdio.h>
+#include <string.h>
+
+#include "lc_3.h"
+
+#define CHECK(condition)                                                            \
+    do {                                                                            \
+        if (!(condition)) {                                                         \
+            fprintf(stderr, "%s:%d: failed: %s\n", __FILE__, __LINE__, #condition); \
+            return 1;                                                               \
+        }                                                                           \
+    } while (0)
+
+struct terminal {
+    const char *input;
+    char output[64];
+    size_t length;
+};
+
+static int32_t input(void *user_data) {
+    struct terminal *terminal = user_data;
+
+    if (*terminal->input == '\0') {
+        return -1;
+    }
+    return (uint8_t)*terminal->input++;
+}
+
+static void output(void *user_data, uint8_t byte) {
+    struct terminal *terminal = user_data;
+
+    if (terminal->length < sizeof(terminal->output) - 1) {
+        terminal->output[terminal->length++] = (char)byte;
+    }
+}
+
+// Echoes keys until a newline, counting them in R1
+static const uint8_t ECHO[] = {
+    0x30, 0x00, // .ORIG x3000
+    0xF0, 0x20, // LOOP GETC
+    0xF0, 0x21, //      OUT
+    0x12, 0x61, //      ADD R1, R1, #1
+    0x14, 0x36, //      ADD R2, R0, #-10
+    0x0B, 0xFB, //      BRnp LOOP
+    0xF0, 0x25, //      HALT
+};
+
+int main(void) {
+    struct terminal terminal = {"hi", {0}, 0};
+    lc3_cpu *cpu = lc3_cpu_new();
+
+    CHECK(cpu != NULL);
+    lc3_set_console(cpu, input, output, &terminal);
+    CHECK(lc3_load_image(cpu, ECHO, 1) == LC3_ERROR);
+    CHECK(lc3_load_image(cpu, ECHO, sizeof(ECHO)) == 0);
+    CHECK(lc3_get_pc(cpu) == 0x3000);
+
+    CHECK(lc3_step(cpu) == LC3_RUNNING);
+    CHECK(lc3_get_register(cpu, 0) == 'h');
+
+    CHECK(lc3_run(cpu, 0) == LC3_INPUT_EXHAUSTED);
+    CHECK(lc3_get_register(cpu, 1) == 2);
+    terminal.input = "\n";
+    lc3_resume(cpu);
+    CHECK(lc3_run(cpu, 0) == LC3_HALTED);
+    CHECK(lc3_state(cpu) == LC3_HALTED);
+    CHECK(strcmp(terminal.output, "hi\n\nHALT\n") == 0);
+
+    lc3_set_register(cpu, 5, 0xBEEF);
+    CHECK(lc3_get_register(cpu, 5) == 0xBEEF);
+    lc3_write_memory(cpu, 0x4000, 0x1234);
+    CHECK(lc3_read_memory(cpu, 0x4000) == 0x1234);
+    CHECK(lc3_read_memory(cpu, 0x3000) == 0xF020);
+
+    lc3_cpu_free(cpu);
+
+    // A budget stops a program that never halts
+    cpu = lc3_cpu_new();
+    CHECK(lc3_load_image(cpu, ECHO, sizeof(ECHO)) == 0);
+    lc3_write_memory(cpu, 0x3000, 0x0FFF); // BRnzp to itself
+    CHECK(lc3_run(cpu, 50) == LC3_RUNNING);
+    CHECK(lc3_instruction_count(cpu) == 50);
+    CHECK(lc3_get_pc(cpu) == 0x3000);
+    lc3_set_pc(cpu, 0x3005);
+    CHECK(lc3_step(cpu) == LC3_HALTED);
+
+    lc3_cpu_free(cpu);
+    CHECK(lc3_state(NULL) == LC3_ERROR);
+    // The constants expand safely inside expressions
+    CHECK(1-LC3_ERROR == 2);
+
+    printf("ok\n");
+    return 0;
+}
@@ -0,0 +1,118 @@
+; Conformance checks for the operate instructions ADD, AND and NOT and the
+; condition codes they set. Prints a dot for every passing check followed by
+; PASS, or stops with FAIL at the first mismatch.
+
+        .ORIG x3000
+
+; ADD register mode, positive result
+        AND R1, R1, #0
+        ADD R1, R1, #7
+        ADD R3, R1, R1
+        BRnz FAIL
+        LD R4, FOURTEEN
+        JSR COMPARE
+        JSR DOT
+
+; ADD with the most negative 5 bit immediate
+        ADD R3, R1, #-16
+        BRzp FAIL
+        ADD R3, R3, #9
+        BRnp FAIL
+        JSR DOT
+
+; ADD with the most positive 5 bit immediate
+        AND R1, R1, #0
+        ADD R3, R1, #15
+        BRnz FAIL
+        ADD R3, R3, #-15
+        BRnp FAIL
+        JSR DOT
+
+; ADD overflowing into the sign bit
+        LD R1, MAX_INT
+        ADD R3, R1, #1
+        BRzp FAIL
+        LD R4, MIN_INT
+        JSR COMPARE
+        JSR DOT
+
+; ADD wrapping around to zero
+        LD R1, ALL_ONES
+        ADD R3, R1, #1
+        BRnp FAIL
+        JSR DOT
+
+; AND register mode
+        LD R1, PATTERN_A
+        LD R2, PATTERN_B
+        AND R3, R1, R2
+        BRnz FAIL
+        LD R4, PATTERN_AND
+        JSR COMPARE
+        JSR DOT
+
+; AND with zero clears the register and sets Z
+        AND R3, R1, #0
+        BRnp FAIL
+        JSR DOT
+
+; AND with -1 keeps every bit, including the sign
+        AND R3, R1, #-1
+        BRzp FAIL
+        ADD R4, R1, #0
+        JSR COMPARE
+        JSR DOT
+
+; NOT of a negative value is positive, and NOT twice is the identity
+        NOT R3, R1
+        BRnz FAIL
+        NOT R3, R3
+        BRzp FAIL
+        ADD R4, R1, #0
+        JSR COMPARE
+        JSR DOT
+
+; NOT of all ones is zero
+        LD R1, ALL_ONES
+        NOT R3, R1
+        BRnp FAIL
+        JSR DOT
+
+        LEA R0, PASSED
+        PUTS
+        HALT
+
+FAIL    LEA R0, FAILED
+        PUTS
+        HALT
+
+; Fails unless R3 equals R4, clobbers R4
+COMPARE NOT R4, R4
+        ADD R4, R4, #1
+        ADD R4, R3, R4
+        BRnp FAIL
+        RET
+
+; Prints a dot, preserving R0 and R7
+DOT     ST R0, SAVE_R0
+        ST R7, SAVE_R7
+        LD R0, DOT_CHAR
+        OUT
+        LD R0, SAVE_R0
+        LD R7, SAVE_R7
+        RET
+
+SAVE_R0     .BLKW 1
+SAVE_R7     .BLKW 1
+DOT_CHAR    .FILL x002E
+FOURTEEN    .FILL #14
+MAX_INT     .FILL x7FFF
+MIN_INT     .FILL x8000
+ALL_ONES    .FILL xFFFF
+PATTERN_A   .FILL xF0F0
+PATTERN_B   .FILL x3C3C
+PATTERN_AND .FILL x3030
+PASSED      .STRINGZ " PASS\n"
+FAILED      .STRINGZ " FAIL\n"
+
+        .END
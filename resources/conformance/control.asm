@@ -0,0 +1,99 @@
+; Conformance checks for the control instructions BR, JMP, RET, JSR and JSRR.
+; Prints a dot for every passing check followed by PASS, or stops with FAIL at
+; the first mismatch.
+
+        .ORIG x3000
+
+; BR against a zero result
+        AND R0, R0, #0
+        BRn FAIL
+        BRp FAIL
+        BRnp FAIL
+        BRz ZERO_OK
+        BRnzp FAIL
+ZERO_OK JSR DOT
+
+; BR against a negative result
+        ADD R0, R0, #-1
+        BRz FAIL
+        BRp FAIL
+        BRzp FAIL
+        BRn NEGATIVE_OK
+        BRnzp FAIL
+NEGATIVE_OK
+        JSR DOT
+
+; BR against a positive result
+        ADD R0, R0, #2
+        BRn FAIL
+        BRz FAIL
+        BRnz FAIL
+        BRp POSITIVE_OK
+        BRnzp FAIL
+POSITIVE_OK
+        JSR DOT
+
+; BR without condition flags never branches
+        .FILL x0001
+        BRnzp NOP_OK
+        .FILL xFFFF
+NOP_OK  JSR DOT
+
+; JMP to an address held in a register
+        LEA R3, JUMP_OK
+        JMP R3
+        BRnzp FAIL
+JUMP_OK JSR DOT
+
+; JSR and RET
+        AND R1, R1, #0
+        JSR MARK
+        ADD R1, R1, #-1
+        BRnp FAIL
+        JSR DOT
+
+; JSRR through a register
+        AND R1, R1, #0
+        LEA R2, MARK
+        JSRR R2
+        ADD R1, R1, #-1
+        BRnp FAIL
+        JSR DOT
+
+; JSRR R7 jumps to the old R7 before saving the return address into it
+        AND R1, R1, #0
+        LEA R7, MARK
+        JSRR R7
+        ADD R1, R1, #-1
+        BRnp FAIL
+        JSR DOT
+
+        LEA R0, PASSED
+        PUTS
+        HALT
+
+FAIL    LEA R0, FAILED
+        PUTS
+        HALT
+
+; Sets R1 to one and returns
+MARK    AND R1, R1, #0
+        ADD R1, R1, #1
+        RET
+
+; Prints a dot, preserving R0 and R7
+DOT     ST R0, SAVE_R0
+        ST R7, SAVE_R7
+        LD R0, DOT_CHAR
+        OUT
+        LD R0, SAVE_R0
+        LD R7, SAVE_R7
+        RET
+
+SAVE_R0  .BLKW 1
+SAVE_R7  .BLKW 1
+DOT_CHAR .FILL x002E
+PASSED   .STRINGZ " PASS\n"
+FAILED   .STRINGZ " FAIL\n"
+
+        .END
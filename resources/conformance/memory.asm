@@ -0,0 +1,113 @@
+; Conformance checks for the data movement instructions LD, LDI, LDR, LEA, ST,
+; STI and STR. Loads and LEA set the condition codes, stores leave them alone.
+; Prints a dot for every passing check followed by PASS, or stops with FAIL at
+; the first mismatch.
+
+        .ORIG x3000
+
+; LD sets N, Z and P from the loaded value
+        LD R1, NEGATIVE
+        BRzp FAIL
+        LD R1, ZERO
+        BRnp FAIL
+        LD R1, VALUE
+        BRnz FAIL
+        JSR DOT
+
+; LDI loads through a pointer
+        LDI R3, POINTER
+        BRnz FAIL
+        LD R4, VALUE
+        JSR COMPARE
+        JSR DOT
+
+; LDR with positive and negative offsets
+        LEA R2, VALUE
+        LDR R3, R2, #0
+        LD R4, VALUE
+        JSR COMPARE
+        LEA R2, AFTER
+        LDR R3, R2, #-1
+        LD R4, BEFORE
+        JSR COMPARE
+        JSR DOT
+
+; LEA computes an address without touching memory and sets the condition codes
+        AND R0, R0, #0
+        LEA R2, VALUE
+        BRnz FAIL
+        LD R3, POINTER
+        ADD R4, R2, #0
+        JSR COMPARE
+        JSR DOT
+
+; ST writes memory and keeps the condition codes
+        LD R1, VALUE
+        AND R0, R0, #0
+        ST R1, SLOT
+        BRnp FAIL
+        LD R3, SLOT
+        ADD R4, R1, #0
+        JSR COMPARE
+        JSR DOT
+
+; STI writes through a pointer
+        LD R1, NEGATIVE
+        AND R0, R0, #0
+        STI R1, SLOT_POINTER
+        BRnp FAIL
+        LD R3, SLOT
+        ADD R4, R1, #0
+        JSR COMPARE
+        JSR DOT
+
+; STR with a negative offset
+        LEA R2, AFTER
+        AND R1, R1, #0
+        ADD R1, R1, #9
+        STR R1, R2, #-1
+        BRnz FAIL
+        LD R3, BEFORE
+        ADD R4, R1, #0
+        JSR COMPARE
+        JSR DOT
+
+        LEA R0, PASSED
+        PUTS
+        HALT
+
+FAIL    LEA R0, FAILED
+        PUTS
+        HALT
+
+; Fails unless R3 equals R4, clobbers R4
+COMPARE NOT R4, R4
+        ADD R4, R4, #1
+        ADD R4, R3, R4
+        BRnp FAIL
+        RET
+
+; Prints a dot, preserving R0 and R7
+DOT     ST R0, SAVE_R0
+        ST R7, SAVE_R7
+        LD R0, DOT_CHAR
+        OUT
+        LD R0, SAVE_R0
+        LD R7, SAVE_R7
+        RET
+
+SAVE_R0      .BLKW 1
+SAVE_R7      .BLKW 1
+DOT_CHAR     .FILL x002E
+NEGATIVE     .FILL x8000
+ZERO         .FILL #0
+VALUE        .FILL #1234
+POINTER      .FILL VALUE
+BEFORE       .FILL #5
+AFTER        .FILL #0
+SLOT         .BLKW 1
+SLOT_POINTER .FILL SLOT
+PASSED       .STRINGZ " PASS\n"
+FAILED       .STRINGZ " FAIL\n"
+
+        .END
@@ -0,0 +1,60 @@
+; Conformance checks for RTI in supervisor mode, which programs start in. RTI
+; pops the program counter and then the processor status off the stack in R6.
+; Prints a dot for every passing check followed by PASS, or stops with FAIL at
+; the first mismatch.
+
+        .ORIG x3000
+
+; Return to RESUME with positive condition codes
+        LEA R6, STACK
+        LEA R0, RESUME
+        STR R0, R6, #0
+        LD R0, STATUS
+        STR R0, R6, #1
+        AND R0, R0, #0
+        RTI
+        BRnzp FAIL
+RESUME  BRnz FAIL
+        JSR DOT
+
+; Both words were popped off the stack
+        LEA R3, STACK_END
+        ADD R4, R6, #0
+        JSR COMPARE
+        JSR DOT
+
+        LEA R0, PASSED
+        PUTS
+        HALT
+
+FAIL    LEA R0, FAILED
+        PUTS
+        HALT
+
+; Fails unless R3 equals R4, clobbers R4
+COMPARE NOT R4, R4
+        ADD R4, R4, #1
+        ADD R4, R3, R4
+        BRnp FAIL
+        RET
+
+; Prints a dot, preserving R0 and R7
+DOT     ST R0, SAVE_R0
+        ST R7, SAVE_R7
+        LD R0, DOT_CHAR
+        OUT
+        LD R0, SAVE_R0
+        LD R7, SAVE_R7
+        RET
+
+SAVE_R0   .BLKW 1
+SAVE_R7   .BLKW 1
+DOT_CHAR  .FILL x002E
+; Supervisor mode, priority 0, condition codes P
+STATUS    .FILL x0001
+STACK     .BLKW 2
+STACK_END .FILL #0
+PASSED    .STRINGZ " PASS\n"
+FAILED    .STRINGZ " FAIL\n"
+
+        .END
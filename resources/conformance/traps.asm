@@ -0,0 +1,33 @@
+; Conformance checks for the output trap routines OUT, PUTS and PUTSP. The
+; expected output is compared against traps.out.
+
+        .ORIG x3000
+
+        LD R0, LETTER
+        OUT
+        LD R0, NEWLINE
+        OUT
+
+        LEA R0, MESSAGE
+        PUTS
+
+; PUTSP prints the low byte of each word before the high byte and stops at
+; a zero word, so an odd length string ends with a zero high byte
+        LEA R0, PACKED
+        PUTSP
+
+        HALT
+
+LETTER  .FILL x0041
+NEWLINE .FILL x000A
+MESSAGE .STRINGZ "PUTS prints one character per word\n"
+PACKED  .FILL x5550
+        .FILL x5354
+        .FILL x2050
+        .FILL x6170
+        .FILL x6B63
+        .FILL x2173
+        .FILL x000A
+        .FILL x0000
+
+        .END
@@ -0,0 +1,583 @@
+use std::{collections::BTreeMap, fmt};
+
+pub type SymbolTable = BTreeMap<String, u16>;
+
+pub struct Program {
+    pub origin: u16,
+    pub words: Vec<u16>,
+    pub symbols: SymbolTable,
+    // Source line (starting at 1) that produced each word
+    pub lines: Vec<usize>,
+    // Whether each word is an instruction rather than data from a directive
+    pub code: Vec<bool>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AssembleError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Text(String),
+}
+
+struct Statement {
+    line: usize,
+    label: Option<String>,
+    mnemonic: Option<String>,
+    operands: Vec<Token>,
+}
+
+const MNEMONICS: [&str; 27] = [
+    "ADD", "AND", "NOT", "LD", "LDI", "LDR", "LEA", "ST", "STI", "STR", "JMP", "JSR", "JSRR",
+    "RET", "RTI", "TRAP", "GETC", "OUT", "PUTS", "IN", "PUTSP", "HALT", ".ORIG", ".FILL", ".BLKW",
+    ".STRINGZ", ".END",
+];
+
+impl Program {
+    // Object file layout: origin followed by the program words, all big-endian
+    pub fn to_object(&self) -> Vec<u8> {
+        let mut data: Vec<u8> = self.origin.to_be_bytes().to_vec();
+
+        for word in &self.words {
+            data.extend_from_slice(&word.to_be_bytes());
+        }
+
+        data
+    }
+
+    // Symbol file in the format written by the reference `lc3as`
+    pub fn symbol_file(&self) -> String {
+        let mut text: String = String::from(
+            "// Symbol table\n// Scope level 0:\n//\tSymbol Name       Page Address\n//\t----------------  ------------\n",
+        );
+
+        for (name, address) in &self.symbols {
+            text.push_str(&format!("//\t{:<16}  {:04X}\n", name, address));
+        }
+
+        text
+    }
+
+    // Address of the word generated by a source line, if any
+    pub fn address_of_line(&self, line: usize) -> Option<u16> {
+        self.lines
+            .iter()
+            .position(|word_line| *word_line == line)
+            .map(|index| self.origin.wrapping_add(index as u16))
+    }
+
+    // Source line that generated the word at an address, if any
+    pub fn line_of_address(&self, address: u16) -> Option<usize> {
+        self.lines
+            .get(address.wrapping_sub(self.origin) as usize)
+            .copied()
+    }
+}
+
+pub fn parse_symbol_file(text: &str) -> SymbolTable {
+    let mut symbols: SymbolTable = SymbolTable::new();
+
+    for line in text.lines() {
+        let fields: Vec<&str> = line.trim_start_matches('/').split_whitespace().collect();
+
+        if let [name, address] = fields.as_slice() {
+            if let Ok(address) = u16::from_str_radix(address, 16) {
+                symbols.insert(name.to_string(), address);
+            }
+        }
+    }
+
+    symbols
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, AssembleError> {
+    Err(AssembleError { line, message })
+}
+
+fn is_mnemonic(word: &str) -> bool {
+    let upper: String = word.to_uppercase();
+
+    MNEMONICS.contains(&upper.as_str()) || branch_flags(&upper).is_some()
+}
+
+// Condition flags of a BR mnemonic, plain BR branches unconditionally
+fn branch_flags(word: &str) -> Option<u16> {
+    let flags: &str = word.strip_prefix("BR")?;
+
+    match flags {
+        "" | "NZP" => Some(0x7),
+        "N" => Some(0x4),
+        "Z" => Some(0x2),
+        "P" => Some(0x1),
+        "NZ" => Some(0x6),
+        "NP" => Some(0x5),
+        "ZP" => Some(0x3),
+        _ => None,
+    }
+}
+
+fn tokenize(line: usize, text: &str) -> Result<Vec<Token>, AssembleError> {
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => break,
+            ',' => {}
+            c if c.is_whitespace() => {}
+            '"' => {
+                let mut value: String = String::new();
+
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some('r') => value.push('\r'),
+                            Some('e') => value.push('\x1B'),
+                            Some('0') => value.push('\0'),
+                            Some(other) => value.push(other),
+                            None => return error(line, String::from("unterminated string")),
+                        },
+                        Some(other) => value.push(other),
+                        None => return error(line, String::from("unterminated string")),
+                    }
+                }
+
+                tokens.push(Token::Text(value));
+            }
+            _ => {
+                let mut word: String = c.to_string();
+
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || next == ',' || next == ';' || next == '"' {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn parse_statement(line: usize, text: &str) -> Result<Option<Statement>, AssembleError> {
+    let mut tokens: Vec<Token> = tokenize(line, text)?;
+
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+
+    let mut label: Option<String> = None;
+
+    if let Token::Word(word) = &tokens[0] {
+        if !is_mnemonic(word) {
+            let name: &str = word.strip_suffix(':').unwrap_or(word);
+            let valid: bool = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+            if !valid {
+                return error(line, format!("invalid label '{}'", word));
+            }
+
+            label = Some(name.to_string());
+            tokens.remove(0);
+        }
+    }
+
+    let mnemonic: Option<String> = match tokens.first() {
+        Some(Token::Word(word)) if is_mnemonic(word) => Some(word.to_uppercase()),
+        Some(Token::Word(word)) => return error(line, format!("unknown instruction '{}'", word)),
+        Some(Token::Text(_)) => return error(line, String::from("unexpected string")),
+        None => None,
+    };
+
+    if mnemonic.is_some() {
+        tokens.remove(0);
+    }
+
+    Ok(Some(Statement {
+        line,
+        label,
+        mnemonic,
+        operands: tokens,
+    }))
+}
+
+pub fn parse_number(word: &str) -> Option<i32> {
+    let (negative, digits): (bool, &str) = match word.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, word),
+    };
+
+    let value: i32 = if let Some(decimal) = digits.strip_prefix('#') {
+        return parse_number(decimal).map(|value| if negative { -value } else { value });
+    } else if let Some(hex) = digits.strip_prefix(['x', 'X']) {
+        i32::from_str_radix(hex.strip_prefix('-').unwrap_or(hex), 16)
+            .ok()
+            .map(|value| if hex.starts_with('-') { -value } else { value })?
+    } else if let Some(binary) = digits.strip_prefix(['b', 'B']) {
+        i32::from_str_radix(binary, 2).ok()?
+    } else if digits.starts_with(|c: char| c.is_ascii_digit()) {
+        digits.parse::<i32>().ok()?
+    } else {
+        return None;
+    };
+
+    Some(if negative { -value } else { value })
+}
+
+// Number of words a statement occupies
+fn statement_size(statement: &Statement) -> Result<u16, AssembleError> {
+    match statement.mnemonic.as_deref() {
+        None | Some(".ORIG") | Some(".END") => Ok(0),
+        Some(".BLKW") => match statement.operands.first() {
+            Some(Token::Word(word)) => match parse_number(word) {
+                Some(count) if (0..=0xFFFF).contains(&count) => Ok(count as u16),
+                _ => error(statement.line, format!("invalid block size '{}'", word)),
+            },
+            _ => error(statement.line, String::from(".BLKW expects a size")),
+        },
+        Some(".STRINGZ") => match statement.operands.first() {
+            Some(Token::Text(text)) => Ok(text.chars().count() as u16 + 1),
+            _ => error(statement.line, String::from(".STRINGZ expects a string")),
+        },
+        Some(_) => Ok(1),
+    }
+}
+
+struct Encoder<'a> {
+    statement: &'a Statement,
+    address: u16,
+    symbols: &'a SymbolTable,
+}
+
+impl Encoder<'_> {
+    fn expect_operands(&self, count: usize) -> Result<(), AssembleError> {
+        match self.statement.operands.len() == count {
+            true => Ok(()),
+            false => error(
+                self.statement.line,
+                format!(
+                    "{} expects {} operand(s)",
+                    self.statement.mnemonic.as_deref().unwrap_or(""),
+                    count
+                ),
+            ),
+        }
+    }
+
+    fn word(&self, index: usize) -> Result<&str, AssembleError> {
+        match &self.statement.operands[index] {
+            Token::Word(word) => Ok(word),
+            Token::Text(_) => error(self.statement.line, String::from("unexpected string")),
+        }
+    }
+
+    fn register(&self, index: usize) -> Result<u16, AssembleError> {
+        let word: &str = self.word(index)?;
+
+        match word
+            .to_uppercase()
+            .strip_prefix('R')
+            .map(|n| n.parse::<u16>())
+        {
+            Some(Ok(register)) if register < 8 => Ok(register),
+            _ => error(
+                self.statement.line,
+                format!("expected register, found '{}'", word),
+            ),
+        }
+    }
+
+    fn immediate(&self, index: usize, bit_count: u32) -> Result<u16, AssembleError> {
+        let word: &str = self.word(index)?;
+        let min: i32 = -(1 << (bit_count - 1));
+        let max: i32 = (1 << (bit_count - 1)) - 1;
+
+        match parse_number(word) {
+            Some(value) if (min..=max).contains(&value) => {
+                Ok((value as u16) & ((1 << bit_count) - 1))
+            }
+            Some(_) => error(
+                self.statement.line,
+                format!("immediate '{}' does not fit in {} bits", word, bit_count),
+            ),
+            None => error(
+                self.statement.line,
+                format!("expected immediate, found '{}'", word),
+            ),
+        }
+    }
+
+    // Labels are converted to an offset from the incremented program counter
+    fn pc_offset(&self, index: usize, bit_count: u32) -> Result<u16, AssembleError> {
+        let word: &str = self.word(index)?;
+
+        let offset: i32 = match parse_number(word) {
+            Some(value) => value,
+            None => match self.symbols.get(word) {
+                Some(address) => *address as i32 - (self.address as i32 + 1),
+                None => return error(self.statement.line, format!("undefined label '{}'", word)),
+            },
+        };
+
+        let min: i32 = -(1 << (bit_count - 1));
+        let max: i32 = (1 << (bit_count - 1)) - 1;
+
+        match (min..=max).contains(&offset) {
+            true => Ok((offset as u16) & ((1 << bit_count) - 1)),
+            false => error(
+                self.statement.line,
+                format!("'{}' is out of range for a {} bit offset", word, bit_count),
+            ),
+        }
+    }
+
+    fn encode(&self) -> Result<Vec<u16>, AssembleError> {
+        let mnemonic: &str = self.statement.mnemonic.as_deref().unwrap_or("");
+
+        let operation: u16 = match mnemonic {
+            "ADD" | "AND" => {
+                self.expect_operands(3)?;
+                let op_code: u16 = if mnemonic == "ADD" { 0x1000 } else { 0x5000 };
+                let base: u16 = op_code | (self.register(0)? << 9) | (self.register(1)? << 6);
+
+                match self.register(2) {
+                    Ok(register) => base | register,
+                    Err(_) => base | 0x0020 | self.immediate(2, 5)?,
+                }
+            }
+            "NOT" => {
+                self.expect_operands(2)?;
+                0x903F | (self.register(0)? << 9) | (self.register(1)? << 6)
+            }
+            "LD" | "LDI" | "LEA" | "ST" | "STI" => {
+                self.expect_operands(2)?;
+                let op_code: u16 = match mnemonic {
+                    "LD" => 0x2000,
+                    "LDI" => 0xA000,
+                    "LEA" => 0xE000,
+                    "ST" => 0x3000,
+                    _ => 0xB000,
+                };
+
+                op_code | (self.register(0)? << 9) | self.pc_offset(1, 9)?
+            }
+            "LDR" | "STR" => {
+                self.expect_operands(3)?;
+                let op_code: u16 = if mnemonic == "LDR" { 0x6000 } else { 0x7000 };
+
+                op_code
+                    | (self.register(0)? << 9)
+                    | (self.register(1)? << 6)
+                    | self.immediate(2, 6)?
+            }
+            "JMP" => {
+                self.expect_operands(1)?;
+                0xC000 | (self.register(0)? << 6)
+            }
+            "RET" => {
+                self.expect_operands(0)?;
+                0xC1C0
+            }
+            "JSR" => {
+                self.expect_operands(1)?;
+                0x4800 | self.pc_offset(0, 11)?
+            }
+            "JSRR" => {
+                self.expect_operands(1)?;
+                0x4000 | (self.register(0)? << 6)
+            }
+            "RTI" => {
+                self.expect_operands(0)?;
+                0x8000
+            }
+            "TRAP" => {
+                self.expect_operands(1)?;
+                let word: &str = self.word(0)?;
+
+                match parse_number(word) {
+                    Some(vector) if (0..=0xFF).contains(&vector) => 0xF000 | vector as u16,
+                    _ => {
+                        return error(
+                            self.statement.line,
+                            format!("invalid trap vector '{}'", word),
+                        )
+                    }
+                }
+            }
+            "GETC" | "OUT" | "PUTS" | "IN" | "PUTSP" | "HALT" => {
+                self.expect_operands(0)?;
+                let vector: u16 = match mnemonic {
+                    "GETC" => 0x20,
+                    "OUT" => 0x21,
+                    "PUTS" => 0x22,
+                    "IN" => 0x23,
+                    "PUTSP" => 0x24,
+                    _ => 0x25,
+                };
+
+                0xF000 | vector
+            }
+            ".FILL" => {
+                self.expect_operands(1)?;
+                let word: &str = self.word(0)?;
+
+                match (parse_number(word), self.symbols.get(word)) {
+                    (Some(value), _) if (-0x8000..=0xFFFF).contains(&value) => value as u16,
+                    (None, Some(address)) => *address,
+                    _ => {
+                        return error(
+                            self.statement.line,
+                            format!("invalid .FILL value '{}'", word),
+                        )
+                    }
+                }
+            }
+            ".BLKW" => {
+                return Ok(vec![0; statement_size(self.statement)? as usize]);
+            }
+            ".STRINGZ" => {
+                self.expect_operands(1)?;
+                let mut words: Vec<u16> = Vec::new();
+
+                if let Token::Text(text) = &self.statement.operands[0] {
+                    words.extend(text.chars().map(|c| c as u16));
+                }
+                words.push(0);
+
+                return Ok(words);
+            }
+            branch => match branch_flags(branch) {
+                Some(flags) => {
+                    self.expect_operands(1)?;
+                    (flags << 9) | self.pc_offset(0, 9)?
+                }
+                None => {
+                    return error(
+                        self.statement.line,
+                        format!("unknown instruction '{}'", branch),
+                    )
+                }
+            },
+        };
+
+        Ok(vec![operation])
+    }
+}
+
+pub fn assemble(source: &str) -> Result<Program, AssembleError> {
+    let mut statements: Vec<Statement> = Vec::new();
+
+    for (index, text) in source.lines().enumerate() {
+        if let Some(statement) = parse_statement(index + 1, text)? {
+            let end: bool = statement.mnemonic.as_deref() == Some(".END");
+            statements.push(statement);
+
+            if end {
+                break;
+            }
+        }
+    }
+
+    // First pass assigns an address to every label
+    let mut origin: Option<u16> = None;
+    let mut address: u16 = 0;
+    let mut symbols: SymbolTable = SymbolTable::new();
+
+    for statement in &statements {
+        if statement.mnemonic.as_deref() == Some(".ORIG") {
+            if origin.is_some() {
+                return error(statement.line, String::from("only one .ORIG is supported"));
+            }
+
+            let value: Option<i32> = match statement.operands.first() {
+                Some(Token::Word(word)) => parse_number(word),
+                _ => None,
+            };
+
+            match value {
+                Some(value) if (0..=0xFFFF).contains(&value) => {
+                    origin = Some(value as u16);
+                    address = value as u16;
+                }
+                _ => return error(statement.line, String::from(".ORIG expects an address")),
+            }
+            continue;
+        }
+
+        if origin.is_none() && (statement.mnemonic.is_some() || statement.label.is_some()) {
+            return error(
+                statement.line,
+                String::from("expected .ORIG before any code"),
+            );
+        }
+
+        if let Some(label) = &statement.label {
+            if symbols.insert(label.clone(), address).is_some() {
+                return error(statement.line, format!("duplicate label '{}'", label));
+            }
+        }
+
+        address = address.wrapping_add(statement_size(statement)?);
+    }
+
+    let origin: u16 = match origin {
+        Some(origin) => origin,
+        None => return error(1, String::from("missing .ORIG")),
+    };
+
+    // Second pass encodes each statement now that every label is known
+    let mut words: Vec<u16> = Vec::new();
+    let mut lines: Vec<usize> = Vec::new();
+    let mut code: Vec<bool> = Vec::new();
+
+    for statement in &statements {
+        match statement.mnemonic.as_deref() {
+            None | Some(".ORIG") | Some(".END") => continue,
+            _ => {}
+        }
+
+        let encoder: Encoder = Encoder {
+            statement,
+            address: origin.wrapping_add(words.len() as u16),
+            symbols: &symbols,
+        };
+
+        let instruction: bool = !statement.mnemonic.as_deref().unwrap_or("").starts_with('.');
+
+        for word in encoder.encode()? {
+            words.push(word);
+            lines.push(statement.line);
+            code.push(instruction);
+        }
+    }
+
+    Ok(Program {
+        origin,
+        words,
+        symbols,
+        lines,
+        code,
+    })
+}
+
+#[cfg(test)]
+#[path = "./assembler_test.rs"]
+mod assembler_test;
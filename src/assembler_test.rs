@@ -0,0 +1,114 @@
+use crate::assembler::{assemble, parse_number, parse_symbol_file, AssembleError, Program};
+
+#[test]
+fn test_assemble_hello_world() {
+    let source: &str = "
+        .ORIG x3000
+        LEA R0, HELLO   ; Load the string address
+        PUTS
+        HALT
+HELLO   .STRINGZ \"Hi\"
+        .END
+    ";
+    let program: Program = assemble(source).unwrap();
+
+    assert_eq!(program.origin, 0x3000);
+    assert_eq!(program.words, vec![0xE002, 0xF022, 0xF025, 0x48, 0x69, 0x0]);
+    assert_eq!(program.symbols.get("HELLO"), Some(&0x3003));
+    assert_eq!(
+        program.to_object(),
+        vec![0x30, 0x00, 0xE0, 0x02, 0xF0, 0x22, 0xF0, 0x25, 0x00, 0x48, 0x00, 0x69, 0x00, 0x00]
+    );
+}
+
+#[test]
+fn test_assemble_operate() {
+    let program: Program =
+        assemble(".ORIG x3000\nADD R1, R0, R2\nADD R1, R0, #-1\nAND R1, R0, x0B\nNOT R1, R0\n.END")
+            .unwrap();
+
+    assert_eq!(
+        program.words,
+        vec![
+            0b0001_0010_0000_0010,
+            0b0001_0010_0011_1111,
+            0b0101_0010_0010_1011,
+            0b1001_0010_0011_1111
+        ]
+    );
+}
+
+#[test]
+fn test_assemble_control() {
+    let source: &str = "
+        .ORIG x3000
+LOOP    BRnz LOOP
+        BR DONE
+        JSR LOOP
+        JSRR R3
+        JMP R2
+        RET
+        RTI
+DONE    TRAP x25
+        .FILL LOOP
+        .BLKW 2
+        .END
+    ";
+    let program: Program = assemble(source).unwrap();
+
+    assert_eq!(
+        program.words,
+        vec![0x0DFF, 0x0E05, 0x4FFD, 0x40C0, 0xC080, 0xC1C0, 0x8000, 0xF025, 0x3000, 0x0, 0x0]
+    );
+    assert_eq!(program.lines[0], 3);
+    assert_eq!(program.address_of_line(10), Some(0x3007));
+    assert_eq!(program.line_of_address(0x3001), Some(4));
+    assert!(program.code[7]);
+    assert_eq!(program.code[8..], [false, false, false]);
+}
+
+#[test]
+fn test_assemble_errors() {
+    assert_eq!(
+        assemble("ADD R1, R1, #1").err(),
+        Some(AssembleError {
+            line: 1,
+            message: String::from("expected .ORIG before any code")
+        })
+    );
+    assert_eq!(
+        assemble(".ORIG x3000\nADD R1, R1, #16\n.END")
+            .err()
+            .unwrap()
+            .line,
+        2
+    );
+    assert_eq!(
+        assemble(".ORIG x3000\nBR NOWHERE\n.END")
+            .err()
+            .unwrap()
+            .message,
+        "undefined label 'NOWHERE'"
+    );
+    assert!(assemble(".ORIG x3000\nFOO\nFOO\n.END").is_err());
+    assert!(assemble(".ORIG x3000\nMUL R1, R2\n.END").is_err());
+}
+
+#[test]
+fn test_parse_number() {
+    assert_eq!(parse_number("#10"), Some(10));
+    assert_eq!(parse_number("#-10"), Some(-10));
+    assert_eq!(parse_number("x1F"), Some(31));
+    assert_eq!(parse_number("x-1"), Some(-1));
+    assert_eq!(parse_number("b101"), Some(5));
+    assert_eq!(parse_number("LOOP"), None);
+}
+
+#[test]
+fn test_symbol_file_round_trip() {
+    let program: Program = assemble(".ORIG x3000\nSTART HALT\nDATA .FILL #1\n.END").unwrap();
+    let symbols = parse_symbol_file(&program.symbol_file());
+
+    assert_eq!(symbols, program.symbols);
+    assert_eq!(symbols.get("DATA"), Some(&0x3001));
+}
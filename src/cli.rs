@@ -0,0 +1,748 @@
+use std::{
+    cell::RefCell,
+    fmt, fs,
+    io::{self, Write},
+    path::Path,
+    rc::Rc,
+};
+
+use lc_3::{
+    assembler::{assemble, parse_symbol_file, Program, SymbolTable},
+    console::{BufferedConsole, Console, StdConsole},
+    coverage::Coverage,
+    cpu::{RunState, CPU},
+    disassembler::{disassemble, disassemble_with_symbols, label_at},
+    disk::Disk,
+    dump::{dump, load, DumpFormat},
+    image::object_image,
+    profiler::Profiler,
+    screen::ScreenConsole,
+    script::{parse_script, ScriptRecorder, ScriptedConsole},
+    semihost::Semihost,
+    uart::Uart,
+    video::Video,
+};
+
+use crate::{
+    dap,
+    debugger::{parse_value, Debugger},
+    tui::Tui,
+};
+
+pub const USAGE: &str = "\
+An LC-3 virtual machine
+
+Usage: lc_3 <COMMAND> <FILE> [OPTIONS]
+       lc_3 <FILE.obj>             (same as `lc_3 run <FILE.obj>`)
+       lc_3 dap                    (the program comes with the editor's launch request)
+
+Commands:
+  run        Execute a program (.obj, .hex, .bin or .asm)
+  debug      Execute a program under the interactive debugger
+  tui        Execute a program under the full-screen debugger
+  assemble   Assemble a .asm source into .obj and .sym files
+  disasm     Print the disassembly of a program
+  test       Run a program headless and check that it halts with the expected output
+  trace      Execute a program and log every instruction
+  profile    Execute a program and report hot spots, op codes, subroutines and traps
+  coverage   Execute a program and report which lines and branch outcomes were exercised
+  dap        Serve the Debug Adapter Protocol on stdin and stdout for editors
+
+Options:
+  -e, --entry <ADDR>         Start executing at ADDR instead of the image origin
+  -l, --limit <COUNT>        Stop after COUNT instructions
+  -i, --input <FILE>         Read keyboard input from FILE instead of the terminal
+  -s, --script <FILE>        Feed keyboard input from an input script that times each key by
+                             instruction count or keyboard polls; `test` also passes when the
+                             program waits for more input after the script is used up
+  -r, --record <FILE>        Record every key the program reads, and when, as an input script
+                             that replays the session exactly with --script
+      --screen <FILE>        Render output on a virtual 80x25 terminal and write a text frame
+                             of it to FILE after each instruction that writes output
+  -t, --trace-output <FILE>  Write the instruction trace to FILE (default stderr)
+      --os <FILE>            Load an OS image and route traps through its vector table
+      --permissive           Let user mode programs access system space and the I/O page
+      --load <FILE[@ADDR]>   Load a memory dump over the program before it runs, raw dumps at ADDR
+      --dump <FILE>          Save memory to FILE once the program stopped: .obj with its origin,
+                             .hex as text with addresses that runs as an image again,
+                             anything else raw big-endian words
+      --range <START>:<END>  Memory saved by --dump (default x0000:xFFFF)
+      --video <FILE>         Save the PennSim video memory at xC000 as a PPM image once the
+                             program stopped
+      --video-every <COUNT>  Also save a numbered frame every COUNT instructions
+      --disk <FILE>          Attach FILE as the block storage device, created if missing
+      --uart <LINE>          Bridge the serial port to connect:SOCKET, listen:SOCKET or pty
+      --semihost <DIR>       Serve host file I/O, time and exit traps x30-x35, files in DIR only
+  -o, --output <FILE>        Object file written by `assemble` (default FILE.obj),
+                             or report file written by `profile` and `coverage` (default stdout)
+  -x, --expect <FILE>        Expected console output for `test`
+      --lcov <FILE>          Also write `coverage` results as an lcov tracefile (.asm only)
+  -h, --help                 Print this help
+
+Exit codes:
+  0  success
+  1  the program did not halt, or `test` output did not match
+  2  invalid command line
+  3  a file could not be read, written, loaded or assembled
+  N  the nonzero status a program passed to the semihosting exit trap, 255 for anything
+     larger. Statuses 1 to 3 look the same as the failures above";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Command {
+    #[default]
+    Run,
+    Debug,
+    Tui,
+    Assemble,
+    Disasm,
+    Test,
+    Trace,
+    Profile,
+    Coverage,
+    Dap,
+    Help,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Options {
+    pub command: Command,
+    pub file: String,
+    pub entry: Option<u16>,
+    pub limit: Option<u64>,
+    pub input: Option<String>,
+    pub script: Option<String>,
+    pub record: Option<String>,
+    pub screen: Option<String>,
+    pub dump: Option<String>,
+    pub range: Option<(u16, u16)>,
+    pub load: Option<String>,
+    pub video: Option<String>,
+    pub video_every: Option<u64>,
+    pub disk: Option<String>,
+    pub uart: Option<String>,
+    pub semihost: Option<String>,
+    pub trace_output: Option<String>,
+    pub os_image: Option<String>,
+    pub permissive: bool,
+    pub output: Option<String>,
+    pub expected: Option<String>,
+    pub lcov: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CliError {
+    Usage(String),
+    Failure(String),
+    File(String),
+    // The program exited through the semihosting exit trap with a nonzero status
+    Exit(u16),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            CliError::Failure(_) => 1,
+            CliError::Usage(_) => 2,
+            CliError::File(_) => 3,
+            // Saturated so large statuses never wrap around to success
+            CliError::Exit(status) => (*status).min(u8::MAX as u16) as u8,
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) | CliError::Failure(message) | CliError::File(message) => {
+                write!(f, "{}", message)
+            }
+            CliError::Exit(status) => write!(f, "program exited with status {}", status),
+        }
+    }
+}
+
+impl Options {
+    // Interactive runs read single key presses straight from the terminal
+    pub fn raw_terminal(&self) -> bool {
+        matches!(
+            self.command,
+            Command::Run | Command::Trace | Command::Profile | Command::Coverage
+        ) && self.input.is_none()
+            && self.script.is_none()
+            // The full-screen debugger takes the keys itself, the program gets what is typed for it
+            || self.command == Command::Tui
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Options, CliError> {
+    let mut options: Options = Options::default();
+    let mut arguments = args.iter();
+    let mut file: Option<String> = None;
+
+    let command: Option<Command> = match args.first().map(String::as_str) {
+        Some("run") => Some(Command::Run),
+        Some("debug") => Some(Command::Debug),
+        Some("tui") => Some(Command::Tui),
+        Some("assemble") => Some(Command::Assemble),
+        Some("disasm") => Some(Command::Disasm),
+        Some("test") => Some(Command::Test),
+        Some("trace") => Some(Command::Trace),
+        Some("profile") => Some(Command::Profile),
+        Some("coverage") => Some(Command::Coverage),
+        Some("dap") => Some(Command::Dap),
+        Some("help") => Some(Command::Help),
+        _ => None,
+    };
+
+    if let Some(command) = command {
+        options.command = command;
+        arguments.next();
+    }
+
+    while let Some(argument) = arguments.next() {
+        let mut value = |name: &str| -> Result<String, CliError> {
+            arguments
+                .next()
+                .cloned()
+                .ok_or_else(|| CliError::Usage(format!("{} expects a value", name)))
+        };
+
+        match argument.as_str() {
+            "-h" | "--help" => options.command = Command::Help,
+            "-e" | "--entry" => {
+                let entry: String = value(argument)?;
+                options.entry =
+                    Some(parse_value(&entry, &SymbolTable::new()).ok_or_else(|| {
+                        CliError::Usage(format!("invalid entry point '{}'", entry))
+                    })?);
+            }
+            "-l" | "--limit" => {
+                let limit: String = value(argument)?;
+                options.limit = Some(limit.parse::<u64>().map_err(|_| {
+                    CliError::Usage(format!("invalid instruction limit '{}'", limit))
+                })?);
+            }
+            "-i" | "--input" => options.input = Some(value(argument)?),
+            "-s" | "--script" => options.script = Some(value(argument)?),
+            "-r" | "--record" => options.record = Some(value(argument)?),
+            "--screen" => options.screen = Some(value(argument)?),
+            "--dump" => options.dump = Some(value(argument)?),
+            "--range" => {
+                let range: String = value(argument)?;
+                let invalid = || CliError::Usage(format!("invalid memory range '{}'", range));
+                let (start, end) = range.split_once(':').ok_or_else(invalid)?;
+
+                match (
+                    parse_value(start, &SymbolTable::new()),
+                    parse_value(end, &SymbolTable::new()),
+                ) {
+                    (Some(start), Some(end)) if start <= end => options.range = Some((start, end)),
+                    _ => return Err(invalid()),
+                }
+            }
+            "--load" => options.load = Some(value(argument)?),
+            "--video" => options.video = Some(value(argument)?),
+            "--disk" => options.disk = Some(value(argument)?),
+            "--semihost" => options.semihost = Some(value(argument)?),
+            "--uart" => {
+                let line: String = value(argument)?;
+
+                if line != "pty" && !line.starts_with("connect:") && !line.starts_with("listen:") {
+                    return Err(CliError::Usage(format!("invalid serial line '{}'", line)));
+                }
+                options.uart = Some(line);
+            }
+            "--video-every" => {
+                let every: String = value(argument)?;
+                options.video_every = match every.parse::<u64>() {
+                    Ok(every) if every > 0 => Some(every),
+                    _ => {
+                        return Err(CliError::Usage(format!(
+                            "invalid frame interval '{}'",
+                            every
+                        )))
+                    }
+                };
+            }
+            "-t" | "--trace-output" => options.trace_output = Some(value(argument)?),
+            "--os" => options.os_image = Some(value(argument)?),
+            "--permissive" => options.permissive = true,
+            "-o" | "--output" => options.output = Some(value(argument)?),
+            "-x" | "--expect" => options.expected = Some(value(argument)?),
+            "--lcov" => options.lcov = Some(value(argument)?),
+            flag if flag.starts_with('-') => {
+                return Err(CliError::Usage(format!("unknown option '{}'", flag)))
+            }
+            path => match file {
+                None => file = Some(path.to_string()),
+                Some(_) => return Err(CliError::Usage(format!("unexpected argument '{}'", path))),
+            },
+        }
+    }
+
+    if options.input.is_some() && options.script.is_some() {
+        return Err(CliError::Usage(String::from(
+            "--input and --script cannot be combined",
+        )));
+    }
+
+    match (options.command, file) {
+        (Command::Help, _) => {}
+        (Command::Dap, Some(file)) => {
+            return Err(CliError::Usage(format!(
+                "unexpected argument '{}', the editor names the program",
+                file
+            )))
+        }
+        (Command::Dap, None) => {}
+        (_, Some(file)) => options.file = file,
+        (_, None) => return Err(CliError::Usage(String::from("no program file given"))),
+    }
+
+    Ok(options)
+}
+
+fn read_file(path: &str) -> Result<Vec<u8>, CliError> {
+    fs::read(path).map_err(|error| CliError::File(format!("cannot read '{}': {}", path, error)))
+}
+
+fn write_file(path: &str, data: &[u8]) -> Result<(), CliError> {
+    fs::write(path, data)
+        .map_err(|error| CliError::File(format!("cannot write '{}': {}", path, error)))
+}
+
+fn assemble_file(path: &str) -> Result<Program, CliError> {
+    let source: Vec<u8> = read_file(path)?;
+
+    assemble(&String::from_utf8_lossy(&source))
+        .map_err(|error| CliError::File(format!("{}: {}", path, error)))
+}
+
+// Object image of a program, assembling sources on the fly, plus any known symbols
+pub fn load_program(path: &str) -> Result<(Vec<u8>, SymbolTable), CliError> {
+    if path.ends_with(".asm") {
+        let program: Program = assemble_file(path)?;
+        return Ok((program.to_object(), program.symbols));
+    }
+
+    let image: Vec<u8> = object_image(&read_file(path)?)
+        .map_err(|error| CliError::File(format!("{}: {}", path, error)))?;
+    let symbol_path = Path::new(path).with_extension("sym");
+    let symbols: SymbolTable = match fs::read_to_string(symbol_path) {
+        Ok(text) => parse_symbol_file(&text),
+        Err(_) => SymbolTable::new(),
+    };
+
+    Ok((image, symbols))
+}
+
+struct Machine {
+    cpu: CPU,
+    symbols: SymbolTable,
+    output: Option<Rc<RefCell<Vec<u8>>>>,
+    // Location of the program image in memory
+    origin: u16,
+    length: u16,
+}
+
+fn build_machine(options: &Options) -> Result<Machine, CliError> {
+    let mut output: Option<Rc<RefCell<Vec<u8>>>> = None;
+
+    let script: Option<ScriptedConsole> = match &options.script {
+        Some(path) => {
+            let text: String = String::from_utf8_lossy(&read_file(path)?).into_owned();
+            let events = parse_script(&text)
+                .map_err(|error| CliError::File(format!("{}: {}", path, error)))?;
+            Some(ScriptedConsole::new(events))
+        }
+        None => None,
+    };
+
+    let mut console: Box<dyn Console> = match (script, &options.input, options.command) {
+        (Some(console), _, Command::Test) => {
+            output = Some(console.output());
+            Box::new(console)
+        }
+        (Some(console), _, _) => Box::new(console.with_echo()),
+        (None, input, Command::Test) => {
+            let data: Vec<u8> = match input {
+                Some(path) => read_file(path)?,
+                None => Vec::new(),
+            };
+            let console: BufferedConsole = BufferedConsole::new(&data);
+            output = Some(console.output());
+            Box::new(console)
+        }
+        (None, Some(path), _) => Box::new(BufferedConsole::new(&read_file(path)?).with_echo()),
+        (None, None, _) => Box::new(StdConsole),
+    };
+
+    if let Some(path) = &options.screen {
+        let frames: fs::File = fs::File::create(path)
+            .map_err(|error| CliError::File(format!("cannot write '{}': {}", path, error)))?;
+        console = Box::new(ScreenConsole::new(console).with_frames(Box::new(frames)));
+    }
+
+    let mut cpu: CPU = CPU::with_console(console);
+
+    cpu.set_access_control(!options.permissive);
+
+    if let Some(path) = &options.disk {
+        let disk: Disk = Disk::open(path)
+            .map_err(|error| CliError::File(format!("cannot open '{}': {}", path, error)))?;
+        cpu.memory_mut().set_disk(disk);
+    }
+
+    if let Some(line) = &options.uart {
+        let uart: Uart = match line.split_once(':') {
+            Some(("connect", path)) => Uart::connect(path),
+            Some(("listen", path)) => {
+                eprintln!("Waiting for a connection on {}", path);
+                Uart::listen(path)
+            }
+            _ => Uart::pty().map(|(uart, path)| {
+                eprintln!("Serial port on {}", path);
+                uart
+            }),
+        }
+        .map_err(|error| {
+            CliError::File(format!("cannot open serial line '{}': {}", line, error))
+        })?;
+        cpu.memory_mut().set_uart(uart);
+    }
+
+    if let Some(directory) = &options.semihost {
+        cpu.set_semihost(Semihost::new(directory));
+    }
+
+    if let Some(path) = &options.record {
+        let mut file: fs::File = fs::File::create(path)
+            .map_err(|error| CliError::File(format!("cannot write '{}': {}", path, error)))?;
+        writeln!(file, "; Session recorded from {}", options.file)
+            .map_err(|error| CliError::File(format!("cannot write '{}': {}", path, error)))?;
+        cpu.memory_mut()
+            .set_recorder(Box::new(ScriptRecorder::new(file)));
+    }
+
+    if let Some(path) = &options.os_image {
+        cpu.load_os_image(&read_file(path)?)
+            .map_err(|error| CliError::File(format!("{}: {}", path, error)))?;
+    }
+
+    let (image, symbols) = load_program(&options.file)?;
+
+    let origin: u16 = cpu
+        .load_image(&image)
+        .map_err(|error| CliError::File(format!("{}: {}", options.file, error)))?;
+
+    // Dumps are loaded over the program so they can patch its data
+    if let Some(argument) = &options.load {
+        let (path, address) = match argument.rsplit_once('@') {
+            Some((path, address)) => match parse_value(address, &symbols) {
+                Some(address) => (path, Some(address)),
+                None => {
+                    return Err(CliError::Usage(format!(
+                        "invalid load address '{}'",
+                        address
+                    )))
+                }
+            },
+            None => (argument.as_str(), None),
+        };
+
+        load(
+            cpu.memory_mut(),
+            &read_file(path)?,
+            DumpFormat::from_path(path),
+            address,
+        )
+        .map_err(|error| CliError::File(format!("{}: {}", path, error)))?;
+    }
+
+    if let Some(entry) = options.entry {
+        cpu.set_program_counter(entry);
+    }
+
+    Ok(Machine {
+        cpu,
+        symbols,
+        output,
+        origin,
+        length: (image.len() / 2).saturating_sub(1) as u16,
+    })
+}
+
+fn check_stopped(cpu: &CPU) -> Result<(), CliError> {
+    match cpu.state() {
+        RunState::Halted => match cpu.exit_status() {
+            Some(status) if status != 0 => Err(CliError::Exit(status)),
+            _ => Ok(()),
+        },
+        RunState::InputExhausted => Err(CliError::Failure(format!(
+            "program ran out of input after {} instructions",
+            cpu.instruction_count()
+        ))),
+        RunState::Fault(exception) => Err(CliError::Failure(format!(
+            "{} at x{:04X} after {} instructions",
+            exception,
+            cpu.program_counter(),
+            cpu.instruction_count()
+        ))),
+        RunState::Running => Err(CliError::Failure(format!(
+            "instruction limit reached after {} instructions",
+            cpu.instruction_count()
+        ))),
+    }
+}
+
+pub fn trace_line(cpu: &CPU, address: u16, operation: u16) -> String {
+    let mut line: String = format!(
+        "x{:04X}  x{:04X}  {:<20}",
+        address,
+        operation,
+        disassemble(address, operation)
+    );
+
+    for (index, value) in cpu.registers().iter().enumerate() {
+        line.push_str(&format!(" R{}=x{:04X}", index, value));
+    }
+
+    line.push_str(&format!(" PSR=x{:04X}", cpu.processor_status_register()));
+    line
+}
+
+fn run_traced(cpu: &mut CPU, limit: Option<u64>, out: &mut dyn Write) -> Result<(), CliError> {
+    let mut executed: u64 = 0;
+
+    while cpu.state() == RunState::Running && limit.is_none_or(|limit| executed < limit) {
+        let address: u16 = cpu.program_counter();
+        let operation: u16 = cpu.memory().peek(address);
+
+        cpu.step();
+        executed += 1;
+
+        writeln!(out, "{}", trace_line(cpu, address, operation))
+            .map_err(|error| CliError::File(format!("cannot write trace: {}", error)))?;
+    }
+
+    cpu.memory_mut().console().flush();
+    Ok(())
+}
+
+fn execute_machine(options: &Options, machine: &mut Machine) -> Result<(), CliError> {
+    let cpu: &mut CPU = &mut machine.cpu;
+
+    match (&options.trace_output, options.command) {
+        (Some(path), _) => {
+            let file = fs::File::create(path)
+                .map_err(|error| CliError::File(format!("cannot create '{}': {}", path, error)))?;
+            run_traced(cpu, options.limit, &mut io::BufWriter::new(file))
+        }
+        (None, Command::Trace) => run_traced(cpu, options.limit, &mut io::stderr()),
+        (None, _) => match &options.video {
+            Some(path) => {
+                let mut video: Video = Video::new(path, options.video_every);
+
+                video.run(cpu, options.limit).map_err(|error| {
+                    CliError::File(format!("cannot write '{}': {}", path, error))
+                })?;
+                Ok(())
+            }
+            None => {
+                cpu.run(options.limit);
+                Ok(())
+            }
+        },
+    }?;
+
+    dump_memory(options, cpu)
+}
+
+// Saves the requested memory range once the program has run
+fn dump_memory(options: &Options, cpu: &CPU) -> Result<(), CliError> {
+    let Some(path) = &options.dump else {
+        return Ok(());
+    };
+    let (start, end) = options.range.unwrap_or((0x0000, 0xFFFF));
+
+    write_file(
+        path,
+        &dump(cpu.memory(), start, end, DumpFormat::from_path(path)),
+    )
+}
+
+pub fn execute(options: &Options) -> Result<(), CliError> {
+    match options.command {
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        Command::Assemble => {
+            let program: Program = assemble_file(&options.file)?;
+            let object_path: String = match &options.output {
+                Some(path) => path.clone(),
+                None => Path::new(&options.file)
+                    .with_extension("obj")
+                    .to_string_lossy()
+                    .into_owned(),
+            };
+            let symbol_path = Path::new(&object_path).with_extension("sym");
+
+            write_file(&object_path, &program.to_object())?;
+            write_file(
+                &symbol_path.to_string_lossy(),
+                program.symbol_file().as_bytes(),
+            )?;
+            println!(
+                "Assembled {} words into {}",
+                program.words.len(),
+                object_path
+            );
+            Ok(())
+        }
+        Command::Disasm => {
+            let (image, symbols) = load_program(&options.file)?;
+
+            if image.len() < 2 || !image.len().is_multiple_of(2) {
+                return Err(CliError::File(format!(
+                    "{}: not an object image",
+                    options.file
+                )));
+            }
+
+            let origin: u16 = u16::from_be_bytes([image[0], image[1]]);
+
+            for (index, chunk) in image[2..].chunks(2).enumerate() {
+                let address: u16 = origin.wrapping_add(index as u16);
+                let operation: u16 = u16::from_be_bytes([chunk[0], chunk[1]]);
+                let label: &str = label_at(&symbols, address).unwrap_or("");
+
+                println!(
+                    "x{:04X}  x{:04X}  {:<12}{}",
+                    address,
+                    operation,
+                    label,
+                    disassemble_with_symbols(address, operation, &symbols)
+                );
+            }
+
+            Ok(())
+        }
+        Command::Debug => {
+            let machine: Machine = build_machine(options)?;
+            let mut debugger: Debugger = Debugger::new(machine.cpu, machine.symbols);
+
+            debugger
+                .session(&mut io::stdin().lock(), &mut io::stdout())
+                .map_err(|error| CliError::File(format!("debugger I/O failed: {}", error)))
+        }
+        Command::Dap => dap::serve(io::BufReader::new(io::stdin()), Box::new(io::stdout()))
+            .map_err(|error| CliError::File(format!("debug adapter I/O failed: {}", error))),
+        Command::Tui => {
+            let machine: Machine = build_machine(options)?;
+            let mut tui: Tui = Tui::new(machine.cpu, machine.symbols, &options.file);
+
+            tui.session()
+                .map_err(|error| CliError::File(format!("debugger I/O failed: {}", error)))
+        }
+        Command::Run | Command::Trace => {
+            let mut machine: Machine = build_machine(options)?;
+
+            if options.command == Command::Run {
+                println!("Attempting to execute program: {}", options.file);
+            }
+
+            execute_machine(options, &mut machine)?;
+            check_stopped(&machine.cpu)
+        }
+        Command::Profile => {
+            let mut machine: Machine = build_machine(options)?;
+            let mut profiler: Profiler = Profiler::new();
+
+            profiler.run(&mut machine.cpu, options.limit);
+            dump_memory(options, &machine.cpu)?;
+
+            let report: String = profiler.report(machine.cpu.memory(), &machine.symbols);
+
+            match &options.output {
+                Some(path) => write_file(path, report.as_bytes())?,
+                None => print!("\n{}", report),
+            }
+
+            check_stopped(&machine.cpu)
+        }
+        Command::Coverage => {
+            let mut machine: Machine = build_machine(options)?;
+            let mut coverage: Coverage = Coverage::new();
+
+            coverage.run(&mut machine.cpu, options.limit);
+            dump_memory(options, &machine.cpu)?;
+
+            let report: String = match options.file.ends_with(".asm") {
+                true => {
+                    let source: Vec<u8> = read_file(&options.file)?;
+                    let program: Program = assemble_file(&options.file)?;
+
+                    if let Some(path) = &options.lcov {
+                        write_file(path, coverage.lcov(&program, &options.file).as_bytes())?;
+                    }
+
+                    coverage.source_report(&program, &String::from_utf8_lossy(&source))
+                }
+                false if options.lcov.is_some() => {
+                    return Err(CliError::Usage(String::from(
+                        "--lcov needs the program's .asm source",
+                    )))
+                }
+                false => coverage.image_report(
+                    machine.cpu.memory(),
+                    machine.origin,
+                    machine.length,
+                    &machine.symbols,
+                ),
+            };
+
+            match &options.output {
+                Some(path) => write_file(path, report.as_bytes())?,
+                None => print!("\n{}", report),
+            }
+
+            check_stopped(&machine.cpu)
+        }
+        Command::Test => {
+            let mut machine: Machine = build_machine(options)?;
+
+            execute_machine(options, &mut machine)?;
+
+            // A scripted session ends once the program wants more input than the script has
+            if options.script.is_none() || machine.cpu.state() != RunState::InputExhausted {
+                check_stopped(&machine.cpu)?;
+            }
+
+            if let (Some(path), Some(output)) = (&options.expected, &machine.output) {
+                let expected: Vec<u8> = read_file(path)?;
+                let actual = output.borrow();
+
+                if *actual != expected {
+                    return Err(CliError::Failure(format!(
+                        "output did not match '{}'\n--- expected\n{}\n--- actual\n{}",
+                        path,
+                        String::from_utf8_lossy(&expected),
+                        String::from_utf8_lossy(&actual)
+                    )));
+                }
+            }
+
+            println!(
+                "PASS {} ({} instructions)",
+                options.file,
+                machine.cpu.instruction_count()
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./cli_test.rs"]
+mod cli_test;
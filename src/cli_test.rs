@@ -0,0 +1,263 @@
+use crate::cli::{execute, parse_args, CliError, Command, Options};
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(String::from).collect()
+}
+
+#[test]
+fn test_parse_bare_file_runs() {
+    let options: Options = parse_args(&args("resources/2048.obj")).unwrap();
+
+    assert_eq!(options.command, Command::Run);
+    assert_eq!(options.file, "resources/2048.obj");
+}
+
+#[test]
+fn test_parse_options() {
+    let options: Options = parse_args(&args(
+        "trace prog.obj --entry x3010 -l 500 -i keys.txt -r keys.session -t out.log --os os.obj \
+         --permissive --screen frames.txt --dump mem.hex --range x3000:x30FF --load data.bin@x4000 \
+         --video out.ppm --video-every 1000 --disk disk.img --uart listen:uart.sock \
+         --semihost fixtures",
+    ))
+    .unwrap();
+
+    assert_eq!(options.command, Command::Trace);
+    assert_eq!(options.entry, Some(0x3010));
+    assert_eq!(options.limit, Some(500));
+    assert_eq!(options.input.as_deref(), Some("keys.txt"));
+    assert_eq!(options.record.as_deref(), Some("keys.session"));
+    assert_eq!(options.screen.as_deref(), Some("frames.txt"));
+    assert_eq!(options.dump.as_deref(), Some("mem.hex"));
+    assert_eq!(options.range, Some((0x3000, 0x30FF)));
+    assert_eq!(options.load.as_deref(), Some("data.bin@x4000"));
+    assert_eq!(options.video.as_deref(), Some("out.ppm"));
+    assert_eq!(options.video_every, Some(1000));
+    assert_eq!(options.disk.as_deref(), Some("disk.img"));
+    assert_eq!(options.uart.as_deref(), Some("listen:uart.sock"));
+    assert_eq!(options.semihost.as_deref(), Some("fixtures"));
+    assert_eq!(options.trace_output.as_deref(), Some("out.log"));
+    assert_eq!(options.os_image.as_deref(), Some("os.obj"));
+    assert!(options.permissive);
+}
+
+#[test]
+fn test_parse_errors() {
+    assert_eq!(parse_args(&[]).unwrap_err().exit_code(), 2);
+    assert!(matches!(parse_args(&args("run")), Err(CliError::Usage(_))));
+    assert!(matches!(
+        parse_args(&args("run a.obj --limit")),
+        Err(CliError::Usage(_))
+    ));
+    assert!(matches!(
+        parse_args(&args("run a.obj --limit ten")),
+        Err(CliError::Usage(_))
+    ));
+    assert!(matches!(
+        parse_args(&args("run a.obj --bogus")),
+        Err(CliError::Usage(_))
+    ));
+    assert!(matches!(
+        parse_args(&args("run a.obj b.obj")),
+        Err(CliError::Usage(_))
+    ));
+    assert!(matches!(
+        parse_args(&args("run a.obj -i keys.txt -s keys.script")),
+        Err(CliError::Usage(_))
+    ));
+    assert!(matches!(
+        parse_args(&args("run a.obj --range x3010:x3000")),
+        Err(CliError::Usage(_))
+    ));
+    assert!(matches!(
+        parse_args(&args("run a.obj --video-every 0")),
+        Err(CliError::Usage(_))
+    ));
+    assert!(matches!(
+        parse_args(&args("run a.obj --uart /tmp/uart.sock")),
+        Err(CliError::Usage(_))
+    ));
+}
+
+#[test]
+fn test_parse_help() {
+    assert_eq!(parse_args(&args("--help")).unwrap().command, Command::Help);
+    assert_eq!(parse_args(&args("run -h")).unwrap().command, Command::Help);
+}
+
+#[test]
+fn test_parse_dap() {
+    // The editor's launch request names the program
+    assert_eq!(parse_args(&args("dap")).unwrap().command, Command::Dap);
+    assert!(matches!(
+        parse_args(&args("dap prog.obj")),
+        Err(CliError::Usage(_))
+    ));
+}
+
+#[test]
+fn test_execute_test_command() {
+    let expected = std::env::temp_dir().join("lc_3_cli_hello.txt");
+    std::fs::write(&expected, "Hello World!\nHALT\n").unwrap();
+
+    let mut options: Options = parse_args(&args("test resources/hello-world.obj")).unwrap();
+    options.expected = Some(expected.to_string_lossy().into_owned());
+
+    assert_eq!(execute(&options), Ok(()));
+
+    std::fs::write(&expected, "Goodbye").unwrap();
+    assert!(matches!(execute(&options), Err(CliError::Failure(_))));
+}
+
+#[test]
+fn test_execute_limit_and_missing_file() {
+    let options: Options = parse_args(&args("test resources/2048.obj --limit 100")).unwrap();
+    assert_eq!(execute(&options).unwrap_err().exit_code(), 1);
+
+    let options: Options = parse_args(&args("test resources/missing.obj")).unwrap();
+    assert_eq!(execute(&options).unwrap_err().exit_code(), 3);
+}
+
+#[test]
+fn test_execute_test_with_script() {
+    let script = std::env::temp_dir().join("lc_3_cli_2048.script");
+    std::fs::write(&script, "@50000 \"w\"\n+20000 \"a\"\n").unwrap();
+
+    let mut options: Options = parse_args(&args("test resources/2048.obj")).unwrap();
+    options.script = Some(script.to_string_lossy().into_owned());
+
+    assert!(!options.raw_terminal());
+    assert_eq!(execute(&options), Ok(()));
+
+    std::fs::write(&script, "@fifty \"w\"").unwrap();
+    assert_eq!(execute(&options).unwrap_err().exit_code(), 3);
+}
+
+#[test]
+fn test_execute_record_and_replay() {
+    let directory = std::env::temp_dir();
+    let script = directory.join("lc_3_cli_record.script");
+    let recording = directory.join("lc_3_cli_record.session");
+    std::fs::write(&script, "@50000 \"w\"\n+20000 \"a\" \"d\"\n").unwrap();
+
+    let mut options: Options = parse_args(&args("test resources/2048.obj")).unwrap();
+    options.script = Some(script.to_string_lossy().into_owned());
+    options.record = Some(recording.to_string_lossy().into_owned());
+    assert_eq!(execute(&options), Ok(()));
+
+    let text: String = std::fs::read_to_string(&recording).unwrap();
+    assert!(text.starts_with("; Session recorded from resources/2048.obj\n"));
+    assert_eq!(text.lines().filter(|line| line.starts_with('@')).count(), 3);
+
+    // Replaying the recording reads the same keys again
+    options.script = Some(recording.to_string_lossy().into_owned());
+    options.record = None;
+    assert_eq!(execute(&options), Ok(()));
+}
+
+#[test]
+fn test_execute_semihost_exit() {
+    let directory = std::env::temp_dir();
+    let source = directory.join("lc_3_cli_exit.asm");
+    std::fs::write(
+        &source,
+        ".ORIG x3000\nAND R0, R0, #0\nADD R0, R0, #5\nTRAP x35\n.END\n",
+    )
+    .unwrap();
+
+    let mut options: Options = parse_args(&args("run x.asm")).unwrap();
+    options.file = source.to_string_lossy().into_owned();
+    options.semihost = Some(directory.to_string_lossy().into_owned());
+
+    let result = execute(&options);
+    assert_eq!(result, Err(CliError::Exit(5)));
+    assert_eq!(result.unwrap_err().exit_code(), 5);
+
+    // Statuses past the range of exit codes saturate instead of wrapping around to success
+    assert_eq!(CliError::Exit(1).exit_code(), 1);
+    assert_eq!(CliError::Exit(255).exit_code(), 255);
+    assert_eq!(CliError::Exit(256).exit_code(), 255);
+    assert_eq!(CliError::Exit(0xFFFF).exit_code(), 255);
+}
+
+#[test]
+fn test_execute_dump_and_load() {
+    let directory = std::env::temp_dir();
+    let data = directory.join("lc_3_cli_load.bin");
+    let dump = directory.join("lc_3_cli_dump.obj");
+    std::fs::write(&data, [0xBE, 0xEF, 0xCA, 0xFE]).unwrap();
+
+    let mut options: Options =
+        parse_args(&args("test resources/hello-world.obj --range x4000:x4001")).unwrap();
+    options.load = Some(format!("{}@x4000", data.to_string_lossy()));
+    options.dump = Some(dump.to_string_lossy().into_owned());
+
+    assert_eq!(execute(&options), Ok(()));
+    assert_eq!(
+        std::fs::read(&dump).unwrap(),
+        vec![0x40, 0x00, 0xBE, 0xEF, 0xCA, 0xFE]
+    );
+
+    // Raw dumps cannot be placed without an address
+    options.load = Some(data.to_string_lossy().into_owned());
+    assert_eq!(execute(&options).unwrap_err().exit_code(), 3);
+}
+
+#[test]
+fn test_execute_text_image() {
+    let image = std::env::temp_dir().join("lc_3_cli_halt.bin");
+    std::fs::write(&image, "0011000000000000\n1111000000100101\n").unwrap();
+
+    let options: Options =
+        parse_args(&[String::from("test"), image.to_string_lossy().into_owned()]).unwrap();
+    assert_eq!(execute(&options), Ok(()));
+
+    std::fs::write(&image, "0011000000000000\n11110000001001\n").unwrap();
+    assert_eq!(execute(&options).unwrap_err().exit_code(), 3);
+}
+
+#[test]
+fn test_execute_profile_command() {
+    let report = std::env::temp_dir().join("lc_3_cli_profile.txt");
+    let mut options: Options =
+        parse_args(&args("profile resources/hello-world.obj -i /dev/null")).unwrap();
+    options.output = Some(report.to_string_lossy().into_owned());
+
+    assert_eq!(options.command, Command::Profile);
+    assert_eq!(execute(&options), Ok(()));
+
+    let text: String = std::fs::read_to_string(&report).unwrap();
+    assert!(text.starts_with("Instructions executed: 3"));
+    assert!(text.contains("HALT"));
+}
+
+#[test]
+fn test_execute_coverage_command() {
+    let directory = std::env::temp_dir();
+    let source = directory.join("lc_3_cli_coverage.asm");
+    let lcov = directory.join("lc_3_cli_coverage.info");
+    let report = directory.join("lc_3_cli_coverage.txt");
+    std::fs::write(&source, ".ORIG x3000\nBRz SKIP\nSKIP HALT\n.END\n").unwrap();
+
+    let options: Options = parse_args(&[
+        String::from("coverage"),
+        source.to_string_lossy().into_owned(),
+        String::from("--lcov"),
+        lcov.to_string_lossy().into_owned(),
+        String::from("-o"),
+        report.to_string_lossy().into_owned(),
+    ])
+    .unwrap();
+
+    assert_eq!(execute(&options), Ok(()));
+    assert!(std::fs::read_to_string(&lcov)
+        .unwrap()
+        .contains("BRDA:2,0,0,0\nBRDA:2,0,1,1\n"));
+    assert!(std::fs::read_to_string(&report)
+        .unwrap()
+        .contains("Lines: 2/2"));
+
+    let options: Options =
+        parse_args(&args("coverage resources/hello-world.obj --lcov x.info")).unwrap();
+    assert!(matches!(execute(&options), Err(CliError::Usage(_))));
+}
@@ -0,0 +1,512 @@
+use std::{fs, path::PathBuf};
+
+use crate::{
+    assembler::assemble,
+    console::BufferedConsole,
+    cpu::{Engine, RunState, CPU},
+};
+
+// Architectural behavior of every op code, checked on both execution engines.
+// LEA follows the second edition of the LC-3 and sets the condition codes.
+
+const ENGINES: [Engine; 2] = [Engine::Interpreter, Engine::Predecoded];
+
+// Condition codes in the processor status register
+const N: u16 = 0b100;
+const Z: u16 = 0b010;
+const P: u16 = 0b001;
+
+struct Case {
+    name: &'static str,
+    address: u16,
+    operation: u16,
+    registers: [u16; 8],
+    psr: u16,
+    memory: &'static [(u16, u16)],
+    // Registers expected to change, every other register must keep its value
+    changed: &'static [(usize, u16)],
+    // Program counter afterwards, the next address when not given
+    pc: Option<u16>,
+    expected_psr: u16,
+    stored: &'static [(u16, u16)],
+}
+
+const BASE: Case = Case {
+    name: "",
+    address: 0x3000,
+    operation: 0x0,
+    registers: [0x0; 8],
+    psr: Z,
+    memory: &[],
+    changed: &[],
+    pc: None,
+    expected_psr: Z,
+    stored: &[],
+};
+
+const CASES: &[Case] = &[
+    // Operate instructions
+    Case {
+        name: "ADD register",
+        operation: 0x1042, // ADD R0, R1, R2
+        registers: [0, 3, 4, 0, 0, 0, 0, 0],
+        changed: &[(0, 7)],
+        expected_psr: P,
+        ..BASE
+    },
+    Case {
+        name: "ADD overflows into the sign bit",
+        operation: 0x1042,
+        registers: [0, 0x7FFF, 1, 0, 0, 0, 0, 0],
+        changed: &[(0, 0x8000)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "ADD wraps around to zero",
+        operation: 0x1042,
+        registers: [9, 0xFFFF, 1, 0, 0, 0, 0, 0],
+        psr: P,
+        changed: &[(0, 0)],
+        expected_psr: Z,
+        ..BASE
+    },
+    Case {
+        name: "ADD a register to itself",
+        operation: 0x16C3, // ADD R3, R3, R3
+        registers: [0, 0, 0, 0x4000, 0, 0, 0, 0],
+        changed: &[(3, 0x8000)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "ADD most negative immediate",
+        operation: 0x1070, // ADD R0, R1, #-16
+        registers: [0, 16, 0, 0, 0, 0, 0, 0],
+        psr: P,
+        changed: &[(0, 0)],
+        expected_psr: Z,
+        ..BASE
+    },
+    Case {
+        name: "ADD most positive immediate",
+        operation: 0x106F, // ADD R0, R1, #15
+        registers: [0, 0xFFF0, 0, 0, 0, 0, 0, 0],
+        changed: &[(0, 0xFFFF)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "AND register",
+        operation: 0x5042, // AND R0, R1, R2
+        registers: [0, 0xF0F0, 0x3C3C, 0, 0, 0, 0, 0],
+        changed: &[(0, 0x3030)],
+        expected_psr: P,
+        ..BASE
+    },
+    Case {
+        name: "AND with zero",
+        operation: 0x5060, // AND R0, R1, #0
+        registers: [0, 0xFFFF, 0, 0, 0, 0, 0, 0],
+        psr: P,
+        changed: &[(0, 0)],
+        expected_psr: Z,
+        ..BASE
+    },
+    Case {
+        name: "AND with minus one",
+        operation: 0x507F, // AND R0, R1, #-1
+        registers: [0, 0x8001, 0, 0, 0, 0, 0, 0],
+        changed: &[(0, 0x8001)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "NOT to zero",
+        operation: 0x907F, // NOT R0, R1
+        registers: [0, 0xFFFF, 0, 0, 0, 0, 0, 0],
+        psr: N,
+        changed: &[(0, 0)],
+        expected_psr: Z,
+        ..BASE
+    },
+    Case {
+        name: "NOT to negative",
+        operation: 0x907F,
+        registers: [0, 0x00FF, 0, 0, 0, 0, 0, 0],
+        changed: &[(0, 0xFF00)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "NOT to positive",
+        operation: 0x927F, // NOT R1, R1
+        registers: [0, 0x8000, 0, 0, 0, 0, 0, 0],
+        changed: &[(1, 0x7FFF)],
+        expected_psr: P,
+        ..BASE
+    },
+    // Data movement instructions
+    Case {
+        name: "LD most negative offset",
+        operation: 0x2100, // LD R0, #-256
+        memory: &[(0x2F01, 0x8000)],
+        changed: &[(0, 0x8000)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "LD most positive offset",
+        operation: 0x20FF, // LD R0, #255
+        registers: [5, 0, 0, 0, 0, 0, 0, 0],
+        psr: P,
+        changed: &[(0, 0)],
+        expected_psr: Z,
+        ..BASE
+    },
+    Case {
+        name: "LD wraps below address zero",
+        address: 0x0010,
+        operation: 0x25E0, // LD R2, #-32
+        memory: &[(0xFFF1, 0x1234)],
+        changed: &[(2, 0x1234)],
+        expected_psr: P,
+        ..BASE
+    },
+    Case {
+        name: "LDI",
+        operation: 0xA201, // LDI R1, #1
+        memory: &[(0x3002, 0x4000), (0x4000, 0xFFFF)],
+        changed: &[(1, 0xFFFF)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "LDR most negative offset",
+        operation: 0x6060, // LDR R0, R1, #-32
+        registers: [0, 0x4020, 0, 0, 0, 0, 0, 0],
+        memory: &[(0x4000, 5)],
+        changed: &[(0, 5)],
+        expected_psr: P,
+        ..BASE
+    },
+    Case {
+        name: "LDR wraps past the top of memory",
+        operation: 0x605F, // LDR R0, R1, #31
+        registers: [1, 0xFFF0, 0, 0, 0, 0, 0, 0],
+        psr: N,
+        changed: &[(0, 0)],
+        expected_psr: Z,
+        ..BASE
+    },
+    Case {
+        name: "LEA",
+        operation: 0xEBFF, // LEA R5, #-1
+        changed: &[(5, 0x3000)],
+        expected_psr: P,
+        ..BASE
+    },
+    Case {
+        name: "LEA wraps below address zero",
+        address: 0x0000,
+        operation: 0xE1FE, // LEA R0, #-2
+        changed: &[(0, 0xFFFF)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "ST keeps the condition codes",
+        operation: 0x3700, // ST R3, #-256
+        registers: [0, 0, 0, 0xBEEF, 0, 0, 0, 0],
+        stored: &[(0x2F01, 0xBEEF)],
+        ..BASE
+    },
+    Case {
+        name: "STI",
+        operation: 0xB602, // STI R3, #2
+        registers: [0, 0, 0, 0x0042, 0, 0, 0, 0],
+        memory: &[(0x3003, 0x4000)],
+        psr: N,
+        stored: &[(0x4000, 0x0042)],
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "STR negative offset",
+        operation: 0x79BF, // STR R4, R6, #-1
+        registers: [0, 0, 0, 0, 0x0042, 0, 0x4000, 0],
+        psr: P,
+        stored: &[(0x3FFF, 0x0042)],
+        expected_psr: P,
+        ..BASE
+    },
+    // Control instructions
+    Case {
+        name: "BR most negative offset",
+        operation: 0x0F00, // BRnzp #-256
+        pc: Some(0x2F01),
+        ..BASE
+    },
+    Case {
+        name: "BR at the top of memory wraps to zero",
+        address: 0xFFFF,
+        operation: 0x0E01, // BRnzp #1
+        pc: Some(0x0001),
+        ..BASE
+    },
+    Case {
+        name: "BR without condition flags",
+        operation: 0x00FF, // NOP
+        ..BASE
+    },
+    Case {
+        name: "JMP",
+        operation: 0xC080, // JMP R2
+        registers: [0, 0, 0x1234, 0, 0, 0, 0, 0],
+        pc: Some(0x1234),
+        ..BASE
+    },
+    Case {
+        name: "RET",
+        operation: 0xC1C0, // RET
+        registers: [0, 0, 0, 0, 0, 0, 0, 0x3050],
+        psr: N,
+        pc: Some(0x3050),
+        expected_psr: N,
+        ..BASE
+    },
+    Case {
+        name: "JSR most negative offset",
+        operation: 0x4C00, // JSR #-1024
+        changed: &[(7, 0x3001)],
+        pc: Some(0x2C01),
+        ..BASE
+    },
+    Case {
+        name: "JSR most positive offset",
+        operation: 0x4BFF, // JSR #1023
+        changed: &[(7, 0x3001)],
+        pc: Some(0x3400),
+        ..BASE
+    },
+    Case {
+        name: "JSRR",
+        operation: 0x40C0, // JSRR R3
+        registers: [0, 0, 0, 0x5000, 0, 0, 0, 0],
+        changed: &[(7, 0x3001)],
+        pc: Some(0x5000),
+        ..BASE
+    },
+    Case {
+        name: "JSRR R7 jumps to the old R7",
+        operation: 0x41C0, // JSRR R7
+        registers: [0, 0, 0, 0, 0, 0, 0, 0x5000],
+        changed: &[(7, 0x3001)],
+        pc: Some(0x5000),
+        ..BASE
+    },
+    Case {
+        name: "RTI in supervisor mode",
+        operation: 0x8000,
+        registers: [0, 0, 0, 0, 0, 0, 0x2FFE, 0],
+        memory: &[(0x2FFE, 0x3100), (0x2FFF, 0x0404)],
+        changed: &[(6, 0x3000)],
+        pc: Some(0x3100),
+        expected_psr: 0x0404,
+        ..BASE
+    },
+];
+
+fn execute(case: &Case, engine: Engine) -> CPU {
+    let mut cpu: CPU = CPU::new();
+    cpu.set_engine(engine);
+    cpu.registers = case.registers;
+    cpu.processor_status_register = case.psr;
+
+    for (address, value) in case.memory {
+        cpu.memory.write(*address, *value);
+    }
+    cpu.memory.write(case.address, case.operation);
+    cpu.program_counter = case.address;
+
+    assert_eq!(cpu.step(), RunState::Running, "{}", case.name);
+    cpu
+}
+
+#[test]
+fn test_instruction_table() {
+    for case in CASES {
+        let mut registers: [u16; 8] = case.registers;
+        for (register, value) in case.changed {
+            registers[*register] = *value;
+        }
+
+        for engine in ENGINES {
+            let mut cpu: CPU = execute(case, engine);
+            let context: String = format!("{} ({:?})", case.name, engine);
+
+            assert_eq!(cpu.registers, registers, "{}", context);
+            assert_eq!(
+                cpu.program_counter,
+                case.pc.unwrap_or(case.address.wrapping_add(1)),
+                "{}",
+                context
+            );
+            assert_eq!(
+                cpu.processor_status_register, case.expected_psr,
+                "{}",
+                context
+            );
+
+            for (address, value) in case.stored {
+                assert_eq!(cpu.memory.read(*address), *value, "{}", context);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_branch_condition_codes() {
+    for condition_codes in 0..8 {
+        for flag in [N, Z, P] {
+            let taken: bool = condition_codes & flag != 0;
+            let case: Case = Case {
+                name: "BR",
+                operation: (condition_codes << 9) | 0x0005,
+                psr: flag,
+                pc: Some(if taken { 0x3006 } else { 0x3001 }),
+                expected_psr: flag,
+                ..BASE
+            };
+
+            for engine in ENGINES {
+                let cpu: CPU = execute(&case, engine);
+                let context: String = format!(
+                    "BR {:03b} with {:03b} ({:?})",
+                    condition_codes, flag, engine
+                );
+
+                assert_eq!(cpu.program_counter, case.pc.unwrap(), "{}", context);
+                assert_eq!(cpu.branch_taken(), Some(taken), "{}", context);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_trap_through_vector_table() {
+    for engine in ENGINES {
+        let mut cpu: CPU = CPU::new();
+        cpu.set_engine(engine);
+        cpu.native_traps = false;
+        cpu.processor_status_register = N;
+        cpu.memory.write(0x0023, 0x0480);
+        cpu.memory.write(0x3000, 0xF023); // TRAP x23
+
+        cpu.step();
+
+        assert_eq!(cpu.program_counter, 0x0480);
+        assert_eq!(cpu.registers[7], 0x3001);
+        assert_eq!(cpu.processor_status_register, N);
+    }
+}
+
+#[test]
+fn test_rti_to_user_mode() {
+    for engine in ENGINES {
+        let mut cpu: CPU = CPU::new();
+        cpu.set_engine(engine);
+        cpu.saved_user_stack_pointer = 0xFDFF;
+        cpu.registers[6] = 0x2FFE;
+        cpu.memory.write(0x2FFE, 0x4000);
+        cpu.memory.write(0x2FFF, 0x8001); // User mode, condition codes P
+        cpu.memory.write(0x3000, 0x8000);
+
+        cpu.step();
+
+        assert_eq!(cpu.program_counter, 0x4000);
+        assert_eq!(cpu.processor_status_register, 0x8001);
+        assert_eq!(cpu.registers[6], 0xFDFF);
+        assert_eq!(cpu.saved_supervisor_stack_pointer, 0x3000);
+    }
+}
+
+#[test]
+fn test_rti_privilege_mode_violation() {
+    for engine in ENGINES {
+        let mut cpu: CPU = CPU::new();
+        cpu.set_engine(engine);
+        cpu.native_traps = false;
+        cpu.processor_status_register = 0x8000 | P;
+        cpu.registers[6] = 0xFDFF;
+        cpu.memory.write(0x0100, 0x0500);
+        cpu.memory.write(0x3000, 0x8000);
+
+        cpu.step();
+
+        // The handler runs in supervisor mode with the interrupted state on its stack
+        assert_eq!(cpu.program_counter, 0x0500);
+        assert_eq!(cpu.processor_status_register, P);
+        assert_eq!(cpu.registers[6], 0x2FFE);
+        assert_eq!(cpu.memory.read(0x2FFE), 0x3001);
+        assert_eq!(cpu.memory.read(0x2FFF), 0x8000 | P);
+        assert_eq!(cpu.saved_user_stack_pointer, 0xFDFF);
+    }
+}
+
+#[test]
+fn test_reserved_op_code() {
+    for engine in ENGINES {
+        let mut cpu: CPU = CPU::new();
+        cpu.set_engine(engine);
+        cpu.native_traps = false;
+        cpu.processor_status_register = 0x8000 | Z;
+        cpu.registers[6] = 0xFDFF;
+        cpu.memory.write(0x0101, 0x0600);
+        cpu.memory.write(0x3000, 0xD000);
+
+        cpu.step();
+
+        assert_eq!(cpu.program_counter, 0x0600);
+        assert_eq!(cpu.processor_status_register, Z);
+        assert_eq!(cpu.registers[6], 0x2FFE);
+        assert_eq!(cpu.memory.read(0x2FFE), 0x3001);
+        assert_eq!(cpu.memory.read(0x2FFF), 0x8000 | Z);
+        assert_eq!(cpu.saved_user_stack_pointer, 0xFDFF);
+    }
+}
+
+// Every program in resources/conformance must halt with the output in its .out file
+#[test]
+fn test_conformance_programs() {
+    let mut paths: Vec<PathBuf> = fs::read_dir("resources/conformance")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "asm"))
+        .collect();
+    paths.sort();
+
+    assert!(!paths.is_empty());
+
+    for path in paths {
+        let program = assemble(&fs::read_to_string(&path).unwrap()).unwrap();
+        let expected: Vec<u8> = fs::read(path.with_extension("out")).unwrap();
+
+        for engine in ENGINES {
+            let console: BufferedConsole = BufferedConsole::new(&[]);
+            let output = console.output();
+            let mut cpu: CPU = CPU::with_console(Box::new(console));
+            cpu.set_engine(engine);
+            cpu.load_image(&program.to_object()).unwrap();
+
+            assert_eq!(cpu.run(Some(100_000)), RunState::Halted, "{:?}", path);
+            assert_eq!(
+                String::from_utf8_lossy(&output.borrow()),
+                String::from_utf8_lossy(&expected),
+                "{:?} ({:?})",
+                path,
+                engine
+            );
+        }
+    }
+}
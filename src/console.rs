@@ -0,0 +1,138 @@
+use alloc::{boxed::Box, collections::VecDeque, rc::Rc, vec::Vec};
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use std::{
+    io::{Read, Write},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+// Character device behind the keyboard/display registers and the I/O trap routines
+pub trait Console {
+    // Blocks until a byte is available, `None` once the input is exhausted
+    fn read_byte(&mut self) -> Option<u8>;
+
+    // Keyboard status poll, `instructions` is the number executed so far. Consoles without
+    // timing of their own simply hand out the next byte
+    fn poll_byte(&mut self, _instructions: u64) -> Option<u8> {
+        self.read_byte()
+    }
+
+    // True once no more input can ever arrive
+    fn exhausted(&self) -> bool {
+        false
+    }
+
+    fn write_byte(&mut self, byte: u8);
+
+    fn flush(&mut self) {}
+
+    // Called at the end of every instruction that wrote output, such as a whole PUTS string
+    fn output_finished(&mut self) {}
+
+    // True once written output can no longer be delivered, the machine stops then
+    fn closed(&self) -> bool {
+        false
+    }
+}
+
+// Talks to the host terminal through stdin/stdout
+#[cfg(feature = "std")]
+pub struct StdConsole;
+
+// Set once writing to stdout failed, e.g. because the reading end of a pipe was closed.
+// Stdout is shared by the whole process, so every StdConsole sees it
+#[cfg(feature = "std")]
+static STDOUT_CLOSED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "std")]
+impl Console for StdConsole {
+    fn read_byte(&mut self) -> Option<u8> {
+        // Make sure any prompt is visible before blocking on the keyboard
+        self.flush();
+
+        let mut buffer: [u8; 1] = [0; 1];
+
+        match std::io::stdin().read_exact(&mut buffer) {
+            Ok(()) => Some(buffer[0]),
+            Err(_) => None,
+        }
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        if std::io::stdout().write_all(&[byte]).is_err() {
+            STDOUT_CLOSED.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn flush(&mut self) {
+        if std::io::stdout().flush().is_err() {
+            STDOUT_CLOSED.store(true, Ordering::Relaxed);
+        }
+    }
+
+    fn closed(&self) -> bool {
+        STDOUT_CLOSED.load(Ordering::Relaxed)
+    }
+}
+
+// Feeds a fixed input and records everything written unless it is echoed, used for tests and scripted runs
+pub struct BufferedConsole {
+    input: VecDeque<u8>,
+    output: Rc<RefCell<Vec<u8>>>,
+    // Receives everything written instead of the output buffer
+    echo: Option<Box<dyn Console>>,
+}
+
+impl BufferedConsole {
+    pub fn new(input: &[u8]) -> Self {
+        BufferedConsole {
+            input: input.iter().copied().collect(),
+            output: Rc::new(RefCell::new(Vec::new())),
+            echo: None,
+        }
+    }
+
+    // Copy output to stdout as it is produced instead of capturing it, so long runs don't pile
+    // up everything they ever printed
+    #[cfg(feature = "std")]
+    pub fn with_echo(mut self) -> Self {
+        self.echo = Some(Box::new(StdConsole));
+        self
+    }
+
+    // Shared handle to the captured output that stays valid after the console is moved into memory
+    pub fn output(&self) -> Rc<RefCell<Vec<u8>>> {
+        Rc::clone(&self.output)
+    }
+}
+
+impl Console for BufferedConsole {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.input.pop_front()
+    }
+
+    fn exhausted(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        match &mut self.echo {
+            Some(echo) => echo.write_byte(byte),
+            None => self.output.borrow_mut().push(byte),
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(echo) = &mut self.echo {
+            echo.flush();
+        }
+    }
+
+    fn closed(&self) -> bool {
+        self.echo.as_ref().is_some_and(|echo| echo.closed())
+    }
+}
+
+#[cfg(test)]
+#[path = "./console_test.rs"]
+mod console_test;
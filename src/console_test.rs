@@ -0,0 +1,21 @@
+use crate::console::{BufferedConsole, Console};
+
+#[test]
+fn test_buffered_console_input() {
+    let mut console: BufferedConsole = BufferedConsole::new(b"ab");
+
+    assert_eq!(console.read_byte(), Some(b'a'));
+    assert_eq!(console.read_byte(), Some(b'b'));
+    assert_eq!(console.read_byte(), None);
+}
+
+#[test]
+fn test_buffered_console_output() {
+    let mut console: BufferedConsole = BufferedConsole::new(&[]);
+    let output = console.output();
+
+    console.write_byte(b'O');
+    console.write_byte(b'K');
+
+    assert_eq!(output.borrow().as_slice(), b"OK");
+}
@@ -0,0 +1,74 @@
+// Runs without the standard library, `cargo test --no-default-features --lib` covers the core
+// through these alone
+use alloc::{boxed::Box, collections::VecDeque, rc::Rc, vec::Vec};
+use core::cell::RefCell;
+
+use crate::{
+    console::Console,
+    cpu::{RunState, Snapshot, CPU},
+    trap::TrapContext,
+};
+
+// Keys and output kept in memory, the way an embedded host would wire up its own device
+struct Terminal {
+    keys: VecDeque<u8>,
+    output: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Console for Terminal {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.keys.pop_front()
+    }
+
+    fn exhausted(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.output.borrow_mut().push(byte);
+    }
+}
+
+// GETC, OUT, TRAP x40, OUT, HALT
+const IMAGE: [u8; 12] = [
+    0x30, 0x00, 0xF0, 0x20, 0xF0, 0x21, 0xF0, 0x40, 0xF0, 0x21, 0xF0, 0x25,
+];
+
+#[test]
+fn test_core_machine() {
+    let output: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+    let mut cpu: CPU = CPU::with_console(Box::new(Terminal {
+        keys: b"a".iter().copied().collect(),
+        output: output.clone(),
+    }));
+
+    // Counts its calls into x4000 and moves R0 on to the next letter
+    cpu.register_trap(0x40, |context: &mut TrapContext| {
+        let calls: u16 = context.memory.read(0x4000) + 1;
+
+        context.memory.write(0x4000, calls);
+        context.registers[0] += 1;
+    })
+    .unwrap();
+
+    assert_eq!(cpu.load_image(&IMAGE), Ok(0x3000));
+    cpu.step();
+    cpu.step();
+    let snapshot: Snapshot = cpu.snapshot();
+
+    assert_eq!(cpu.run(None), RunState::Halted);
+    assert_eq!(output.borrow().as_slice(), b"ab\nHALT\n");
+    assert_eq!(cpu.memory().peek(0x4000), 1);
+
+    // Going back before the trap undoes its write, running again calls it afresh
+    cpu.restore(&snapshot);
+    assert_eq!(cpu.program_counter(), 0x3002);
+    assert_eq!(cpu.registers()[0], b'a' as u16);
+    assert_eq!(cpu.memory().peek(0x4000), 0);
+
+    output.borrow_mut().clear();
+    assert_eq!(cpu.run(None), RunState::Halted);
+    assert_eq!(output.borrow().as_slice(), b"b\nHALT\n");
+    assert_eq!(cpu.memory().peek(0x4000), 1);
+    assert_eq!(cpu.instruction_count(), 5);
+}
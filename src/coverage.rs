@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    assembler::{Program, SymbolTable},
+    cpu::{RunState, CPU},
+    disassembler::{disassemble_with_symbols, label_at},
+    memory::Memory,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BranchOutcomes {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+// Records executed addresses and the outcomes of every BR instruction
+#[derive(Default)]
+pub struct Coverage {
+    hits: BTreeMap<u16, u64>,
+    branches: BTreeMap<u16, BranchOutcomes>,
+}
+
+fn percent(hit: usize, found: usize) -> f64 {
+    hit as f64 * 100.0 / found.max(1) as f64
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage::default()
+    }
+
+    pub fn hits(&self, address: u16) -> u64 {
+        self.hits.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn branch(&self, address: u16) -> BranchOutcomes {
+        self.branches.get(&address).copied().unwrap_or_default()
+    }
+
+    pub fn step(&mut self, cpu: &mut CPU) -> RunState {
+        let address: u16 = cpu.program_counter();
+        let state: RunState = cpu.step();
+
+        self.record(address, cpu.branch_taken());
+        state
+    }
+
+    pub fn run(&mut self, cpu: &mut CPU, limit: Option<u64>) -> RunState {
+        let mut executed: u64 = 0;
+
+        while cpu.state() == RunState::Running && limit.is_none_or(|limit| executed < limit) {
+            self.step(cpu);
+            executed += 1;
+        }
+
+        cpu.memory_mut().console().flush();
+        cpu.state()
+    }
+
+    pub fn record(&mut self, address: u16, branch_taken: Option<bool>) {
+        *self.hits.entry(address).or_insert(0) += 1;
+
+        if let Some(taken) = branch_taken {
+            let outcomes: &mut BranchOutcomes = self.branches.entry(address).or_default();
+
+            match taken {
+                true => outcomes.taken += 1,
+                false => outcomes.not_taken += 1,
+            }
+        }
+    }
+
+    // Only conditional branches have two outcomes worth covering
+    fn is_conditional_branch(operation: u16) -> bool {
+        let flags: u16 = (operation >> 9) & 0x7;
+
+        operation >> 12 == 0x0 && flags != 0 && flags != 0x7
+    }
+
+    // Source lines holding instructions, with their hit count and branch outcomes
+    fn line_coverage(&self, program: &Program) -> BTreeMap<usize, (u64, Option<BranchOutcomes>)> {
+        let mut lines: BTreeMap<usize, (u64, Option<BranchOutcomes>)> = BTreeMap::new();
+
+        for (index, word) in program.words.iter().enumerate() {
+            if !program.code[index] {
+                continue;
+            }
+
+            let address: u16 = program.origin.wrapping_add(index as u16);
+            let entry = lines.entry(program.lines[index]).or_insert((0, None));
+
+            entry.0 = entry.0.max(self.hits(address));
+
+            if Coverage::is_conditional_branch(*word) {
+                entry.1 = Some(self.branch(address));
+            }
+        }
+
+        lines
+    }
+
+    // Annotated source listing in the spirit of gcov
+    pub fn source_report(&self, program: &Program, source: &str) -> String {
+        let lines = self.line_coverage(program);
+        let mut report: String = String::new();
+        let (mut outcomes_found, mut outcomes_hit) = (0, 0);
+
+        for (index, text) in source.lines().enumerate() {
+            let count: String = match lines.get(&(index + 1)) {
+                Some((0, _)) => String::from("#####"),
+                Some((hits, _)) => hits.to_string(),
+                None => String::from("-"),
+            };
+
+            report.push_str(&format!("{:>9}  {:>5}  {}", count, index + 1, text));
+
+            if let Some((_, Some(outcomes))) = lines.get(&(index + 1)) {
+                outcomes_found += 2;
+                outcomes_hit += (outcomes.taken > 0) as usize + (outcomes.not_taken > 0) as usize;
+                report.push_str(&format!(
+                    "    [taken {}, not taken {}]",
+                    outcomes.taken, outcomes.not_taken
+                ));
+            }
+
+            report.push('\n');
+        }
+
+        let lines_hit: usize = lines.values().filter(|(hits, _)| *hits > 0).count();
+
+        report.push_str(&format!(
+            "\nLines: {}/{} ({:.1}%)  Branch outcomes: {}/{} ({:.1}%)\n",
+            lines_hit,
+            lines.len(),
+            percent(lines_hit, lines.len()),
+            outcomes_hit,
+            outcomes_found,
+            percent(outcomes_hit, outcomes_found)
+        ));
+
+        report
+    }
+
+    // Per address listing of an image loaded without its source
+    pub fn image_report(
+        &self,
+        memory: &Memory,
+        origin: u16,
+        length: u16,
+        symbols: &SymbolTable,
+    ) -> String {
+        let mut report: String = String::new();
+        let mut hit: usize = 0;
+
+        for offset in 0..length {
+            let address: u16 = origin.wrapping_add(offset);
+            let operation: u16 = memory.peek(address);
+            let hits: u64 = self.hits(address);
+
+            hit += (hits > 0) as usize;
+            report.push_str(&format!(
+                "{:>9}  x{:04X}  {:<12}  {}",
+                if hits == 0 {
+                    String::from("#####")
+                } else {
+                    hits.to_string()
+                },
+                address,
+                label_at(symbols, address).unwrap_or(""),
+                disassemble_with_symbols(address, operation, symbols)
+            ));
+
+            if Coverage::is_conditional_branch(operation) {
+                let outcomes: BranchOutcomes = self.branch(address);
+                report.push_str(&format!(
+                    "    [taken {}, not taken {}]",
+                    outcomes.taken, outcomes.not_taken
+                ));
+            }
+
+            report.push('\n');
+        }
+
+        report.push_str(&format!(
+            "\nAddresses: {}/{} ({:.1}%)\n",
+            hit,
+            length,
+            percent(hit, length as usize)
+        ));
+
+        report
+    }
+
+    // Coverage in the lcov tracefile format understood by genhtml and most CI tools
+    pub fn lcov(&self, program: &Program, source_path: &str) -> String {
+        let lines = self.line_coverage(program);
+        let mut report: String = format!("TN:\nSF:{}\n", source_path);
+        let (mut branches_found, mut branches_hit) = (0, 0);
+
+        for (line, (hits, outcomes)) in &lines {
+            if let Some(outcomes) = outcomes {
+                for (branch, count) in [(0, outcomes.taken), (1, outcomes.not_taken)] {
+                    let taken: String = match hits {
+                        0 => String::from("-"),
+                        _ => count.to_string(),
+                    };
+
+                    branches_found += 1;
+                    branches_hit += (count > 0) as usize;
+                    report.push_str(&format!("BRDA:{},0,{},{}\n", line, branch, taken));
+                }
+            }
+        }
+
+        report.push_str(&format!("BRF:{}\nBRH:{}\n", branches_found, branches_hit));
+
+        for (line, (hits, _)) in &lines {
+            report.push_str(&format!("DA:{},{}\n", line, hits));
+        }
+
+        let lines_hit: usize = lines.values().filter(|(hits, _)| *hits > 0).count();
+        report.push_str(&format!(
+            "LF:{}\nLH:{}\nend_of_record\n",
+            lines.len(),
+            lines_hit
+        ));
+
+        report
+    }
+}
+
+#[cfg(test)]
+#[path = "./coverage_test.rs"]
+mod coverage_test;
@@ -0,0 +1,88 @@
+use crate::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    coverage::{BranchOutcomes, Coverage},
+    cpu::{RunState, CPU},
+};
+
+const PROGRAM: &str = "        .ORIG x3000
+        AND R1, R1, #0
+        ADD R1, R1, #2
+LOOP    ADD R1, R1, #-1
+        BRp LOOP
+        BRn NEVER
+        HALT
+NEVER   ADD R2, R2, #1  ; unreachable
+        HALT
+DATA    .FILL #7
+        .END";
+
+fn covered() -> (Coverage, CPU, Program) {
+    let program: Program = assemble(PROGRAM).unwrap();
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(&[])));
+    let mut coverage: Coverage = Coverage::new();
+
+    cpu.load_image(&program.to_object()).unwrap();
+    assert_eq!(coverage.run(&mut cpu, Some(100)), RunState::Halted);
+
+    (coverage, cpu, program)
+}
+
+#[test]
+fn test_coverage_hits_and_branches() {
+    let (coverage, _, _) = covered();
+
+    assert_eq!(coverage.hits(0x3002), 2);
+    assert_eq!(coverage.hits(0x3006), 0);
+    assert_eq!(
+        coverage.branch(0x3003),
+        BranchOutcomes {
+            taken: 1,
+            not_taken: 1
+        }
+    );
+    assert_eq!(
+        coverage.branch(0x3004),
+        BranchOutcomes {
+            taken: 0,
+            not_taken: 1
+        }
+    );
+}
+
+#[test]
+fn test_coverage_source_report() {
+    let (coverage, _, program) = covered();
+    let report: String = coverage.source_report(&program, PROGRAM);
+    let lines: Vec<&str> = report.lines().collect();
+
+    assert!(lines[0].trim_start().starts_with("-"));
+    assert!(lines[3].trim_start().starts_with("2"));
+    assert!(lines[4].ends_with("[taken 1, not taken 1]"));
+    assert!(lines[7].trim_start().starts_with("#####"));
+    assert!(lines[9].trim_start().starts_with("-"));
+    assert!(report.contains("Lines: 6/8 (75.0%)  Branch outcomes: 3/4 (75.0%)"));
+}
+
+#[test]
+fn test_coverage_lcov() {
+    let (coverage, _, program) = covered();
+    let lcov: String = coverage.lcov(&program, "loop.asm");
+
+    assert!(lcov.starts_with("TN:\nSF:loop.asm\n"));
+    assert!(lcov.contains("BRDA:5,0,0,1\nBRDA:5,0,1,1\nBRDA:6,0,0,0\nBRDA:6,0,1,1\n"));
+    assert!(lcov.contains("BRF:4\nBRH:3\n"));
+    assert!(lcov.contains("DA:4,2\n"));
+    assert!(lcov.contains("DA:8,0\n"));
+    assert!(!lcov.contains("DA:10,"));
+    assert!(lcov.ends_with("LF:8\nLH:6\nend_of_record\n"));
+}
+
+#[test]
+fn test_coverage_image_report() {
+    let (coverage, cpu, program) = covered();
+    let report: String = coverage.image_report(cpu.memory(), 0x3000, 9, &program.symbols);
+
+    assert!(report.contains("x3002  LOOP          ADD R1, R1, #-1"));
+    assert!(report.contains("Addresses: 6/9"));
+}
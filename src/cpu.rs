@@ -1,33 +1,159 @@
-use std::{fs::File, io::Read, slice::Chunks};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+use core::{fmt, slice::Chunks};
+#[cfg(feature = "std")]
+use std::{fs::File, io::Read};
+
+use crate::{
+    console::Console,
+    image,
+    instruction::{sign_extension, Instruction},
+    memory::{self, Memory, MemorySnapshot},
+    observer::Observer,
+    trap::{TrapContext, TrapHandler, TrapTable},
+};
+#[cfg(feature = "std")]
+use crate::{
+    console::StdConsole,
+    semihost::{self, Semihost},
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RunState {
+    Running,
+    Halted,
+    // A trap routine needed a character but the console has no more input
+    InputExhausted,
+    // An exception was raised with no operating system loaded to handle it
+    Fault(Exception),
+}
+
+// Exceptions raised by the processor, serviced through the interrupt vector table
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Exception {
+    PrivilegeModeViolation,
+    IllegalOpCode,
+    AccessControlViolation,
+    // A TRAP with no handler registered for its vector, only raised while traps are native
+    UnknownTrap(u8),
+}
+
+impl Exception {
+    pub fn vector(&self) -> u8 {
+        match self {
+            Exception::PrivilegeModeViolation => 0x00,
+            Exception::IllegalOpCode => 0x01,
+            Exception::AccessControlViolation => 0x02,
+            // Without native traps the OS image's trap table serves every vector, so this
+            // never goes through the vector table. It is closest to an illegal op code
+            Exception::UnknownTrap(_) => 0x01,
+        }
+    }
+}
+
+impl fmt::Display for Exception {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exception::PrivilegeModeViolation => write!(f, "privilege mode violation"),
+            Exception::IllegalOpCode => write!(f, "illegal op code"),
+            Exception::AccessControlViolation => write!(f, "access control violation"),
+            Exception::UnknownTrap(vector) => write!(f, "unknown trap x{:02X}", vector),
+        }
+    }
+}
+
+// Machine state saved by CPU::snapshot, sharing memory pages with the CPU until either side
+// writes to them
+#[derive(Clone)]
+pub struct Snapshot {
+    registers: [u16; 8],
+    program_counter: u16,
+    processor_status_register: u16,
+    state: RunState,
+    instruction_count: u64,
+    saved_user_stack_pointer: u16,
+    saved_supervisor_stack_pointer: u16,
+    memory: MemorySnapshot,
+}
 
-use crate::memory::Memory;
+impl Snapshot {
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+}
+
+// Start of the interrupt vector table, exception and interrupt vectors index into it
+pub const INTERRUPT_VECTOR_TABLE: u16 = 0x0100;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Engine {
+    // Decodes every instruction word as it is executed
+    Interpreter,
+    // Executes instructions decoded once and cached by memory
+    Predecoded,
+}
 
+#[allow(clippy::upper_case_acronyms)]
 pub struct CPU {
     registers: [u16; 8],
     program_counter: u16,
     processor_status_register: u16,
     memory: Memory,
+    state: RunState,
+    instruction_count: u64,
+    // Service traps in Rust instead of jumping through the trap vector table of an OS image,
+    // exceptions stop the machine instead of entering a handler while this is set
+    native_traps: bool,
+    // Outcome of the BR instruction executed by the last tick, if it was one
+    branch_taken: Option<bool>,
+    // Stack pointer of the mode not currently running, swapped into R6 on a mode change
+    saved_user_stack_pointer: u16,
+    saved_supervisor_stack_pointer: u16,
+    // Raise access control violations for user mode accesses to system space and the I/O page
+    access_control: bool,
+    engine: Engine,
+    // Host services behind the extended trap vectors, off unless enabled
+    #[cfg(feature = "std")]
+    semihost: Option<Semihost>,
+    // Handlers serving traps natively, starting out with the standard routines
+    traps: TrapTable,
+    observers: Vec<Box<dyn Observer>>,
 }
 
 impl CPU {
+    #[cfg(feature = "std")]
     pub fn new() -> Self {
+        CPU::with_console(Box::new(StdConsole))
+    }
+
+    pub fn with_console(console: Box<dyn Console>) -> Self {
         CPU {
             registers: [0x0; 8],
             program_counter: 0x3000,
-            memory: Memory::new(),
+            memory: Memory::with_console(console),
             processor_status_register: 0x0,
+            state: RunState::Running,
+            instruction_count: 0,
+            native_traps: true,
+            branch_taken: None,
+            saved_user_stack_pointer: 0x0,
+            saved_supervisor_stack_pointer: 0x3000,
+            access_control: true,
+            engine: Engine::Predecoded,
+            #[cfg(feature = "std")]
+            semihost: None,
+            traps: TrapTable::new(),
+            observers: Vec::new(),
         }
     }
 
-    pub fn execute_program(&mut self, file_path: &String) {
+    #[cfg(feature = "std")]
+    pub fn execute_program(&mut self, file_path: &str) {
         self.read_image(file_path);
-
-        while self.program_counter < u16::MAX {
-            self.tick();
-        }
+        self.run(None);
     }
 
-    fn read_image(&mut self, file_path: &String) {
+    #[cfg(feature = "std")]
+    fn read_image(&mut self, file_path: &str) {
         // Attempt to read file path
         let mut file: File = match File::open(file_path) {
             Ok(ok_file) => ok_file,
@@ -38,20 +164,32 @@ impl CPU {
 
         file.read_to_end(&mut data).unwrap();
 
-        match data.len() % 2 {
-            result if result != 0 => panic!("Buffer size not even"),
-            _ => {}
+        if let Err(message) = self.load_image(&data) {
+            panic!("{}", message);
+        }
+    }
+
+    // Copies an object image into memory and points the program counter at its origin.
+    // Binary .obj images and the .hex and .bin text formats are told apart by their contents
+    pub fn load_image(&mut self, data: &[u8]) -> Result<u16, String> {
+        let data: &[u8] = &image::object_image(data)?;
+
+        if !data.len().is_multiple_of(2) {
+            return Err(String::from("Buffer size not even"));
         }
 
         // Collect the data into chunks of size two 8 bit values as the lc3 stores data by 16 bits
         let mut data_chunks: Chunks<u8> = data.chunks(2);
 
-        let program_counter_chunk: &[u8] = data_chunks.next().unwrap();
+        let program_counter_chunk: &[u8] = match data_chunks.next() {
+            Some(chunk) => chunk,
+            None => return Err(String::from("Image is missing its origin")),
+        };
 
-        let mut program_counter: u16 =
-            u16::from_be_bytes([program_counter_chunk[0], program_counter_chunk[1]]);
+        let origin: u16 = u16::from_be_bytes([program_counter_chunk[0], program_counter_chunk[1]]);
+        let mut program_counter: u16 = origin;
 
-        self.program_counter = program_counter; // Set program counter to origin provided by image (usually 0x3000)
+        self.program_counter = origin; // Set program counter to origin provided by image (usually 0x3000)
 
         // Iterate through the rest of the chunks and insert into memory sequentially
         for data_chunk in data_chunks {
@@ -61,11 +199,235 @@ impl CPU {
             );
             program_counter = program_counter.wrapping_add(1);
         }
+
+        Ok(origin)
+    }
+
+    // Loads an operating system image whose trap vector table replaces the native trap routines
+    pub fn load_os_image(&mut self, data: &[u8]) -> Result<u16, String> {
+        let program_counter: u16 = self.program_counter;
+        let origin: u16 = self.load_image(data)?;
+
+        self.program_counter = program_counter;
+        self.native_traps = false;
+
+        Ok(origin)
+    }
+
+    // Executes a single instruction unless the machine has stopped
+    pub fn step(&mut self) -> RunState {
+        if self.state == RunState::Running {
+            let address: u16 = self.program_counter;
+
+            self.notify(|observer, cpu| observer.before_instruction(cpu, address));
+            match self.engine {
+                Engine::Interpreter => self.tick(),
+                Engine::Predecoded => self.tick_predecoded(),
+            }
+            self.instruction_count += 1;
+            self.memory.set_instruction_count(self.instruction_count);
+            self.notify(|observer, cpu| observer.after_instruction(cpu, address));
+
+            if !self.memory.clock_enabled() || self.memory.output_closed() {
+                // Also stop once nobody is left to read the output, e.g. a closed pipe
+                self.state = RunState::Halted;
+            } else if self.memory.take_input_exhausted() {
+                // Polling the keyboard cannot make progress any more
+                self.state = RunState::InputExhausted;
+            } else if let Some((priority, vector)) = self.memory.interrupt() {
+                // The instruction may have stopped the machine, which then stays where it is
+                if self.state == RunState::Running && priority > self.priority() {
+                    self.interrupt(priority, vector);
+                    self.notify(|observer, cpu| observer.interrupt(cpu, priority, vector));
+                }
+            }
+        }
+
+        self.state
+    }
+
+    // Runs until the machine stops or `limit` more instructions have executed
+    pub fn run(&mut self, limit: Option<u64>) -> RunState {
+        let mut executed: u64 = 0;
+
+        while self.state == RunState::Running && limit.is_none_or(|limit| executed < limit) {
+            self.step();
+            executed += 1;
+        }
+
+        self.memory.console().flush();
+        self.state
+    }
+
+    pub fn state(&self) -> RunState {
+        self.state
+    }
+
+    // Picks up again after the machine ran out of input, once the console has more to offer
+    pub fn resume(&mut self) {
+        if self.state == RunState::InputExhausted {
+            self.state = RunState::Running;
+        }
+    }
+
+    pub fn instruction_count(&self) -> u64 {
+        self.instruction_count
+    }
+
+    pub fn registers(&self) -> &[u16; 8] {
+        &self.registers
+    }
+
+    pub fn set_register(&mut self, register: usize, value: u16) {
+        self.registers[register] = value;
+    }
+
+    pub fn program_counter(&self) -> u16 {
+        self.program_counter
+    }
+
+    pub fn set_program_counter(&mut self, address: u16) {
+        self.program_counter = address;
+    }
+
+    pub fn processor_status_register(&self) -> u16 {
+        self.processor_status_register
+    }
+
+    pub fn memory(&self) -> &Memory {
+        &self.memory
+    }
+
+    pub fn memory_mut(&mut self) -> &mut Memory {
+        &mut self.memory
+    }
+
+    pub fn engine(&self) -> Engine {
+        self.engine
+    }
+
+    pub fn set_engine(&mut self, engine: Engine) {
+        self.engine = engine;
+    }
+
+    pub fn access_control(&self) -> bool {
+        self.access_control
+    }
+
+    // Disabling access control lets user mode programs touch any address, as some courses expect
+    pub fn set_access_control(&mut self, enabled: bool) {
+        self.access_control = enabled;
+    }
+
+    // Replaces the routine a trap vector runs, receiving the registers and memory. Returns the
+    // handler it replaces. Handlers only run while no OS image is loaded, the image's trap
+    // vector table serves every trap after load_os_image. Semihosting, once enabled, serves
+    // x30 to x35 ahead of any handler. Registering a handler that could never run is an error
+    pub fn register_trap(
+        &mut self,
+        vector: u8,
+        handler: impl TrapHandler + 'static,
+    ) -> Result<Option<Box<dyn TrapHandler>>, String> {
+        if !self.native_traps {
+            return Err(format!(
+                "trap x{:02X} is served by the loaded OS image",
+                vector
+            ));
+        }
+
+        #[cfg(feature = "std")]
+        if self.semihost.is_some() && semihost::handles(vector) {
+            return Err(format!("trap x{:02X} is served by semihosting", vector));
+        }
+
+        Ok(self.traps.register(vector, handler))
+    }
+
+    pub fn traps_mut(&mut self) -> &mut TrapTable {
+        &mut self.traps
+    }
+
+    // Attaches an observer, called back in the order observers were added
+    pub fn add_observer(&mut self, observer: impl Observer + 'static) {
+        self.observers.push(Box::new(observer));
+    }
+
+    pub fn take_observers(&mut self) -> Vec<Box<dyn Observer>> {
+        core::mem::take(&mut self.observers)
+    }
+
+    // Services the extended trap vectors in Rust, with or without an OS image loaded and ahead
+    // of any handlers registered for them
+    #[cfg(feature = "std")]
+    pub fn set_semihost(&mut self, semihost: Semihost) {
+        self.semihost = Some(semihost);
+    }
+
+    // Status the program passed when it exited through the semihosting exit trap
+    #[cfg(feature = "std")]
+    pub fn exit_status(&self) -> Option<u16> {
+        self.semihost.as_ref().and_then(Semihost::exit_status)
+    }
+
+    // Saves the registers and memory, costing a pointer per allocated page
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            registers: self.registers,
+            program_counter: self.program_counter,
+            processor_status_register: self.processor_status_register,
+            state: self.state,
+            instruction_count: self.instruction_count,
+            saved_user_stack_pointer: self.saved_user_stack_pointer,
+            saved_supervisor_stack_pointer: self.saved_supervisor_stack_pointer,
+            memory: self.memory.snapshot(),
+        }
+    }
+
+    // Goes back to a snapshot. The console, devices, traps and observers are left as they are
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.registers = snapshot.registers;
+        self.program_counter = snapshot.program_counter;
+        self.processor_status_register = snapshot.processor_status_register;
+        self.state = snapshot.state;
+        self.instruction_count = snapshot.instruction_count;
+        self.saved_user_stack_pointer = snapshot.saved_user_stack_pointer;
+        self.saved_supervisor_stack_pointer = snapshot.saved_supervisor_stack_pointer;
+        self.memory.restore(&snapshot.memory);
+        self.memory.set_instruction_count(self.instruction_count);
+        self.branch_taken = None;
+    }
+
+    // A second machine continuing from the current state with its own console, for exploring
+    // other inputs. Memory is shared until written. Devices, semihosting, observers and
+    // replaced trap handlers stay with this CPU, the fork starts with the standard routines.
+    // A machine waiting for input continues reading from the new console
+    pub fn fork(&self, console: Box<dyn Console>) -> CPU {
+        let mut fork: CPU = CPU::with_console(console);
+
+        fork.restore(&self.snapshot());
+        fork.native_traps = self.native_traps;
+        fork.access_control = self.access_control;
+        fork.engine = self.engine;
+        fork.resume();
+        fork
+    }
+
+    // Whether the last executed instruction was a BR that was taken
+    pub fn branch_taken(&self) -> Option<bool> {
+        self.branch_taken
     }
 
     fn tick(&mut self) {
-        let curr_op: u16 = self.memory.read(self.program_counter);
+        self.branch_taken = None;
+
+        let address: u16 = self.program_counter;
         self.program_counter = self.program_counter.wrapping_add(1);
+
+        if self.access_violation(address) {
+            self.raise(Exception::AccessControlViolation);
+            return;
+        }
+        let curr_op: u16 = self.memory.read(address);
         let op_code: u16 = curr_op >> 12;
 
         match op_code {
@@ -88,14 +450,144 @@ impl CPU {
             0xC => self.jump(curr_op),
             0x4 => self.jump_register(curr_op),
             0xF => self.trap(curr_op),
+            0x8 => self.return_from_interrupt(),
             // Reserved instruction
-            0xD => unimplemented!("Reserved operation"),
+            0xD => self.raise(Exception::IllegalOpCode),
 
             // Any other op code
             _ => unreachable!("Bad op code"),
         }
     }
 
+    fn tick_predecoded(&mut self) {
+        self.branch_taken = None;
+
+        let address: u16 = self.program_counter;
+        self.program_counter = self.program_counter.wrapping_add(1);
+
+        if self.access_violation(address) {
+            self.raise(Exception::AccessControlViolation);
+            return;
+        }
+        let instruction: Instruction = self.memory.fetch(address);
+
+        match instruction {
+            Instruction::Add { dst, src1, src2 } => {
+                let result: u16 =
+                    self.registers[src1 as usize].wrapping_add(self.registers[src2 as usize]);
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::AddImmediate {
+                dst,
+                src1,
+                immediate,
+            } => {
+                let result: u16 = self.registers[src1 as usize].wrapping_add(immediate);
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::And { dst, src1, src2 } => {
+                let result: u16 = self.registers[src1 as usize] & self.registers[src2 as usize];
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::AndImmediate {
+                dst,
+                src1,
+                immediate,
+            } => {
+                let result: u16 = self.registers[src1 as usize] & immediate;
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::Not { dst, src } => {
+                let result: u16 = !self.registers[src as usize];
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::Load { dst, offset } => {
+                let Some(result) = self.load_word(self.program_counter.wrapping_add(offset)) else {
+                    return;
+                };
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::LoadIndirect { dst, offset } => {
+                let Some(memory_address) =
+                    self.load_word(self.program_counter.wrapping_add(offset))
+                else {
+                    return;
+                };
+                let Some(result) = self.load_word(memory_address) else {
+                    return;
+                };
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::LoadOffset { dst, base, offset } => {
+                let Some(result) =
+                    self.load_word(self.registers[base as usize].wrapping_add(offset))
+                else {
+                    return;
+                };
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::LoadImmediate { dst, offset } => {
+                let result: u16 = self.program_counter.wrapping_add(offset);
+                self.registers[dst as usize] = result;
+                self.set_condition_codes(result);
+            }
+            Instruction::Store { src, offset } => {
+                self.store_word(
+                    self.program_counter.wrapping_add(offset),
+                    self.registers[src as usize],
+                );
+            }
+            Instruction::StoreIndirect { src, offset } => {
+                let Some(memory_address) =
+                    self.load_word(self.program_counter.wrapping_add(offset))
+                else {
+                    return;
+                };
+                self.store_word(memory_address, self.registers[src as usize]);
+            }
+            Instruction::StoreOffset { src, base, offset } => {
+                self.store_word(
+                    self.registers[base as usize].wrapping_add(offset),
+                    self.registers[src as usize],
+                );
+            }
+            Instruction::Branch {
+                condition_codes,
+                offset,
+            } => {
+                let taken: bool = condition_codes & self.processor_status_register & 0x0007 != 0;
+
+                if taken {
+                    self.program_counter = self.program_counter.wrapping_add(offset);
+                }
+                self.branch_taken = Some(taken);
+            }
+            Instruction::Jump { base } => {
+                self.program_counter = self.registers[base as usize];
+            }
+            Instruction::JumpSubroutine { offset } => {
+                self.registers[7] = self.program_counter;
+                self.program_counter = self.program_counter.wrapping_add(offset);
+            }
+            Instruction::JumpSubroutineRegister { base } => {
+                let target: u16 = self.registers[base as usize];
+                self.registers[7] = self.program_counter;
+                self.program_counter = target;
+            }
+            Instruction::Trap { vector } => self.trap(0xF000 | vector as u16),
+            Instruction::Reserved => self.raise(Exception::IllegalOpCode),
+            Instruction::ReturnFromInterrupt => self.return_from_interrupt(),
+        }
+    }
+
     fn not(&mut self, operation: u16) {
         let dst: u8 = ((operation & 0x0E00) >> 9) as u8;
         let src: u8 = ((operation & 0x01C0) >> 6) as u8;
@@ -118,7 +610,7 @@ impl CPU {
                 self.set_condition_codes(result);
             }
             0x2 => {
-                let immediate: u16 = self.sign_extension(operation & 0x001F, 5);
+                let immediate: u16 = sign_extension(operation & 0x001F, 5);
                 let result: u16 = self.registers[src1 as usize].wrapping_add(immediate);
                 self.registers[dst as usize] = result;
                 self.set_condition_codes(result);
@@ -140,7 +632,7 @@ impl CPU {
                 self.set_condition_codes(result);
             }
             0x2 => {
-                let immediate: u16 = self.sign_extension(operation & 0x001F, 5);
+                let immediate: u16 = sign_extension(operation & 0x001F, 5);
                 let result: u16 = self.registers[src1 as usize] & immediate;
                 self.registers[dst as usize] = result;
                 self.set_condition_codes(result);
@@ -151,10 +643,12 @@ impl CPU {
 
     fn load(&mut self, operation: u16) {
         let dst: u8 = ((operation & 0x0E00) >> 9) as u8;
-        let signed_extension: u16 = self.sign_extension(operation & 0x01FF, 9);
+        let signed_extension: u16 = sign_extension(operation & 0x01FF, 9);
 
         let memory_address: u16 = self.program_counter.wrapping_add(signed_extension);
-        let result: u16 = self.memory.read(memory_address);
+        let Some(result) = self.load_word(memory_address) else {
+            return;
+        };
 
         self.registers[dst as usize] = result;
         self.set_condition_codes(result);
@@ -162,11 +656,15 @@ impl CPU {
 
     fn load_indirect(&mut self, operation: u16) {
         let dst: u8 = ((operation & 0x0E00) >> 9) as u8;
-        let signed_extension: u16 = self.sign_extension(operation & 0x01FF, 9);
+        let signed_extension: u16 = sign_extension(operation & 0x01FF, 9);
 
         let indirect_memory_address: u16 = self.program_counter.wrapping_add(signed_extension);
-        let memory_address: u16 = self.memory.read(indirect_memory_address);
-        let result: u16 = self.memory.read(memory_address);
+        let Some(memory_address) = self.load_word(indirect_memory_address) else {
+            return;
+        };
+        let Some(result) = self.load_word(memory_address) else {
+            return;
+        };
 
         self.registers[dst as usize] = result;
         self.set_condition_codes(result);
@@ -175,18 +673,20 @@ impl CPU {
     fn load_offset(&mut self, operation: u16) {
         let dst: u8 = ((operation & 0x0E00) >> 9) as u8;
         let base: u8 = ((operation & 0x01C0) >> 6) as u8;
-        let offset: u16 = self.sign_extension(operation & 0x003F, 6);
+        let offset: u16 = sign_extension(operation & 0x003F, 6);
 
         let memory_address: u16 = self.registers[base as usize].wrapping_add(offset);
 
-        let result: u16 = self.memory.read(memory_address);
+        let Some(result) = self.load_word(memory_address) else {
+            return;
+        };
         self.registers[dst as usize] = result;
         self.set_condition_codes(result);
     }
 
     fn load_immediate(&mut self, operation: u16) {
         let dst: u8 = ((operation & 0x0E00) >> 9) as u8;
-        let signed_extension: u16 = self.sign_extension(operation & 0x01FF, 9);
+        let signed_extension: u16 = sign_extension(operation & 0x01FF, 9);
 
         let memory_address: u16 = self.program_counter.wrapping_add(signed_extension);
 
@@ -196,46 +696,46 @@ impl CPU {
 
     fn store(&mut self, operation: u16) {
         let src: u8 = ((operation & 0x0E00) >> 9) as u8;
-        let signed_extension: u16 = self.sign_extension(operation & 0x01FF, 9);
+        let signed_extension: u16 = sign_extension(operation & 0x01FF, 9);
 
         let memory_address: u16 = self.program_counter.wrapping_add(signed_extension);
 
-        self.memory
-            .write(memory_address, self.registers[src as usize]);
+        self.store_word(memory_address, self.registers[src as usize]);
     }
 
     fn store_indirect(&mut self, operation: u16) {
         let src: u8 = ((operation & 0x0E00) >> 9) as u8;
-        let signed_extension: u16 = self.sign_extension(operation & 0x01FF, 9);
+        let signed_extension: u16 = sign_extension(operation & 0x01FF, 9);
 
         let memory_address: u16 = self.program_counter.wrapping_add(signed_extension);
-        let memory_address_content: u16 = self.memory.read(memory_address);
+        let Some(memory_address_content) = self.load_word(memory_address) else {
+            return;
+        };
 
-        self.memory
-            .write(memory_address_content, self.registers[src as usize]);
+        self.store_word(memory_address_content, self.registers[src as usize]);
     }
 
     fn store_offset(&mut self, operation: u16) {
         let src: u8 = ((operation & 0x0E00) >> 9) as u8;
         let base: u8 = ((operation & 0x01C0) >> 6) as u8;
-        let offset: u16 = self.sign_extension(operation & 0x003F, 6);
+        let offset: u16 = sign_extension(operation & 0x003F, 6);
 
         let memory_address: u16 = self.registers[base as usize].wrapping_add(offset);
 
-        self.memory
-            .write(memory_address, self.registers[src as usize]);
+        self.store_word(memory_address, self.registers[src as usize]);
     }
 
     fn branch(&mut self, operation: u16) {
         let op_condition_codes: u8 = ((operation & 0x0E00) >> 9) as u8;
-        let offset: u16 = self.sign_extension(operation & 0x01FF, 9);
+        let offset: u16 = sign_extension(operation & 0x01FF, 9);
         let condition_codes: u8 = (self.processor_status_register & 0x0007) as u8;
 
         match op_condition_codes & condition_codes {
             result if result > 0 => {
                 self.program_counter = self.program_counter.wrapping_add(offset);
+                self.branch_taken = Some(true);
             }
-            _ => {}
+            _ => self.branch_taken = Some(false),
         }
     }
 
@@ -247,98 +747,212 @@ impl CPU {
 
     fn jump_register(&mut self, operation: u16) {
         let flag: u8 = ((operation & 0x0800) >> 11) as u8;
-        self.registers[7] = self.program_counter;
+        let return_address: u16 = self.program_counter;
 
         match flag {
             0 => {
+                // The base is read before R7 is overwritten, so JSRR R7 jumps to the old R7
                 let base: u8 = ((operation & 0x01C0) >> 6) as u8;
                 self.program_counter = self.registers[base as usize];
             }
             _ => {
-                let offset: u16 = self.sign_extension(operation & 0x07FF, 11);
+                let offset: u16 = sign_extension(operation & 0x07FF, 11);
                 self.program_counter = self.program_counter.wrapping_add(offset);
             }
         }
+
+        self.registers[7] = return_address;
+    }
+
+    fn return_from_interrupt(&mut self) {
+        if self.user_mode() {
+            self.raise(Exception::PrivilegeModeViolation);
+            return;
+        }
+
+        // Pop the program counter and then the processor status off the supervisor stack
+        self.program_counter = self.memory.read(self.registers[6]);
+        self.registers[6] = self.registers[6].wrapping_add(1);
+        self.processor_status_register = self.memory.read(self.registers[6]);
+        self.registers[6] = self.registers[6].wrapping_add(1);
+
+        if self.user_mode() {
+            // Returning to user mode switches back to the user stack
+            self.saved_supervisor_stack_pointer = self.registers[6];
+            self.registers[6] = self.saved_user_stack_pointer;
+        }
     }
 
     fn trap(&mut self, operation: u16) {
         let trap_vect: u8 = (operation & 0x00FF) as u8;
 
-        match trap_vect {
-            0x20 => {
-                let mut buffer: [u8; 1] = [0 as u8; 1];
-                std::io::stdin().read_exact(&mut buffer).unwrap();
+        self.notify(|observer, cpu| observer.trap_entry(cpu, trap_vect));
 
-                self.registers[0] = buffer[0] as u16;
-            }
-            0x21 => {
-                print!("{}", (self.registers[0] as u8) as char);
-            }
-            0x22 => {
-                let mut index = self.registers[0];
-                let mut char_value: u16 = self.memory.read(index);
-
-                while char_value != 0 {
-                    print!("{}", (char_value as u8) as char);
-                    index = index + 1;
-                    char_value = self.memory.read(index);
-                }
+        #[cfg(feature = "std")]
+        let restricted: bool = self.access_control && self.user_mode();
+        #[cfg(feature = "std")]
+        if let Some(semihost) = self
+            .semihost
+            .as_mut()
+            .filter(|_| semihost::handles(trap_vect))
+        {
+            semihost.call(trap_vect, &mut self.registers, &mut self.memory, restricted);
+
+            if trap_vect == semihost::TRAP_EXIT {
+                self.state = RunState::Halted;
             }
-            0x23 => {
-                print!("Please enter a character.");
-                let char_value: u16 = std::io::stdin()
-                    .bytes()
-                    .next()
-                    .and_then(|result| result.ok())
-                    .map(|byte| byte as u16)
-                    .unwrap();
-                self.registers[0] = char_value;
+            self.notify(|observer, cpu| observer.trap_exit(cpu, trap_vect));
+            return;
+        }
+
+        if !self.native_traps {
+            if self.user_mode() {
+                // As on the third edition LC-3, the routine runs in supervisor mode and returns with RTI
+                self.enter_service_routine(trap_vect as u16);
+                return;
             }
-            0x24 => {
-                let mut index: u16 = self.registers[0];
-                let mut char_bytes: u16 = self.memory.read(index);
 
-                while char_bytes != 0 {
-                    let low_char: char = ((char_bytes & 0x00FF) as u8) as char;
-                    print!("{}", low_char);
-                    let high_char: char = ((char_bytes & 0xFF00) as u8) as char;
+            // Jump through the trap vector table provided by the loaded OS image
+            self.registers[7] = self.program_counter;
+            self.program_counter = self.memory.read(trap_vect as u16);
+            return;
+        }
 
-                    if high_char != '\0' {
-                        print!("{}", high_char);
-                    }
+        let mut context: TrapContext = TrapContext::new(
+            &mut self.registers,
+            &mut self.memory,
+            &mut self.program_counter,
+            &mut self.state,
+        );
 
-                    index = index + 1;
-                    char_bytes = self.memory.read(index);
-                }
-            }
-            0x25 => {
-                print!("\nHALT\n");
-                self.program_counter = u16::MAX;
-            }
-            _ => unreachable!("Invalid trap vector."),
+        if !self.traps.call(trap_vect, &mut context) {
+            self.raise(Exception::UnknownTrap(trap_vect));
+            return;
+        }
+        self.notify(|observer, cpu| observer.trap_exit(cpu, trap_vect));
+    }
+
+    fn raise(&mut self, exception: Exception) {
+        if self.native_traps {
+            // Stop on the faulting instruction, there is no handler to run
+            self.program_counter = self.program_counter.wrapping_sub(1);
+            self.state = RunState::Fault(exception);
+            return;
+        }
+
+        self.enter_service_routine(INTERRUPT_VECTOR_TABLE.wrapping_add(exception.vector() as u16));
+    }
+
+    // Services a device interrupt between instructions, running the handler at its priority
+    fn interrupt(&mut self, priority: u16, vector: u8) {
+        self.enter_service_routine(INTERRUPT_VECTOR_TABLE.wrapping_add(vector as u16));
+        self.processor_status_register =
+            (self.processor_status_register & !0x0700) | (priority << 8);
+    }
+
+    // Priority level of the running program, bits 10 to 8 of the processor status register
+    fn priority(&self) -> u16 {
+        (self.processor_status_register >> 8) & 0x7
+    }
+
+    // Pushes the processor status and program counter onto the supervisor stack and jumps
+    // to the routine whose address is stored in `vector_address`
+    fn enter_service_routine(&mut self, vector_address: u16) {
+        let processor_status_register: u16 = self.processor_status_register;
+
+        if self.user_mode() {
+            self.saved_user_stack_pointer = self.registers[6];
+            self.registers[6] = self.saved_supervisor_stack_pointer;
+        }
+
+        self.registers[6] = self.registers[6].wrapping_sub(1);
+        self.memory
+            .write(self.registers[6], processor_status_register);
+        self.registers[6] = self.registers[6].wrapping_sub(1);
+        self.memory.write(self.registers[6], self.program_counter);
+
+        self.processor_status_register &= 0x7FFF;
+        self.program_counter = self.memory.read(vector_address);
+    }
+
+    // Reads memory on behalf of the running program, None if an exception was raised instead
+    fn load_word(&mut self, address: u16) -> Option<u16> {
+        if self.access_violation(address) {
+            self.raise(Exception::AccessControlViolation);
+            return None;
         }
+
+        let value: u16 = self.memory.read(address);
+
+        self.notify(|observer, cpu| observer.memory_read(cpu, address, value));
+        Some(value)
     }
 
-    fn sign_extension(&self, mut bits: u16, bit_count: usize) -> u16 {
-        if (bits >> (bit_count - 1)) & 1 == 1 {
-            bits |= 0xFFFF << bit_count;
+    fn store_word(&mut self, address: u16, value: u16) {
+        if self.access_violation(address) {
+            self.raise(Exception::AccessControlViolation);
+            return;
         }
 
-        return bits;
+        self.memory.write(address, value);
+        self.notify(|observer, cpu| observer.memory_write(cpu, address, value));
+    }
+
+    // Calls every attached observer, costing no more than a length check while there are none
+    #[inline]
+    fn notify(&mut self, event: impl Fn(&mut dyn Observer, &CPU)) {
+        if self.observers.is_empty() {
+            return;
+        }
+
+        // Observers see the whole machine, so they are set aside while being called
+        let mut observers: Vec<Box<dyn Observer>> = core::mem::take(&mut self.observers);
+        for observer in observers.iter_mut() {
+            event(observer.as_mut(), self);
+        }
+        self.observers = observers;
+    }
+
+    fn access_violation(&self, address: u16) -> bool {
+        self.access_control && self.user_mode() && memory::privileged(address)
+    }
+
+    // Bit 15 of the processor status register is set while running in user mode
+    fn user_mode(&self) -> bool {
+        self.processor_status_register & 0x8000 != 0
     }
 
     fn set_condition_codes(&mut self, result: u16) {
-        self.processor_status_register = self.processor_status_register & 0xFFF8;
+        self.processor_status_register &= 0xFFF8;
 
         match result {
             x if (x >> 15) == 1 => self.processor_status_register |= 0b100,
-            x if x == 0 => self.processor_status_register |= 0b010,
+            0 => self.processor_status_register |= 0b010,
             _ => self.processor_status_register |= 0b001,
         };
     }
 }
 
+#[cfg(feature = "std")]
+impl Default for CPU {
+    fn default() -> Self {
+        CPU::new()
+    }
+}
+
 // Points to test file instead of directly testing here
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 #[path = "./cpu_test.rs"]
 mod cpu_test;
+
+#[cfg(all(test, feature = "std"))]
+#[path = "./conformance_test.rs"]
+mod conformance_test;
+
+#[cfg(all(test, feature = "std"))]
+#[path = "./differential_test.rs"]
+mod differential_test;
+
+#[cfg(test)]
+#[path = "./core_test.rs"]
+mod core_test;
@@ -1,4 +1,12 @@
-use crate::cpu::CPU;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    assembler::assemble,
+    console::{BufferedConsole, Console},
+    cpu::{Engine, Exception, RunState, Snapshot, CPU},
+    instruction::sign_extension,
+    memory::{DDR, MCR},
+};
 
 //
 // Initialization
@@ -224,6 +232,24 @@ fn test_branch_operation() {
     assert_eq!(cpu.program_counter, 0x3010);
 }
 
+#[test]
+fn test_branch_outcome() {
+    let mut cpu: CPU = CPU::new();
+    cpu.processor_status_register = 0x2;
+
+    cpu.memory.write(0x3000, 0b0000_1000_0000_0001); // BRn, not taken
+    cpu.memory.write(0x3001, 0b0000_0100_0000_0001); // BRz, taken
+    cpu.memory.write(0x3003, 0b0001_0000_0010_0001); // ADD
+
+    cpu.tick();
+    assert_eq!(cpu.branch_taken(), Some(false));
+    cpu.tick();
+    assert_eq!(cpu.branch_taken(), Some(true));
+    assert_eq!(cpu.program_counter, 0x3003);
+    cpu.tick();
+    assert_eq!(cpu.branch_taken(), None);
+}
+
 #[test]
 fn test_jump_operation() {
     let mut cpu: CPU = CPU::new();
@@ -337,13 +363,15 @@ fn test_trap_halt_operation() {
 }
 
 #[test]
-#[should_panic]
 fn test_trap_invalid_operation() {
     let mut cpu: CPU = CPU::new();
     let operation: u16 = 0b1111_0000_1111_1111;
 
     cpu.memory.write(0x3000, operation);
     cpu.tick();
+
+    assert_eq!(cpu.state, RunState::Fault(Exception::UnknownTrap(0xFF)));
+    assert_eq!(cpu.program_counter, 0x3000);
 }
 
 //
@@ -352,9 +380,8 @@ fn test_trap_invalid_operation() {
 
 #[test]
 fn test_signed_extension() {
-    let cpu: CPU = CPU::new();
-    let neg_bits: u16 = cpu.sign_extension(0b1_1101, 5) as u16;
-    let pos_bits: u16 = cpu.sign_extension(0b01001_1101, 9) as u16;
+    let neg_bits: u16 = sign_extension(0b1_1101, 5);
+    let pos_bits: u16 = sign_extension(0b0_1001_1101, 9);
 
     assert_eq!(neg_bits, 0b1111_1111_1111_1101);
     assert_eq!(pos_bits, 0b0000_0000_1001_1101);
@@ -376,3 +403,462 @@ fn test_update_condition_codes() {
 
     assert_eq!(cpu.processor_status_register, 0xFFF2);
 }
+
+//
+// Execution
+//
+
+#[test]
+fn test_load_image() {
+    let mut cpu: CPU = CPU::new();
+    let origin: u16 = cpu.load_image(&[0x40, 0x00, 0x12, 0x34]).unwrap();
+
+    assert_eq!(origin, 0x4000);
+    assert_eq!(cpu.program_counter, 0x4000);
+    assert_eq!(cpu.memory.read(0x4000), 0x1234);
+    assert!(cpu.load_image(&[0x30]).is_err());
+    assert!(cpu.load_image(&[]).is_err());
+}
+
+#[test]
+fn test_run_until_halt() {
+    let console: BufferedConsole = BufferedConsole::new(&[]);
+    let output = console.output();
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+
+    cpu.execute_program("resources/hello-world.obj");
+
+    assert_eq!(cpu.state(), RunState::Halted);
+    assert_eq!(cpu.instruction_count(), 3);
+    assert_eq!(output.borrow().as_slice(), b"Hello World!\nHALT\n");
+    assert_eq!(cpu.step(), RunState::Halted);
+    assert_eq!(cpu.instruction_count(), 3);
+}
+
+#[test]
+fn test_run_with_limit() {
+    let mut cpu: CPU = CPU::new();
+    let operation: u16 = 0b0000_1111_1111_1111; // BRnzp to itself
+
+    cpu.processor_status_register = 0x2;
+    cpu.memory.write(0x3000, operation);
+
+    assert_eq!(cpu.run(Some(10)), RunState::Running);
+    assert_eq!(cpu.instruction_count(), 10);
+    assert_eq!(cpu.program_counter, 0x3000);
+}
+
+#[test]
+fn test_trap_input_exhausted() {
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(b"a")));
+    let operation: u16 = 0b1111_0000_0010_0000;
+
+    cpu.memory.write(0x3000, operation);
+    cpu.memory.write(0x3001, operation);
+
+    assert_eq!(cpu.run(None), RunState::InputExhausted);
+    assert_eq!(cpu.registers[0], b'a' as u16);
+    assert_eq!(cpu.program_counter, 0x3001);
+}
+
+#[test]
+fn test_machine_control_halts() {
+    let mut cpu: CPU = CPU::new();
+    let operation: u16 = 0b1011_0000_0000_0001; // STI R0 through x3002
+
+    cpu.memory.write(0x3000, operation);
+    cpu.memory.write(0x3002, MCR);
+
+    assert_eq!(cpu.run(Some(10)), RunState::Halted);
+    assert_eq!(cpu.instruction_count(), 1);
+}
+
+#[test]
+fn test_os_image_traps() {
+    let mut cpu: CPU = CPU::new();
+
+    // Vector table entry for HALT pointing at x0400
+    cpu.load_os_image(&[0x00, 0x25, 0x04, 0x00]).unwrap();
+    cpu.memory.write(0x3000, 0b1111_0000_0010_0101);
+    cpu.step();
+
+    assert_eq!(cpu.program_counter, 0x0400);
+    assert_eq!(cpu.registers[7], 0x3001);
+    assert_eq!(cpu.state(), RunState::Running);
+}
+
+#[test]
+fn test_illegal_op_code_fault() {
+    for engine in [Engine::Interpreter, Engine::Predecoded] {
+        let mut cpu: CPU = CPU::new();
+        cpu.set_engine(engine);
+        cpu.memory.write(0x3000, 0b1101_0000_0000_0000);
+
+        assert_eq!(cpu.run(Some(10)), RunState::Fault(Exception::IllegalOpCode));
+        assert_eq!(cpu.program_counter, 0x3000);
+        assert_eq!(cpu.instruction_count(), 1);
+    }
+}
+
+#[test]
+fn test_illegal_op_code_handler() {
+    let mut cpu: CPU = CPU::new();
+
+    // Handler at x0400 that skips the faulting instruction and returns to user mode
+    cpu.load_os_image(&[0x01, 0x01, 0x04, 0x00]).unwrap();
+    cpu.memory.write(0x0400, 0b1000_0000_0000_0000); // RTI
+    cpu.memory.write(0x3000, 0b1101_0000_0000_0000);
+    cpu.memory.write(0x3001, 0b0001_0000_0010_0001); // ADD R0, R0, #1
+    cpu.processor_status_register = 0x8002;
+    cpu.registers[6] = 0xFDFF;
+
+    cpu.step();
+    assert_eq!(cpu.program_counter, 0x0400);
+    assert_eq!(cpu.processor_status_register, 0x0002);
+    assert_eq!(cpu.registers[6], 0x2FFE);
+
+    cpu.step();
+    cpu.step();
+    assert_eq!(cpu.registers[0], 1);
+    assert_eq!(cpu.registers[6], 0xFDFF);
+    assert_eq!(cpu.processor_status_register, 0x8001);
+    assert_eq!(cpu.state(), RunState::Running);
+}
+
+#[test]
+fn test_access_control_violation() {
+    for engine in [Engine::Interpreter, Engine::Predecoded] {
+        let mut cpu: CPU = CPU::new();
+        cpu.set_engine(engine);
+        cpu.processor_status_register = 0x8002;
+        cpu.memory.write(0x2000, 0x1234);
+        cpu.memory.write(0x3000, 0b0010_0001_1111_1111); // LD R0, #-1 reads x3000
+        cpu.memory.write(0x3001, 0b0110_0000_0100_0000); // LDR R0, R1, #0
+
+        cpu.registers[1] = 0x2000;
+        cpu.step();
+        assert_eq!(cpu.registers[0], 0x21FF);
+
+        // System space
+        assert_eq!(
+            cpu.run(Some(10)),
+            RunState::Fault(Exception::AccessControlViolation)
+        );
+        assert_eq!(cpu.program_counter, 0x3001);
+        assert_eq!(cpu.registers[0], 0x21FF);
+    }
+}
+
+#[test]
+fn test_access_control_io_page_and_fetch() {
+    let console: BufferedConsole = BufferedConsole::new(&[]);
+    let output = console.output();
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    cpu.processor_status_register = 0x8002;
+    cpu.registers[0] = b'!' as u16;
+    cpu.registers[1] = DDR;
+    cpu.memory.write(0x3000, 0b0111_0000_0100_0000); // STR R0, R1, #0
+
+    assert_eq!(
+        cpu.run(Some(10)),
+        RunState::Fault(Exception::AccessControlViolation)
+    );
+    assert!(output.borrow().is_empty());
+
+    // Instructions cannot be fetched from system space either
+    let mut cpu: CPU = CPU::new();
+    cpu.processor_status_register = 0x8002;
+    cpu.program_counter = 0x0200;
+
+    assert_eq!(
+        cpu.run(Some(10)),
+        RunState::Fault(Exception::AccessControlViolation)
+    );
+    assert_eq!(cpu.program_counter, 0x0200);
+}
+
+#[test]
+fn test_access_control_permissive() {
+    let mut cpu: CPU = CPU::new();
+    cpu.set_access_control(false);
+    cpu.processor_status_register = 0x8002;
+    cpu.registers[1] = 0x2000;
+    cpu.memory.write(0x2000, 0x1234);
+    cpu.memory.write(0x3000, 0b0110_0000_0100_0000); // LDR R0, R1, #0
+
+    assert_eq!(cpu.step(), RunState::Running);
+    assert_eq!(cpu.registers[0], 0x1234);
+}
+
+#[test]
+fn test_access_control_handler() {
+    let mut cpu: CPU = CPU::new();
+
+    cpu.load_os_image(&[0x01, 0x02, 0x05, 0x00]).unwrap();
+    cpu.processor_status_register = 0x8001;
+    cpu.registers[6] = 0xFDFF;
+    cpu.memory.write(0x3000, 0b0011_0001_0000_0000); // ST R0, #-256 writes x2F01
+    cpu.step();
+
+    assert_eq!(cpu.program_counter, 0x0500);
+    assert_eq!(cpu.processor_status_register, 0x0001);
+    assert_eq!(cpu.registers[6], 0x2FFE);
+    assert_eq!(cpu.memory.read(0x2FFE), 0x3001);
+    assert_eq!(cpu.memory.read(0x2FFF), 0x8001);
+}
+
+#[test]
+fn test_user_mode_trap() {
+    let mut cpu: CPU = CPU::new();
+
+    cpu.load_os_image(&[0x00, 0x25, 0x04, 0x00]).unwrap();
+    cpu.processor_status_register = 0x8004;
+    cpu.registers[6] = 0xFDFF;
+    cpu.memory.write(0x3000, 0b1111_0000_0010_0101);
+    cpu.step();
+
+    // The service routine runs in supervisor mode on the supervisor stack
+    assert_eq!(cpu.program_counter, 0x0400);
+    assert_eq!(cpu.processor_status_register, 0x0004);
+    assert_eq!(cpu.registers[6], 0x2FFE);
+    assert_eq!(cpu.memory.read(0x2FFE), 0x3001);
+    assert_eq!(cpu.memory.read(0x2FFF), 0x8004);
+}
+
+#[test]
+fn test_engines_agree() {
+    let program = assemble(
+        "
+        .ORIG x3000
+        LEA R6, DATA
+        AND R0, R0, #0
+        ADD R1, R0, #10
+LOOP    LDR R2, R6, #0
+        ADD R0, R0, R2
+        NOT R3, R0
+        STR R3, R6, #1
+        LDI R4, POINTER
+        ADD R1, R1, #-1
+        BRp LOOP
+        JSR DONE
+        HALT
+DONE    ST R0, DATA
+        RET
+DATA    .FILL #7
+        .BLKW 1
+POINTER .FILL DATA
+        .END
+        ",
+    )
+    .unwrap();
+    let mut states: Vec<([u16; 8], u16, u16, u64)> = Vec::new();
+
+    for engine in [Engine::Interpreter, Engine::Predecoded] {
+        let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(&[])));
+        cpu.set_engine(engine);
+        cpu.load_image(&program.to_object()).unwrap();
+
+        assert_eq!(cpu.run(Some(1000)), RunState::Halted);
+        states.push((
+            cpu.registers,
+            cpu.processor_status_register,
+            cpu.memory.read(program.symbols["DATA"]),
+            cpu.instruction_count(),
+        ));
+    }
+
+    assert_eq!(states[0], states[1]);
+    assert_eq!(states[0].2, 70);
+}
+
+#[test]
+fn test_predecoded_self_modifying_code() {
+    let mut cpu: CPU = CPU::new();
+    cpu.set_engine(Engine::Predecoded);
+
+    cpu.memory.write(0x3000, 0b0001_0000_0010_0001); // ADD R0, R0, #1
+    cpu.memory.write(0x3001, 0b0000_1111_1111_1110); // BRnzp x3000
+
+    cpu.run(Some(4));
+    assert_eq!(cpu.registers[0], 2);
+    assert_eq!(cpu.program_counter, 0x3000);
+
+    // Overwrite the cached ADD #1 with ADD #5
+    cpu.memory.write(0x3000, 0b0001_0000_0010_0101);
+    cpu.step();
+
+    assert_eq!(cpu.registers[0], 7);
+}
+
+#[test]
+fn test_timer_interrupt() {
+    let program = assemble(
+        "
+        .ORIG x3000
+        LD R0, HANDLER      ; Install the handler at vector x81
+        STI R0, VECTOR
+        LD R0, INTERVAL
+        STI R0, TIR
+        LD R0, CONTROL      ; Interrupts enabled at priority 4
+        STI R0, TSR
+        LD R0, USER         ; Drop to user mode and priority 0 through RTI
+        ADD R6, R6, #-1
+        STR R0, R6, #0
+        LEA R0, COUNT
+        ADD R6, R6, #-1
+        STR R0, R6, #0
+        RTI
+COUNT   ADD R1, R1, #1
+        BRnzp COUNT
+HANDLER .FILL TICK
+VECTOR  .FILL x0181
+INTERVAL .FILL #50
+CONTROL .FILL x4481
+USER    .FILL x8002
+TIR     .FILL xFE0A
+TSR     .FILL xFE08
+TICK    ADD R2, R2, #1
+        LDI R3, TSR         ; Acknowledge
+        RTI
+        .END
+        ",
+    )
+    .unwrap();
+
+    for engine in [Engine::Interpreter, Engine::Predecoded] {
+        let mut cpu: CPU = CPU::new();
+        cpu.set_engine(engine);
+        cpu.load_image(&program.to_object()).unwrap();
+        cpu.registers[6] = 0x3000;
+
+        assert_eq!(cpu.run(Some(1000)), RunState::Running);
+
+        // One interrupt per 50 instructions, the handler saw the ready bit
+        assert!((18..=20).contains(&cpu.registers[2]));
+        assert_eq!(cpu.registers[3] & 0xC0FF, 0xC081);
+
+        // The handler runs in supervisor mode at the timer's priority, on the supervisor stack
+        while cpu.program_counter != program.symbols["TICK"] {
+            cpu.step();
+        }
+        assert_eq!(cpu.processor_status_register & 0x8700, 0x0400);
+        assert_eq!(cpu.registers[6], 0x2FFE);
+    }
+}
+
+//
+// Snapshots
+//
+
+// Reads keys into a buffer until a newline, then halts
+const READ_LINE: &str = "
+        .ORIG x3000
+        LEA R1, BUFFER
+LOOP    GETC
+        STR R0, R1, #0
+        ADD R1, R1, #1
+        ADD R0, R0, #-10
+        BRnp LOOP
+        HALT
+BUFFER  .BLKW 16
+        .END
+";
+
+#[test]
+fn test_snapshot_restore() {
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(b"ab\ncd\n")));
+    cpu.load_image(&assemble(READ_LINE).unwrap().to_object())
+        .unwrap();
+    cpu.run(Some(1));
+
+    let snapshot: Snapshot = cpu.snapshot();
+    assert_eq!(snapshot.instruction_count(), 1);
+
+    assert_eq!(cpu.run(None), RunState::Halted);
+    assert_eq!(cpu.memory.peek(0x3007), b'a' as u16);
+
+    // Back to before the first key was stored, the rest of the input is read this time
+    cpu.restore(&snapshot);
+    assert_eq!(cpu.state(), RunState::Running);
+    assert_eq!(cpu.instruction_count(), 1);
+    assert_eq!(cpu.memory.peek(0x3007), 0);
+    assert_eq!(cpu.run(None), RunState::Halted);
+    assert_eq!(cpu.memory.peek(0x3007), b'c' as u16);
+    assert_eq!(cpu.memory.peek(0x3008), b'd' as u16);
+}
+
+#[test]
+fn test_fork() {
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(b"x")));
+    cpu.load_image(&assemble(READ_LINE).unwrap().to_object())
+        .unwrap();
+    assert_eq!(cpu.run(None), RunState::InputExhausted);
+
+    // Each fork explores a different ending from the same point
+    let mut forks: Vec<CPU> = [b"y\n", b"z\n"]
+        .map(|input| cpu.fork(Box::new(BufferedConsole::new(input))))
+        .into();
+    for fork in forks.iter_mut() {
+        assert_eq!(
+            fork.memory.pages().shared(),
+            fork.memory.pages().allocated()
+        );
+        assert_eq!(fork.run(None), RunState::Halted);
+    }
+
+    assert_eq!(forks[0].memory.peek(0x3008), b'y' as u16);
+    assert_eq!(forks[1].memory.peek(0x3008), b'z' as u16);
+    assert_eq!(cpu.memory.peek(0x3007), b'x' as u16);
+    assert_eq!(cpu.memory.peek(0x3008), 0);
+    assert_eq!(cpu.state(), RunState::InputExhausted);
+}
+
+// Takes a fixed number of bytes, then reports its output closed like stdout piped into `head`
+struct ClosingConsole {
+    written: Rc<RefCell<Vec<u8>>>,
+    capacity: usize,
+}
+
+impl Console for ClosingConsole {
+    fn read_byte(&mut self) -> Option<u8> {
+        None
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        if !self.closed() {
+            self.written.borrow_mut().push(byte);
+        }
+    }
+
+    fn closed(&self) -> bool {
+        self.written.borrow().len() >= self.capacity
+    }
+}
+
+#[test]
+fn test_output_closed() {
+    let program = assemble(
+        "
+        .ORIG x3000
+        LD R0, CHAR
+LOOP    OUT
+        STI R0, DDR
+        BRnzp LOOP
+CHAR    .FILL x61
+DDR     .FILL xFE06
+        .END
+        ",
+    )
+    .unwrap();
+    let written: Rc<RefCell<Vec<u8>>> = Rc::new(RefCell::new(Vec::new()));
+    let mut cpu: CPU = CPU::with_console(Box::new(ClosingConsole {
+        written: Rc::clone(&written),
+        capacity: 3,
+    }));
+    cpu.load_image(&program.to_object()).unwrap();
+
+    // The endless loop stops on the write that found nobody reading
+    assert_eq!(cpu.run(Some(1000)), RunState::Halted);
+    assert_eq!(*written.borrow(), b"aaa");
+    assert_eq!(cpu.instruction_count(), 5);
+}
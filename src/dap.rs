@@ -0,0 +1,771 @@
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    fs,
+    io::{self, BufRead, Write},
+    rc::Rc,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use lc_3::{
+    assembler::{assemble, Program, SymbolTable},
+    console::Console,
+    cpu::{RunState, CPU},
+    disassembler::label_at,
+};
+
+use crate::{
+    cli::load_program,
+    debugger::{parse_value, Flow},
+    json::{self, Json},
+};
+
+// Instructions executed between checks for requests while the program runs
+const SLICE: u64 = 20_000;
+
+// The machine is the protocol's only thread, its registers the only scope
+const THREAD: i64 = 1;
+const REGISTERS: i64 = 1;
+
+const RET: u16 = 0xC1C0;
+
+// Keys for the program come from the debug console, its output goes back as output events
+struct DapConsole {
+    keys: Rc<RefCell<VecDeque<u8>>>,
+    output: Rc<RefCell<Vec<u8>>>,
+}
+
+impl Console for DapConsole {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.keys.borrow_mut().pop_front()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.output.borrow_mut().push(byte);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Mode {
+    Continue,
+    // Run until the PC reaches the instruction after a subroutine call
+    StepOver(u16),
+    // Run until a RET leaves the current subroutine, counting the calls made on the way
+    StepOut(u32),
+}
+
+// Debug adapter serving one launched program. Requests are handled one at a time, while the
+// program runs they are checked for between slices of execution so it can be paused.
+pub struct Dap {
+    out: Box<dyn Write>,
+    seq: i64,
+    cpu: Option<CPU>,
+    symbols: SymbolTable,
+    // Assembled program and its canonical path, when launched from source
+    source: Option<(Program, String)>,
+    keys: Rc<RefCell<VecDeque<u8>>>,
+    output: Rc<RefCell<Vec<u8>>>,
+    line_breakpoints: BTreeSet<u16>,
+    // Lines asked for by setBreakpoints with their ids, per canonical path. Kept so breakpoints
+    // set before launch are resolved once the program is assembled
+    requested_lines: BTreeMap<String, Vec<(i64, i64)>>,
+    next_breakpoint_id: i64,
+    instruction_breakpoints: BTreeSet<u16>,
+    stop_on_entry: bool,
+    running: Option<Mode>,
+    // Where the running program was resumed from, a breakpoint there is stepped over rather
+    // than hit again. None for the run started by configurationDone, which stops right away
+    resumed_from: Option<u16>,
+    waiting_for_input: bool,
+    // Events that go out after the response to the current request
+    events: Vec<Json>,
+}
+
+impl Dap {
+    pub fn new(out: Box<dyn Write>) -> Self {
+        Dap {
+            out,
+            seq: 0,
+            cpu: None,
+            symbols: SymbolTable::new(),
+            source: None,
+            keys: Rc::new(RefCell::new(VecDeque::new())),
+            output: Rc::new(RefCell::new(Vec::new())),
+            line_breakpoints: BTreeSet::new(),
+            requested_lines: BTreeMap::new(),
+            next_breakpoint_id: 1,
+            instruction_breakpoints: BTreeSet::new(),
+            stop_on_entry: false,
+            running: None,
+            resumed_from: None,
+            waiting_for_input: false,
+            events: Vec::new(),
+        }
+    }
+
+    pub fn running(&self) -> bool {
+        self.running.is_some()
+    }
+
+    pub fn handle(&mut self, request: &Json) -> io::Result<Flow> {
+        let command: &str = request.get("command").as_str().unwrap_or("");
+        let arguments: &Json = request.get("arguments");
+
+        let result: Result<Json, String> = match command {
+            "initialize" => {
+                self.event("initialized", Json::Null);
+                Ok(Json::object([
+                    ("supportsConfigurationDoneRequest", true.into()),
+                    ("supportsInstructionBreakpoints", true.into()),
+                    ("supportsReadMemoryRequest", true.into()),
+                ]))
+            }
+            "launch" => self.launch(arguments),
+            "setBreakpoints" => self.set_breakpoints(arguments),
+            "setInstructionBreakpoints" => self.set_instruction_breakpoints(arguments),
+            "setExceptionBreakpoints" => Ok(Json::Null),
+            "configurationDone" => {
+                match self.stop_on_entry {
+                    true => self.stopped("entry", None),
+                    false => {
+                        self.running = Some(Mode::Continue);
+                        self.resumed_from = None;
+                    }
+                }
+                Ok(Json::Null)
+            }
+            "threads" => Ok(Json::object([(
+                "threads",
+                vec![Json::object([
+                    ("id", THREAD.into()),
+                    ("name", "LC-3".into()),
+                ])]
+                .into(),
+            )])),
+            "stackTrace" => self.stack_trace(),
+            "scopes" => Ok(Json::object([(
+                "scopes",
+                vec![Json::object([
+                    ("name", "Registers".into()),
+                    ("presentationHint", "registers".into()),
+                    ("variablesReference", REGISTERS.into()),
+                    ("expensive", false.into()),
+                ])]
+                .into(),
+            )])),
+            "variables" => self.variables(arguments),
+            "continue" => self
+                .resume(Mode::Continue)
+                .map(|_| Json::object([("allThreadsContinued", true.into())])),
+            "next" => self.next(),
+            "stepIn" => self.step_in(),
+            "stepOut" => self.resume(Mode::StepOut(0)).map(|_| Json::Null),
+            "pause" => {
+                if self.running.take().is_some() {
+                    self.stopped("pause", None);
+                }
+                Ok(Json::Null)
+            }
+            "readMemory" => self.read_memory(arguments),
+            "evaluate" => self.evaluate(arguments),
+            "disconnect" | "terminate" => {
+                self.respond(request, Ok(Json::Null))?;
+                return Ok(Flow::Quit);
+            }
+            _ => Err(format!("unsupported request '{}'", command)),
+        };
+
+        self.respond(request, result)?;
+        self.flush_output()?;
+
+        for event in std::mem::take(&mut self.events) {
+            self.send(event)?;
+        }
+        Ok(Flow::Continue)
+    }
+
+    // Runs the program for a while, stopping at breakpoints and the end of steps
+    pub fn run_slice(&mut self) -> io::Result<()> {
+        let Some(mut mode) = self.running else {
+            return Ok(());
+        };
+        let Some(cpu) = &mut self.cpu else {
+            return Ok(());
+        };
+        let mut stop: Option<&str> = None;
+
+        for _ in 0..SLICE {
+            let address: u16 = cpu.program_counter();
+
+            if self.resumed_from.take() != Some(address)
+                && (self.line_breakpoints.contains(&address)
+                    || self.instruction_breakpoints.contains(&address))
+            {
+                stop = Some("breakpoint");
+                break;
+            }
+
+            let operation: u16 = cpu.memory().peek(address);
+            let mut returned: bool = false;
+
+            if let Mode::StepOut(depth) = &mut mode {
+                match operation {
+                    RET if *depth == 0 => returned = true,
+                    RET => *depth -= 1,
+                    _ if operation >> 12 == 0x4 => *depth += 1,
+                    _ => {}
+                }
+            }
+
+            if cpu.step() != RunState::Running {
+                break;
+            }
+
+            if returned || mode == Mode::StepOver(cpu.program_counter()) {
+                stop = Some("step");
+                break;
+            }
+        }
+
+        self.running = Some(mode);
+        match (cpu.state(), stop) {
+            (RunState::Running, Some(reason)) => {
+                self.running = None;
+                self.stopped(reason, None);
+            }
+            (RunState::Running, None) => {}
+            (_, _) => {
+                self.running = None;
+                self.machine_stopped();
+            }
+        }
+
+        self.flush_output()?;
+        for event in std::mem::take(&mut self.events) {
+            self.send(event)?;
+        }
+        Ok(())
+    }
+
+    fn cpu(&mut self) -> Result<&mut CPU, String> {
+        self.cpu
+            .as_mut()
+            .ok_or_else(|| String::from("no program has been launched"))
+    }
+
+    fn launch(&mut self, arguments: &Json) -> Result<Json, String> {
+        let Some(path) = arguments.get("program").as_str() else {
+            return Err(String::from("launch needs a 'program' to debug"));
+        };
+
+        let image: Vec<u8> = match path.ends_with(".asm") {
+            true => {
+                let text: String = fs::read_to_string(path)
+                    .map_err(|error| format!("cannot read '{}': {}", path, error))?;
+                let program: Program =
+                    assemble(&text).map_err(|error| format!("{}: {}", path, error))?;
+                let image: Vec<u8> = program.to_object();
+
+                self.symbols = program.symbols.clone();
+                self.source = Some((program, canonical(path)));
+                image
+            }
+            false => {
+                let (image, symbols) = load_program(path).map_err(|error| error.to_string())?;
+                self.symbols = symbols;
+                image
+            }
+        };
+
+        let mut cpu: CPU = CPU::with_console(Box::new(DapConsole {
+            keys: self.keys.clone(),
+            output: self.output.clone(),
+        }));
+        cpu.load_image(&image)
+            .map_err(|error| format!("{}: {}", path, error))?;
+
+        if let Some(input) = arguments.get("input").as_str() {
+            self.keys.borrow_mut().extend(input.bytes());
+        }
+        self.stop_on_entry = arguments.get("stopOnEntry").as_bool().unwrap_or(false);
+        self.cpu = Some(cpu);
+
+        // Breakpoints set before the launch could not be resolved then, report them again
+        if let Some((_, source)) = &self.source {
+            let source: String = source.clone();
+            let requests: Vec<(i64, i64)> = self
+                .requested_lines
+                .get(&source)
+                .cloned()
+                .unwrap_or_default();
+
+            self.line_breakpoints.clear();
+            for (id, line) in requests {
+                let breakpoint: Json = self.line_breakpoint(id, &source, line);
+                self.event(
+                    "breakpoint",
+                    Json::object([("reason", "changed".into()), ("breakpoint", breakpoint)]),
+                );
+            }
+        }
+        Ok(Json::Null)
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Json) -> Result<Json, String> {
+        let path: String = canonical(arguments.get("source").get("path").as_str().unwrap_or(""));
+        let mut requests: Vec<(i64, i64)> = Vec::new();
+        let mut breakpoints: Vec<Json> = Vec::new();
+
+        self.line_breakpoints.clear();
+
+        for breakpoint in arguments.get("breakpoints").as_array() {
+            let line: i64 = breakpoint.get("line").as_i64().unwrap_or(0);
+            let id: i64 = self.next_breakpoint_id;
+
+            self.next_breakpoint_id += 1;
+            requests.push((id, line));
+            breakpoints.push(self.line_breakpoint(id, &path, line));
+        }
+
+        self.requested_lines.insert(path, requests);
+        Ok(Json::object([("breakpoints", breakpoints.into())]))
+    }
+
+    // Resolves a requested line against the launched source, a line of another file or one
+    // asked for before launch stays unverified
+    fn line_breakpoint(&mut self, id: i64, path: &str, line: i64) -> Json {
+        let location: Option<(u16, usize)> = match &self.source {
+            Some((program, source)) if source == path => line_address(program, line as usize),
+            _ => None,
+        };
+
+        match location {
+            Some((address, line)) => {
+                self.line_breakpoints.insert(address);
+                Json::object([
+                    ("id", id.into()),
+                    ("verified", true.into()),
+                    ("line", (line as i64).into()),
+                    ("instructionReference", reference(address).into()),
+                ])
+            }
+            None => Json::object([
+                ("id", id.into()),
+                ("verified", false.into()),
+                ("line", line.into()),
+                ("message", "no instruction at or after this line".into()),
+            ]),
+        }
+    }
+
+    fn set_instruction_breakpoints(&mut self, arguments: &Json) -> Result<Json, String> {
+        let mut breakpoints: Vec<Json> = Vec::new();
+
+        self.instruction_breakpoints.clear();
+
+        for breakpoint in arguments.get("breakpoints").as_array() {
+            let reference: &str = breakpoint
+                .get("instructionReference")
+                .as_str()
+                .unwrap_or("");
+            let offset: i64 = breakpoint.get("offset").as_i64().unwrap_or(0);
+            let address: Option<u16> = parse_value(reference, &self.symbols)
+                .map(|address| address.wrapping_add(offset as u16));
+
+            if let Some(address) = address {
+                self.instruction_breakpoints.insert(address);
+            }
+            breakpoints.push(Json::object([
+                ("verified", address.is_some().into()),
+                ("instructionReference", reference.into()),
+            ]));
+        }
+
+        Ok(Json::object([("breakpoints", breakpoints.into())]))
+    }
+
+    fn stack_trace(&mut self) -> Result<Json, String> {
+        let address: u16 = self.cpu()?.program_counter();
+        let name: String = match label_at(&self.symbols, address) {
+            Some(label) => label.to_string(),
+            None => format!("x{:04X}", address),
+        };
+        let mut frame: Vec<(String, Json)> = vec![
+            (String::from("id"), 1.into()),
+            (String::from("name"), name.into()),
+            (
+                String::from("instructionPointerReference"),
+                reference(address).into(),
+            ),
+            (String::from("column"), 1.into()),
+        ];
+
+        let line: Option<usize> = self
+            .source
+            .as_ref()
+            .and_then(|(program, _)| address_line(program, address));
+        match (&self.source, line) {
+            (Some((_, path)), Some(line)) => {
+                frame.push((String::from("line"), (line as i64).into()));
+                frame.push((
+                    String::from("source"),
+                    Json::object([("path", path.as_str().into())]),
+                ));
+            }
+            _ => frame.push((String::from("line"), 0.into())),
+        }
+
+        Ok(Json::object([
+            ("stackFrames", vec![Json::Object(frame)].into()),
+            ("totalFrames", 1.into()),
+        ]))
+    }
+
+    fn variables(&mut self, arguments: &Json) -> Result<Json, String> {
+        let cpu: &mut CPU = self.cpu()?;
+
+        if arguments.get("variablesReference").as_i64() != Some(REGISTERS) {
+            return Ok(Json::object([("variables", Vec::new().into())]));
+        }
+
+        let mut registers: Vec<(String, u16)> = cpu
+            .registers()
+            .iter()
+            .enumerate()
+            .map(|(index, value)| (format!("R{}", index), *value))
+            .collect();
+        registers.push((String::from("PC"), cpu.program_counter()));
+        registers.push((String::from("PSR"), cpu.processor_status_register()));
+
+        let variables: Vec<Json> = registers
+            .into_iter()
+            .map(|(name, value)| {
+                Json::object([
+                    ("name", name.into()),
+                    ("value", format!("x{:04X} #{}", value, value as i16).into()),
+                    ("variablesReference", 0.into()),
+                    ("memoryReference", reference(value).into()),
+                ])
+            })
+            .collect();
+
+        Ok(Json::object([("variables", variables.into())]))
+    }
+
+    // Memory references are word addresses. Each word reads as two bytes, high byte first, and
+    // offsets and counts are in bytes
+    fn read_memory(&mut self, arguments: &Json) -> Result<Json, String> {
+        let symbols: SymbolTable = self.symbols.clone();
+        let cpu: &mut CPU = self.cpu()?;
+        let memory_reference: &str = arguments.get("memoryReference").as_str().unwrap_or("");
+        let Some(address) = parse_value(memory_reference, &symbols) else {
+            return Err(format!("invalid memory reference '{}'", memory_reference));
+        };
+        let count: i64 = arguments.get("count").as_i64().unwrap_or(0).max(0);
+        let start: i64 = address as i64 * 2 + arguments.get("offset").as_i64().unwrap_or(0);
+        let end: i64 = (start + count).min(0x20000);
+
+        let bytes: Vec<u8> = (start.max(0)..end.max(0))
+            .map(|byte| cpu.memory().peek((byte / 2) as u16).to_be_bytes()[(byte % 2) as usize])
+            .collect();
+
+        Ok(Json::object([
+            ("address", reference((start.max(0) / 2) as u16).into()),
+            ("data", base64(&bytes).into()),
+            ("unreadableBytes", (count - bytes.len() as i64).into()),
+        ]))
+    }
+
+    // Text typed in the debug console is input for the program, ending with a newline
+    fn evaluate(&mut self, arguments: &Json) -> Result<Json, String> {
+        let expression: &str = arguments.get("expression").as_str().unwrap_or("");
+
+        self.cpu()?;
+        self.keys
+            .borrow_mut()
+            .extend(expression.bytes().chain([b'\n']));
+
+        if self.waiting_for_input {
+            self.resume(Mode::Continue)?;
+            self.event("continued", Json::object([("threadId", THREAD.into())]));
+        }
+
+        Ok(Json::object([
+            ("result", "".into()),
+            ("variablesReference", 0.into()),
+        ]))
+    }
+
+    fn next(&mut self) -> Result<Json, String> {
+        let cpu: &mut CPU = self.cpu()?;
+        let address: u16 = cpu.program_counter();
+
+        // Subroutine calls are stepped over as a whole
+        match cpu.memory().peek(address) >> 12 {
+            0x4 => self
+                .resume(Mode::StepOver(address.wrapping_add(1)))
+                .map(|_| Json::Null),
+            _ => self.step_in(),
+        }
+    }
+
+    fn step_in(&mut self) -> Result<Json, String> {
+        let cpu: &mut CPU = self.cpu()?;
+
+        cpu.resume();
+        self.waiting_for_input = false;
+
+        match self.cpu()?.step() {
+            RunState::Running => self.stopped("step", None),
+            _ => self.machine_stopped(),
+        }
+        Ok(Json::Null)
+    }
+
+    fn resume(&mut self, mode: Mode) -> Result<(), String> {
+        self.resumed_from = Some(self.cpu()?.program_counter());
+        self.cpu()?.resume();
+        self.waiting_for_input = false;
+        self.running = Some(mode);
+        Ok(())
+    }
+
+    // Reports why the machine itself stopped running
+    fn machine_stopped(&mut self) {
+        let Some(cpu) = &self.cpu else {
+            return;
+        };
+
+        match cpu.state() {
+            RunState::Running => {}
+            RunState::Halted => {
+                let code: i64 = cpu.exit_status().unwrap_or(0) as i64;
+
+                self.event("exited", Json::object([("exitCode", code.into())]));
+                self.event("terminated", Json::Null);
+            }
+            RunState::InputExhausted => {
+                self.waiting_for_input = true;
+                self.stopped(
+                    "pause",
+                    Some(String::from(
+                        "Waiting for input, type it in the debug console",
+                    )),
+                );
+            }
+            RunState::Fault(exception) => self.stopped("exception", Some(exception.to_string())),
+        }
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<String>) {
+        let mut body: Vec<(String, Json)> = vec![
+            (String::from("reason"), reason.into()),
+            (String::from("threadId"), THREAD.into()),
+            (String::from("allThreadsStopped"), true.into()),
+        ];
+
+        if let Some(text) = text {
+            body.push((String::from("text"), text.into()));
+        }
+        self.event("stopped", Json::Object(body));
+    }
+
+    fn event(&mut self, event: &str, body: Json) {
+        let mut message: Vec<(String, Json)> = vec![
+            (String::from("type"), "event".into()),
+            (String::from("event"), event.into()),
+        ];
+
+        if body != Json::Null {
+            message.push((String::from("body"), body));
+        }
+        self.events.push(Json::Object(message));
+    }
+
+    fn respond(&mut self, request: &Json, result: Result<Json, String>) -> io::Result<()> {
+        let mut message: Vec<(String, Json)> = vec![
+            (String::from("type"), "response".into()),
+            (String::from("request_seq"), request.get("seq").clone()),
+            (String::from("command"), request.get("command").clone()),
+            (String::from("success"), result.is_ok().into()),
+        ];
+
+        match result {
+            Ok(Json::Null) => {}
+            Ok(body) => message.push((String::from("body"), body)),
+            Err(error) => message.push((String::from("message"), error.into())),
+        }
+        self.send(Json::Object(message))
+    }
+
+    fn flush_output(&mut self) -> io::Result<()> {
+        let output: Vec<u8> = std::mem::take(&mut *self.output.borrow_mut());
+
+        if output.is_empty() {
+            return Ok(());
+        }
+
+        self.send(Json::object([
+            ("type", "event".into()),
+            ("event", "output".into()),
+            (
+                "body",
+                Json::object([
+                    ("category", "stdout".into()),
+                    (
+                        "output",
+                        String::from_utf8_lossy(&output).into_owned().into(),
+                    ),
+                ]),
+            ),
+        ]))
+    }
+
+    fn send(&mut self, message: Json) -> io::Result<()> {
+        self.seq += 1;
+
+        let Json::Object(mut members) = message else {
+            unreachable!("Protocol messages are objects.");
+        };
+        members.insert(0, (String::from("seq"), self.seq.into()));
+
+        write_message(&mut self.out, &Json::Object(members))
+    }
+}
+
+// Reads one message framed by a Content-Length header, None at the end of the input
+pub fn read_message(input: &mut dyn BufRead) -> io::Result<Option<Json>> {
+    let mut length: Option<usize> = None;
+
+    loop {
+        let mut line: String = String::new();
+
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line: &str = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "message without a Content-Length header",
+        ));
+    };
+    let mut content: Vec<u8> = vec![0; length];
+    input.read_exact(&mut content)?;
+
+    json::parse(&String::from_utf8_lossy(&content))
+        .map(Some)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+pub fn write_message(out: &mut dyn Write, message: &Json) -> io::Result<()> {
+    let content: String = message.to_string();
+
+    write!(out, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    out.flush()
+}
+
+// Serves the protocol until the client disconnects. Requests are read on their own thread so
+// a running program can be paused
+pub fn serve(input: impl BufRead + Send + 'static, out: Box<dyn Write>) -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+    let mut input = input;
+
+    thread::spawn(move || {
+        while let Ok(Some(message)) = read_message(&mut input) {
+            if sender.send(message).is_err() {
+                return;
+            }
+        }
+    });
+
+    let mut dap: Dap = Dap::new(out);
+
+    loop {
+        let Some(request) = next_request(&receiver, dap.running()) else {
+            return Ok(());
+        };
+
+        if let Some(request) = request {
+            if dap.handle(&request)? == Flow::Quit {
+                return Ok(());
+            }
+        }
+        dap.run_slice()?;
+    }
+}
+
+// Waits for a request unless the program is running. None once the client is gone
+fn next_request(receiver: &Receiver<Json>, running: bool) -> Option<Option<Json>> {
+    match running {
+        true => match receiver.try_recv() {
+            Ok(request) => Some(Some(request)),
+            Err(TryRecvError::Empty) => Some(None),
+            Err(TryRecvError::Disconnected) => None,
+        },
+        false => receiver.recv().ok().map(Some),
+    }
+}
+
+// First instruction assembled from `line` or a later line, and the line it came from
+pub fn line_address(program: &Program, line: usize) -> Option<(u16, usize)> {
+    program
+        .lines
+        .iter()
+        .zip(&program.code)
+        .enumerate()
+        .filter(|(_, (&source, &code))| code && source >= line)
+        .min_by_key(|(_, (&source, _))| source)
+        .map(|(index, (&source, _))| (program.origin.wrapping_add(index as u16), source))
+}
+
+pub fn address_line(program: &Program, address: u16) -> Option<usize> {
+    let index: usize = address.wrapping_sub(program.origin) as usize;
+
+    program.lines.get(index).copied()
+}
+
+fn reference(address: u16) -> String {
+    format!("0x{:04X}", address)
+}
+
+// Paths from the editor and the launch request are compared in their canonical form
+fn canonical(path: &str) -> String {
+    fs::canonicalize(path).map_or(path.to_string(), |path| path.to_string_lossy().into_owned())
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut text: String = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let group: u32 = chunk.iter().enumerate().fold(0, |group, (index, byte)| {
+            group | ((*byte as u32) << (16 - 8 * index))
+        });
+
+        for index in 0..4 {
+            match index <= chunk.len() {
+                true => text.push(ALPHABET[((group >> (18 - 6 * index)) & 0x3F) as usize] as char),
+                false => text.push('='),
+            }
+        }
+    }
+
+    text
+}
+
+#[cfg(test)]
+#[path = "./dap_test.rs"]
+mod dap_test;
@@ -0,0 +1,390 @@
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    rc::Rc,
+};
+
+use crate::{
+    dap::{read_message, write_message, Dap},
+    debugger::Flow,
+    json::{parse, Json},
+};
+
+const SOURCE: &str = "\
+        .ORIG x3000
+        LEA R0, HELLO
+        PUTS
+        JSR DOUBLE
+        ; Back from the subroutine
+        ADD R2, R1, #0
+        HALT
+DOUBLE  ADD R1, R1, #1
+        ADD R1, R1, R1
+        RET
+HELLO   .STRINGZ \"hi\"
+        .END
+";
+
+#[derive(Clone, Default)]
+struct Shared(Rc<RefCell<Vec<u8>>>);
+
+impl Write for Shared {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Client {
+    dap: Dap,
+    out: Shared,
+    seq: i64,
+}
+
+impl Client {
+    fn new() -> Self {
+        let out: Shared = Shared::default();
+
+        Client {
+            dap: Dap::new(Box::new(out.clone())),
+            out,
+            seq: 0,
+        }
+    }
+
+    // Sends a request and returns everything the adapter wrote back
+    fn request(&mut self, command: &str, arguments: &str) -> Vec<Json> {
+        self.seq += 1;
+
+        let request: Json = parse(&format!(
+            r#"{{"seq":{},"type":"request","command":"{}","arguments":{}}}"#,
+            self.seq, command, arguments
+        ))
+        .unwrap();
+        assert_eq!(self.dap.handle(&request).unwrap(), Flow::Continue);
+        self.messages()
+    }
+
+    // Lets the program run until it stops
+    fn run(&mut self) -> Vec<Json> {
+        while self.dap.running() {
+            self.dap.run_slice().unwrap();
+        }
+        self.messages()
+    }
+
+    fn messages(&mut self) -> Vec<Json> {
+        let data: Vec<u8> = std::mem::take(&mut *self.out.0.borrow_mut());
+        let mut input: &[u8] = &data;
+        let mut messages: Vec<Json> = Vec::new();
+
+        while let Some(message) = read_message(&mut input).unwrap() {
+            messages.push(message);
+        }
+        messages
+    }
+
+    fn launch(&mut self, arguments: &str) -> String {
+        let path = std::env::temp_dir().join("lc_3_dap.asm");
+        std::fs::write(&path, SOURCE).unwrap();
+        let path: String = path.to_string_lossy().into_owned();
+
+        self.request("initialize", "{}");
+        let launch: Vec<Json> = self.request(
+            "launch",
+            &format!(r#"{{"program":"{}"{}}}"#, path, arguments),
+        );
+        assert_eq!(launch[0].get("success"), &Json::Bool(true));
+        path
+    }
+}
+
+fn event<'a>(messages: &'a [Json], name: &str) -> Option<&'a Json> {
+    messages
+        .iter()
+        .find(|message| message.get("event").as_str() == Some(name))
+}
+
+fn variable(variables: &Json, name: &str) -> String {
+    variables
+        .get("body")
+        .get("variables")
+        .as_array()
+        .iter()
+        .find(|variable| variable.get("name").as_str() == Some(name))
+        .and_then(|variable| variable.get("value").as_str())
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn test_framing() {
+    let mut data: Vec<u8> = Vec::new();
+    write_message(&mut data, &Json::object([("seq", 1.into())])).unwrap();
+    assert_eq!(data, b"Content-Length: 9\r\n\r\n{\"seq\":1}");
+
+    let mut input: &[u8] = b"Content-Length: 2\r\nContent-Type: x\r\n\r\n{}";
+    assert_eq!(
+        read_message(&mut input).unwrap(),
+        Some(Json::Object(Vec::new()))
+    );
+    assert_eq!(read_message(&mut input).unwrap(), None);
+
+    let mut input: &[u8] = b"\r\n{}";
+    assert!(read_message(&mut input).is_err());
+}
+
+#[test]
+fn test_initialize_and_run() {
+    let mut client: Client = Client::new();
+
+    let messages: Vec<Json> = client.request("initialize", "{}");
+    assert_eq!(messages[0].get("type").as_str(), Some("response"));
+    assert_eq!(messages[0].get("request_seq").as_i64(), Some(1));
+    assert_eq!(
+        messages[0]
+            .get("body")
+            .get("supportsReadMemoryRequest")
+            .as_bool(),
+        Some(true)
+    );
+    assert!(event(&messages, "initialized").is_some());
+
+    // Requests before a launch fail with a message
+    let messages: Vec<Json> = client.request("stackTrace", r#"{"threadId":1}"#);
+    assert_eq!(messages[0].get("success"), &Json::Bool(false));
+    assert!(messages[0].get("message").as_str().is_some());
+
+    client.launch("");
+    client.request("configurationDone", "{}");
+    let messages: Vec<Json> = client.run();
+
+    assert_eq!(
+        event(&messages, "output")
+            .unwrap()
+            .get("body")
+            .get("output")
+            .as_str(),
+        Some("hi\nHALT\n")
+    );
+    assert_eq!(
+        event(&messages, "exited")
+            .unwrap()
+            .get("body")
+            .get("exitCode")
+            .as_i64(),
+        Some(0)
+    );
+    assert!(event(&messages, "terminated").is_some());
+}
+
+#[test]
+fn test_breakpoints_and_stepping() {
+    let mut client: Client = Client::new();
+    let path: String = client.launch(r#","stopOnEntry":true"#);
+
+    // Lines without code move to the next instruction, other files have none
+    let messages: Vec<Json> = client.request(
+        "setBreakpoints",
+        &format!(
+            r#"{{"source":{{"path":"{}"}},"breakpoints":[{{"line":5}},{{"line":40}}]}}"#,
+            path
+        ),
+    );
+    let breakpoints: &[Json] = messages[0].get("body").get("breakpoints").as_array();
+    assert_eq!(breakpoints[0].get("verified").as_bool(), Some(true));
+    assert_eq!(breakpoints[0].get("line").as_i64(), Some(6));
+    assert_eq!(
+        breakpoints[0].get("instructionReference").as_str(),
+        Some("0x3003")
+    );
+    assert_eq!(breakpoints[1].get("verified").as_bool(), Some(false));
+
+    let messages: Vec<Json> = client.request(
+        "setInstructionBreakpoints",
+        r#"{"breakpoints":[{"instructionReference":"DOUBLE","offset":1}]}"#,
+    );
+    let breakpoints: &[Json] = messages[0].get("body").get("breakpoints").as_array();
+    assert_eq!(breakpoints[0].get("verified").as_bool(), Some(true));
+
+    let messages: Vec<Json> = client.request("configurationDone", "{}");
+    let stopped: &Json = event(&messages, "stopped").unwrap();
+    assert_eq!(stopped.get("body").get("reason").as_str(), Some("entry"));
+
+    // Stepping over PUTS and into the subroutine call
+    client.request("next", r#"{"threadId":1}"#);
+    let messages: Vec<Json> = client.request("next", r#"{"threadId":1}"#);
+    assert_eq!(
+        event(&messages, "output")
+            .unwrap()
+            .get("body")
+            .get("output")
+            .as_str(),
+        Some("hi")
+    );
+    client.request("stepIn", r#"{"threadId":1}"#);
+
+    let messages: Vec<Json> = client.request("stackTrace", r#"{"threadId":1}"#);
+    let frame: &Json = &messages[0].get("body").get("stackFrames").as_array()[0];
+    assert_eq!(frame.get("name").as_str(), Some("DOUBLE"));
+    assert_eq!(frame.get("line").as_i64(), Some(8));
+    assert_eq!(
+        frame.get("instructionPointerReference").as_str(),
+        Some("0x3005")
+    );
+
+    // The instruction breakpoint inside the subroutine, then out of it
+    client.request("continue", r#"{"threadId":1}"#);
+    let messages: Vec<Json> = client.run();
+    let stopped: &Json = event(&messages, "stopped").unwrap();
+    assert_eq!(
+        stopped.get("body").get("reason").as_str(),
+        Some("breakpoint")
+    );
+
+    client.request("stepOut", r#"{"threadId":1}"#);
+    let messages: Vec<Json> = client.run();
+    let stopped: &Json = event(&messages, "stopped").unwrap();
+    assert_eq!(stopped.get("body").get("reason").as_str(), Some("step"));
+
+    let messages: Vec<Json> = client.request("variables", r#"{"variablesReference":1}"#);
+    assert_eq!(variable(&messages[0], "R1"), "x0002 #2");
+    assert_eq!(variable(&messages[0], "PC"), "x3003 #12291");
+
+    // Continuing from the line breakpoint runs to the end
+    client.request("continue", r#"{"threadId":1}"#);
+    let messages: Vec<Json> = client.run();
+    assert!(event(&messages, "exited").is_some());
+}
+
+#[test]
+fn test_breakpoint_on_entry() {
+    let mut client: Client = Client::new();
+    let path: String = client.launch("");
+
+    client.request(
+        "setBreakpoints",
+        &format!(
+            r#"{{"source":{{"path":"{}"}},"breakpoints":[{{"line":2}}]}}"#,
+            path
+        ),
+    );
+
+    // The first instruction is checked before it runs
+    client.request("configurationDone", "{}");
+    let messages: Vec<Json> = client.run();
+    let stopped: &Json = event(&messages, "stopped").unwrap();
+    assert_eq!(
+        stopped.get("body").get("reason").as_str(),
+        Some("breakpoint")
+    );
+    let messages: Vec<Json> = client.request("variables", r#"{"variablesReference":1}"#);
+    assert_eq!(variable(&messages[0], "PC"), "x3000 #12288");
+
+    // Continuing steps over the breakpoint it stopped on
+    client.request("continue", r#"{"threadId":1}"#);
+    let messages: Vec<Json> = client.run();
+    assert!(event(&messages, "stopped").is_none());
+    assert!(event(&messages, "exited").is_some());
+}
+
+#[test]
+fn test_breakpoints_before_launch() {
+    let mut client: Client = Client::new();
+    let path = std::env::temp_dir().join("lc_3_dap_early.asm");
+    std::fs::write(&path, SOURCE).unwrap();
+    let path: String = path.to_string_lossy().into_owned();
+
+    // Clients may configure breakpoints as soon as initialize answers, before the launch
+    client.request("initialize", "{}");
+    let messages: Vec<Json> = client.request(
+        "setBreakpoints",
+        &format!(
+            r#"{{"source":{{"path":"{}"}},"breakpoints":[{{"line":5}}]}}"#,
+            path
+        ),
+    );
+    let breakpoints: &[Json] = messages[0].get("body").get("breakpoints").as_array();
+    assert_eq!(breakpoints[0].get("verified").as_bool(), Some(false));
+    let id: Option<i64> = breakpoints[0].get("id").as_i64();
+
+    // The launch resolves it and reports the change
+    let messages: Vec<Json> = client.request("launch", &format!(r#"{{"program":"{}"}}"#, path));
+    assert_eq!(messages[0].get("success"), &Json::Bool(true));
+    let changed: &Json = event(&messages, "breakpoint").unwrap().get("body");
+    assert_eq!(changed.get("reason").as_str(), Some("changed"));
+    assert_eq!(changed.get("breakpoint").get("id").as_i64(), id);
+    assert_eq!(
+        changed.get("breakpoint").get("verified").as_bool(),
+        Some(true)
+    );
+    assert_eq!(changed.get("breakpoint").get("line").as_i64(), Some(6));
+
+    client.request("configurationDone", "{}");
+    let messages: Vec<Json> = client.run();
+    let stopped: &Json = event(&messages, "stopped").unwrap();
+    assert_eq!(
+        stopped.get("body").get("reason").as_str(),
+        Some("breakpoint")
+    );
+    let messages: Vec<Json> = client.request("variables", r#"{"variablesReference":1}"#);
+    assert_eq!(variable(&messages[0], "PC"), "x3003 #12291");
+}
+
+#[test]
+fn test_read_memory() {
+    let mut client: Client = Client::new();
+    client.launch("");
+
+    let messages: Vec<Json> = client.request(
+        "readMemory",
+        r#"{"memoryReference":"HELLO","offset":0,"count":6}"#,
+    );
+    let body: &Json = messages[0].get("body");
+
+    // "hi" and its terminator, high bytes first
+    assert_eq!(body.get("address").as_str(), Some("0x3008"));
+    assert_eq!(body.get("data").as_str(), Some("AGgAaQAA"));
+    assert_eq!(body.get("unreadableBytes").as_i64(), Some(0));
+
+    let messages: Vec<Json> = client.request(
+        "readMemory",
+        r#"{"memoryReference":"xFFFF","offset":1,"count":4}"#,
+    );
+    assert_eq!(
+        messages[0].get("body").get("unreadableBytes").as_i64(),
+        Some(3)
+    );
+}
+
+#[test]
+fn test_input_from_debug_console() {
+    let mut client: Client = Client::new();
+    let path = std::env::temp_dir().join("lc_3_dap_input.asm");
+    std::fs::write(&path, ".ORIG x3000\nGETC\nOUT\nGETC\nOUT\nHALT\n.END\n").unwrap();
+
+    client.request(
+        "launch",
+        &format!(r#"{{"program":"{}","input":"a"}}"#, path.to_string_lossy()),
+    );
+    client.request("configurationDone", "{}");
+    let messages: Vec<Json> = client.run();
+    let stopped: &Json = event(&messages, "stopped").unwrap();
+    assert_eq!(stopped.get("body").get("reason").as_str(), Some("pause"));
+
+    let messages: Vec<Json> = client.request("evaluate", r#"{"expression":"b","context":"repl"}"#);
+    assert!(event(&messages, "continued").is_some());
+    let messages: Vec<Json> = client.run();
+    assert_eq!(
+        event(&messages, "output")
+            .unwrap()
+            .get("body")
+            .get("output")
+            .as_str(),
+        Some("b\nHALT\n")
+    );
+}
@@ -0,0 +1,356 @@
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{self, BufRead, Write},
+};
+
+use lc_3::{
+    assembler::{parse_number, SymbolTable},
+    cpu::{RunState, CPU},
+    disassembler::{disassemble_with_symbols, label_at},
+    dump::{dump, load, DumpFormat},
+    video::Frame,
+};
+
+const HELP: &str = "\
+Commands:
+  s, step [COUNT]         execute COUNT instructions (default 1)
+  c, continue             run until a breakpoint or the program stops
+  b, break [ADDR]         set a breakpoint, or list breakpoints
+  d, delete ADDR          remove a breakpoint
+  r, regs                 show registers and condition codes
+  m, mem ADDR [COUNT]     show COUNT words of memory (default 8)
+  l, list [ADDR]          disassemble around ADDR (default PC)
+  set REG VALUE           set R0-R7 or PC
+  dump START END FILE     save memory from START to END (.obj, .hex or raw words)
+  load FILE [ADDR]        load a memory dump, raw dumps at ADDR
+  frame FILE              save the video memory as a PPM image
+  q, quit                 leave the debugger
+ADDR and VALUE accept x3000, #12288, 0x3000 or a label";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+pub struct Debugger {
+    pub cpu: CPU,
+    pub symbols: SymbolTable,
+    breakpoints: BTreeSet<u16>,
+}
+
+// Accepts LC-3 style numbers, C style hex and labels
+pub fn parse_value(word: &str, symbols: &SymbolTable) -> Option<u16> {
+    if let Some(address) = symbols.get(word) {
+        return Some(*address);
+    }
+
+    let value: i32 = match word.strip_prefix("0x") {
+        Some(hex) => i32::from_str_radix(hex, 16).ok()?,
+        None => parse_number(word)?,
+    };
+
+    match (-0x8000..=0xFFFF).contains(&value) {
+        true => Some(value as u16),
+        false => None,
+    }
+}
+
+impl Debugger {
+    pub fn new(cpu: CPU, symbols: SymbolTable) -> Self {
+        Debugger {
+            cpu,
+            symbols,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn breakpoints(&self) -> &BTreeSet<u16> {
+        &self.breakpoints
+    }
+
+    // Sets a breakpoint at `address`, or removes the one already there. True when set
+    pub fn toggle_breakpoint(&mut self, address: u16) -> bool {
+        if self.breakpoints.remove(&address) {
+            return false;
+        }
+
+        self.breakpoints.insert(address)
+    }
+
+    // Runs until the next breakpoint, always executing at least one instruction
+    pub fn continue_execution(&mut self) -> RunState {
+        loop {
+            let state: RunState = self.cpu.step();
+
+            if state != RunState::Running || self.breakpoints.contains(&self.cpu.program_counter())
+            {
+                self.cpu.memory_mut().console().flush();
+                return state;
+            }
+        }
+    }
+
+    pub fn session(&mut self, input: &mut dyn BufRead, out: &mut dyn Write) -> io::Result<()> {
+        writeln!(out, "Type 'help' for a list of commands.")?;
+        self.show_location(out)?;
+
+        loop {
+            write!(out, "(lc3) ")?;
+            out.flush()?;
+
+            let mut line: String = String::new();
+
+            if input.read_line(&mut line)? == 0 || self.execute(&line, out)? == Flow::Quit {
+                return Ok(());
+            }
+        }
+    }
+
+    pub fn execute(&mut self, line: &str, out: &mut dyn Write) -> io::Result<Flow> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let Some((command, arguments)) = words.split_first() else {
+            return Ok(Flow::Continue);
+        };
+
+        match *command {
+            "s" | "step" => {
+                // A plain decimal count, addresses and negative numbers make no sense here
+                let count: u64 = match arguments.first().map(|word| word.parse::<u64>()) {
+                    Some(Ok(count)) if count > 0 => count,
+                    Some(_) => {
+                        writeln!(out, "Usage: step [COUNT], COUNT a positive number")?;
+                        return Ok(Flow::Continue);
+                    }
+                    None => 1,
+                };
+
+                for _ in 0..count {
+                    if self.cpu.step() != RunState::Running {
+                        break;
+                    }
+                }
+
+                self.cpu.memory_mut().console().flush();
+                self.show_location(out)?;
+            }
+            "c" | "continue" => {
+                self.continue_execution();
+
+                if self.breakpoints.contains(&self.cpu.program_counter()) {
+                    writeln!(out, "Breakpoint reached")?;
+                }
+
+                self.show_location(out)?;
+            }
+            "b" | "break" => match arguments.first() {
+                Some(word) => match parse_value(word, &self.symbols) {
+                    Some(address) => {
+                        self.breakpoints.insert(address);
+                        writeln!(out, "Breakpoint set at x{:04X}", address)?;
+                    }
+                    None => return self.invalid(out, word),
+                },
+                None => {
+                    for address in &self.breakpoints {
+                        writeln!(out, "x{:04X}", address)?;
+                    }
+                }
+            },
+            "d" | "delete" => match arguments
+                .first()
+                .and_then(|w| parse_value(w, &self.symbols))
+            {
+                Some(address) if self.breakpoints.remove(&address) => {
+                    writeln!(out, "Breakpoint removed at x{:04X}", address)?;
+                }
+                _ => writeln!(out, "No such breakpoint")?,
+            },
+            "r" | "regs" => self.show_registers(out)?,
+            "m" | "mem" => {
+                let Some(start) = arguments
+                    .first()
+                    .and_then(|w| parse_value(w, &self.symbols))
+                else {
+                    return self.invalid(out, arguments.first().unwrap_or(&""));
+                };
+                let count: u16 = arguments
+                    .get(1)
+                    .and_then(|w| parse_value(w, &self.symbols))
+                    .unwrap_or(8);
+
+                for offset in 0..count {
+                    let address: u16 = start.wrapping_add(offset);
+                    writeln!(
+                        out,
+                        "x{:04X}: x{:04X}",
+                        address,
+                        self.cpu.memory().peek(address)
+                    )?;
+                }
+            }
+            "l" | "list" => {
+                let center: u16 = match arguments.first() {
+                    Some(word) => match parse_value(word, &self.symbols) {
+                        Some(address) => address,
+                        None => return self.invalid(out, word),
+                    },
+                    None => self.cpu.program_counter(),
+                };
+
+                for offset in 0..10u16 {
+                    let address: u16 = center.wrapping_sub(4).wrapping_add(offset);
+                    writeln!(out, "{}", self.describe(address))?;
+                }
+            }
+            "set" => {
+                let value: Option<u16> =
+                    arguments.get(1).and_then(|w| parse_value(w, &self.symbols));
+
+                match (arguments.first().map(|w| w.to_uppercase()), value) {
+                    (Some(name), Some(value)) if name == "PC" => {
+                        self.cpu.set_program_counter(value)
+                    }
+                    (Some(name), Some(value)) if name.len() == 2 && name.starts_with('R') => {
+                        match name[1..].parse::<usize>() {
+                            Ok(register) if register < 8 => self.cpu.set_register(register, value),
+                            _ => return self.invalid(out, &name),
+                        }
+                    }
+                    _ => writeln!(out, "Usage: set REG VALUE")?,
+                }
+            }
+            "dump" => {
+                let range: Vec<Option<u16>> = arguments
+                    .iter()
+                    .take(2)
+                    .map(|w| parse_value(w, &self.symbols))
+                    .collect();
+
+                match (range.as_slice(), arguments.get(2)) {
+                    ([Some(start), Some(end)], Some(path)) if start <= end => {
+                        let data: Vec<u8> =
+                            dump(self.cpu.memory(), *start, *end, DumpFormat::from_path(path));
+
+                        match fs::write(path, data) {
+                            Ok(()) => {
+                                writeln!(out, "Saved x{:04X}-x{:04X} to {}", start, end, path)?
+                            }
+                            Err(error) => writeln!(out, "Cannot write '{}': {}", path, error)?,
+                        }
+                    }
+                    _ => writeln!(out, "Usage: dump START END FILE")?,
+                }
+            }
+            "load" => {
+                let Some(path) = arguments.first() else {
+                    writeln!(out, "Usage: load FILE [ADDR]")?;
+                    return Ok(Flow::Continue);
+                };
+                let address: Option<u16> = match arguments.get(1) {
+                    Some(word) => match parse_value(word, &self.symbols) {
+                        Some(address) => Some(address),
+                        None => return self.invalid(out, word),
+                    },
+                    None => None,
+                };
+
+                let result: Result<(u16, usize), String> = fs::read(path)
+                    .map_err(|error| error.to_string())
+                    .and_then(|data| {
+                        load(
+                            self.cpu.memory_mut(),
+                            &data,
+                            DumpFormat::from_path(path),
+                            address,
+                        )
+                    });
+
+                match result {
+                    Ok((start, count)) => {
+                        writeln!(out, "Loaded {} words at x{:04X}", count, start)?
+                    }
+                    Err(error) => writeln!(out, "Cannot load '{}': {}", path, error)?,
+                }
+            }
+            "frame" => match arguments.first() {
+                Some(path) => match fs::write(path, Frame::capture(self.cpu.memory()).to_ppm()) {
+                    Ok(()) => writeln!(out, "Saved frame to {}", path)?,
+                    Err(error) => writeln!(out, "Cannot write '{}': {}", path, error)?,
+                },
+                None => writeln!(out, "Usage: frame FILE")?,
+            },
+            "h" | "help" => writeln!(out, "{}", HELP)?,
+            "q" | "quit" => return Ok(Flow::Quit),
+            _ => writeln!(out, "Unknown command '{}', type 'help'", command)?,
+        }
+
+        Ok(Flow::Continue)
+    }
+
+    // One disassembly line with the breakpoint and PC markers
+    pub fn describe(&self, address: u16) -> String {
+        let operation: u16 = self.cpu.memory().peek(address);
+        let marker: char = if self.breakpoints.contains(&address) {
+            '*'
+        } else {
+            ' '
+        };
+        let pointer: &str = if address == self.cpu.program_counter() {
+            "=>"
+        } else {
+            "  "
+        };
+        let label: &str = label_at(&self.symbols, address).unwrap_or("");
+
+        format!(
+            "{}{} x{:04X}  x{:04X}  {:<12}{}",
+            marker,
+            pointer,
+            address,
+            operation,
+            label,
+            disassemble_with_symbols(address, operation, &self.symbols)
+        )
+    }
+
+    fn show_location(&self, out: &mut dyn Write) -> io::Result<()> {
+        match self.cpu.state() {
+            RunState::Running => {}
+            RunState::Halted => writeln!(out, "Program halted")?,
+            RunState::InputExhausted => writeln!(out, "Program is waiting for input")?,
+            RunState::Fault(exception) => writeln!(out, "Program stopped on {}", exception)?,
+        }
+
+        let line: String = self.describe(self.cpu.program_counter());
+        writeln!(out, "{}", line)
+    }
+
+    fn show_registers(&self, out: &mut dyn Write) -> io::Result<()> {
+        for (index, value) in self.cpu.registers().iter().enumerate() {
+            write!(out, "R{}=x{:04X} ", index, value)?;
+        }
+
+        let psr: u16 = self.cpu.processor_status_register();
+        writeln!(
+            out,
+            "\nPC=x{:04X} PSR=x{:04X} {}{}{}",
+            self.cpu.program_counter(),
+            psr,
+            if psr & 0x4 != 0 { 'N' } else { '-' },
+            if psr & 0x2 != 0 { 'Z' } else { '-' },
+            if psr & 0x1 != 0 { 'P' } else { '-' },
+        )
+    }
+
+    fn invalid(&self, out: &mut dyn Write, word: &str) -> io::Result<Flow> {
+        writeln!(out, "Invalid argument '{}'", word)?;
+        Ok(Flow::Continue)
+    }
+}
+
+#[cfg(test)]
+#[path = "./debugger_test.rs"]
+mod debugger_test;
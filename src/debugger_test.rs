@@ -0,0 +1,114 @@
+use lc_3::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    cpu::{RunState, CPU},
+};
+
+use crate::debugger::{parse_value, Debugger, Flow};
+
+fn debugger(source: &str) -> Debugger {
+    let program: Program = assemble(source).unwrap();
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(&[])));
+
+    cpu.load_image(&program.to_object()).unwrap();
+    Debugger::new(cpu, program.symbols)
+}
+
+fn run(debugger: &mut Debugger, command: &str) -> String {
+    let mut out: Vec<u8> = Vec::new();
+    debugger.execute(command, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+const COUNTER: &str = "
+        .ORIG x3000
+        AND R0, R0, #0
+LOOP    ADD R0, R0, #1
+        ADD R1, R0, #-3
+        BRn LOOP
+        HALT
+        .END
+";
+
+#[test]
+fn test_debugger_step() {
+    let mut debugger: Debugger = debugger(COUNTER);
+
+    let output: String = run(&mut debugger, "step 2");
+
+    assert_eq!(debugger.cpu.registers()[0], 1);
+    assert!(output.contains("x3002"));
+    assert!(output.contains("ADD R1, R0, #-3"));
+
+    // Counts that are not positive numbers are refused without stepping
+    for count in ["0", "-1", "x10", "many"] {
+        let output: String = run(&mut debugger, &format!("step {}", count));
+        assert!(output.starts_with("Usage: step"), "{}", count);
+    }
+    assert_eq!(debugger.cpu.instruction_count(), 2);
+
+    run(&mut debugger, "step");
+    assert_eq!(debugger.cpu.instruction_count(), 3);
+}
+
+#[test]
+fn test_debugger_breakpoint() {
+    let mut debugger: Debugger = debugger(COUNTER);
+
+    assert!(run(&mut debugger, "break LOOP").contains("x3001"));
+    run(&mut debugger, "continue");
+    run(&mut debugger, "continue");
+
+    assert_eq!(debugger.cpu.program_counter(), 0x3001);
+    assert_eq!(debugger.cpu.registers()[0], 1);
+
+    run(&mut debugger, "delete x3001");
+    run(&mut debugger, "c");
+
+    assert_eq!(debugger.cpu.state(), RunState::Halted);
+    assert_eq!(debugger.cpu.registers()[0], 3);
+}
+
+#[test]
+fn test_debugger_inspect() {
+    let mut debugger: Debugger = debugger(COUNTER);
+
+    run(&mut debugger, "set R3 x1234");
+    assert!(run(&mut debugger, "regs").contains("R3=x1234"));
+    assert!(run(&mut debugger, "mem x3000 1").contains("x3000: x5020"));
+    assert!(run(&mut debugger, "list").contains("=> x3000"));
+    assert!(run(&mut debugger, "bogus").contains("Unknown command"));
+
+    let mut out: Vec<u8> = Vec::new();
+    assert_eq!(debugger.execute("quit", &mut out).unwrap(), Flow::Quit);
+}
+
+#[test]
+fn test_debugger_dump_and_load() {
+    let mut debugger: Debugger = debugger(COUNTER);
+    let path = std::env::temp_dir().join("lc_3_debugger_dump.hex");
+    let path: String = path.to_string_lossy().into_owned();
+
+    let output: String = run(&mut debugger, &format!("dump x3000 LOOP {}", path));
+    assert!(output.contains("Saved x3000-x3001"));
+    assert_eq!(
+        std::fs::read_to_string(&path).unwrap(),
+        "x3000: 5020 1021\n"
+    );
+
+    assert!(run(&mut debugger, &format!("load {} x4000", path)).contains("Loaded 2 words at x4000"));
+    assert_eq!(debugger.cpu.memory().peek(0x4001), 0x1021);
+
+    assert!(run(&mut debugger, "dump x3000").contains("Usage"));
+    assert!(run(&mut debugger, "load missing.bin x4000").contains("Cannot load"));
+}
+
+#[test]
+fn test_parse_value() {
+    let symbols = assemble(COUNTER).unwrap().symbols;
+
+    assert_eq!(parse_value("LOOP", &symbols), Some(0x3001));
+    assert_eq!(parse_value("0x3000", &symbols), Some(0x3000));
+    assert_eq!(parse_value("#-1", &symbols), Some(0xFFFF));
+    assert_eq!(parse_value("nowhere", &symbols), None);
+}
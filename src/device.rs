@@ -0,0 +1,57 @@
+// Interfaces between the memory mapped devices and whatever backs them on the host. The std
+// build provides implementations over files, sockets and the system clock, an embedder without
+// the standard library brings its own.
+
+// Words in a disk sector
+pub const SECTOR_WORDS: usize = 256;
+
+// Disk command register values
+pub const DISK_READ: u16 = 1; // Copy a sector into memory
+pub const DISK_WRITE: u16 = 2; // Copy memory into a sector
+
+// Disk status register layout
+pub const DISK_READY: u16 = 1 << 15;
+pub const DISK_ERROR: u16 = 1 << 14;
+
+// Block storage behind the disk registers
+pub trait Storage {
+    // None if the sector could not be read
+    fn read_sector(&mut self, sector: u16) -> Option<[u16; SECTOR_WORDS]>;
+
+    // False if the sector could not be written
+    fn write_sector(&mut self, sector: u16, words: &[u16; SECTOR_WORDS]) -> bool;
+}
+
+// Serial port behind the UART registers
+pub trait Serial {
+    // Receiver status, checking the line for a new byte
+    fn status(&mut self) -> u16;
+
+    fn set_control(&mut self, value: u16);
+
+    // Takes the received byte, 0 when there is none
+    fn read_data(&mut self) -> u16;
+
+    fn write_data(&mut self, value: u16);
+
+    // Called after every instruction
+    fn tick(&mut self, _instruction_count: u64) {}
+
+    // Priority and vector of the interrupt the port is requesting, if any
+    fn interrupt(&self) -> Option<(u16, u8)>;
+}
+
+// Wall-clock time for the timer's millisecond mode
+pub trait Clock {
+    // Milliseconds since any fixed point in time
+    fn milliseconds(&mut self) -> u64;
+}
+
+// Receives every key the program consumes along with the instruction count it was read at
+pub trait Recorder {
+    fn record(&mut self, instruction_count: u64, byte: u8);
+}
+
+#[cfg(all(test, feature = "std"))]
+#[path = "./device_test.rs"]
+mod device_test;
@@ -0,0 +1,110 @@
+use std::{
+    cell::{Cell, RefCell},
+    rc::Rc,
+};
+
+use crate::{
+    console::BufferedConsole,
+    device::{
+        Clock, Recorder, Storage, DISK_ERROR, DISK_READ, DISK_READY, DISK_WRITE, SECTOR_WORDS,
+    },
+    memory::{Memory, DKBA, DKCR, DKSN, DKSR, KBDR, KBSR, TIR, TSR},
+    timer::{TIMER_MILLISECONDS, TIMER_READY},
+};
+
+// Sectors kept in memory, the way an embedder without a file system might provide a disk
+struct RamDisk {
+    sectors: Vec<[u16; SECTOR_WORDS]>,
+}
+
+impl Storage for RamDisk {
+    fn read_sector(&mut self, sector: u16) -> Option<[u16; SECTOR_WORDS]> {
+        self.sectors.get(sector as usize).copied()
+    }
+
+    fn write_sector(&mut self, sector: u16, words: &[u16; SECTOR_WORDS]) -> bool {
+        match self.sectors.get_mut(sector as usize) {
+            Some(stored) => {
+                *stored = *words;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// Clock that only moves when the test says so
+struct ManualClock(Rc<Cell<u64>>);
+
+impl Clock for ManualClock {
+    fn milliseconds(&mut self) -> u64 {
+        self.0.get()
+    }
+}
+
+struct Keys(Rc<RefCell<Vec<(u64, u8)>>>);
+
+impl Recorder for Keys {
+    fn record(&mut self, instruction_count: u64, byte: u8) {
+        self.0.borrow_mut().push((instruction_count, byte));
+    }
+}
+
+fn memory() -> Memory {
+    Memory::with_console(Box::new(BufferedConsole::new(b"k")))
+}
+
+#[test]
+fn test_storage() {
+    let mut memory: Memory = memory();
+    memory.set_disk(RamDisk {
+        sectors: vec![[0x1111; SECTOR_WORDS], [0; SECTOR_WORDS]],
+    });
+
+    memory.write(DKSN, 0);
+    memory.write(DKBA, 0x4000);
+    memory.write(DKCR, DISK_READ);
+    assert_eq!(memory.read(DKSR), DISK_READY);
+    assert_eq!(memory.peek(0x4000 + SECTOR_WORDS as u16 - 1), 0x1111);
+
+    memory.poke(0x4000, 0xBEEF);
+    memory.write(DKSN, 1);
+    memory.write(DKCR, DISK_WRITE);
+    memory.poke(0x4000, 0);
+    memory.write(DKCR, DISK_READ);
+    assert_eq!(memory.peek(0x4000), 0xBEEF);
+
+    // Past the end of the storage
+    memory.write(DKSN, 2);
+    memory.write(DKCR, DISK_READ);
+    assert_eq!(memory.read(DKSR), DISK_READY | DISK_ERROR);
+}
+
+#[test]
+fn test_clock() {
+    let now: Rc<Cell<u64>> = Rc::new(Cell::new(1000));
+    let mut memory: Memory = memory();
+    memory.set_clock(ManualClock(Rc::clone(&now)));
+
+    memory.write(TSR, TIMER_MILLISECONDS);
+    memory.write(TIR, 50);
+
+    // The clock is only read every 1024 instructions
+    memory.set_instruction_count(1024);
+    assert_eq!(memory.read(TSR) & TIMER_READY, 0);
+    now.set(1050);
+    memory.set_instruction_count(2048);
+    assert_eq!(memory.read(TSR) & TIMER_READY, TIMER_READY);
+}
+
+#[test]
+fn test_recorder() {
+    let keys: Rc<RefCell<Vec<(u64, u8)>>> = Rc::new(RefCell::new(Vec::new()));
+    let mut memory: Memory = memory();
+    memory.set_recorder(Box::new(Keys(Rc::clone(&keys))));
+
+    memory.set_instruction_count(7);
+    assert_eq!(memory.read(KBSR), 0x8000);
+    assert_eq!(memory.read(KBDR), b'k' as u16);
+    assert_eq!(*keys.borrow(), [(7, b'k')]);
+}
@@ -0,0 +1,529 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    console::BufferedConsole,
+    cpu::{Engine, Exception, RunState, CPU, INTERRUPT_VECTOR_TABLE},
+    disassembler::disassemble,
+    memory::{DDR, DSR, KBSR, MCR},
+    paging::PAGE_WORDS,
+};
+
+// Runs random instruction sequences on the CPU and on the plain reference model below,
+// comparing the whole machine after every step. Divergences are shrunk before reporting.
+
+const SEED: u64 = 0x5EED_1C30_2024_0001;
+const PROGRAMS: usize = 500;
+const PROGRAM_LENGTH: usize = 24;
+const STEPS: usize = 64;
+const ORIGIN: u16 = 0x3000;
+
+// xorshift64, good enough to spread test cases around
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn bits(&mut self, count: u32) -> u16 {
+        (self.next() & ((1 << count) - 1)) as u16
+    }
+
+    fn below(&mut self, bound: u16) -> u16 {
+        (self.next() % bound as u64) as u16
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Program {
+    registers: [u16; 8],
+    psr: u16,
+    // Trap and interrupt vector table entries, all pointing into the program
+    vectors: Vec<(u16, u16)>,
+    words: Vec<u16>,
+}
+
+// Only encodings the ISA defines, every op code equally likely
+fn random_instruction(random: &mut Random) -> u16 {
+    let op_code: u16 = random.bits(4);
+    let operands: u16 = match op_code {
+        // ADD and AND in register mode keep bits 4 and 3 clear
+        0x1 | 0x5 if random.bits(1) == 0 => random.bits(6) << 6 | random.bits(3),
+        0x1 | 0x5 => random.bits(6) << 6 | 0x20 | random.bits(5),
+        0x9 => random.bits(6) << 6 | 0x3F,
+        0xC => random.bits(3) << 6,
+        0x4 if random.bits(1) == 0 => random.bits(3) << 6,
+        0x4 => 0x800 | random.bits(11),
+        0x8 => 0x000,
+        0xF => random.bits(8),
+        _ => random.bits(12),
+    };
+
+    op_code << 12 | operands
+}
+
+// Register values biased towards the program, the system space and the I/O page
+fn random_value(random: &mut Random) -> u16 {
+    match random.bits(2) {
+        0 => random.bits(16),
+        1 => ORIGIN.wrapping_add(random.bits(6)).wrapping_sub(8),
+        2 => random.bits(9),
+        _ => KBSR.wrapping_add(random.bits(3)),
+    }
+}
+
+fn random_program(random: &mut Random) -> Program {
+    let mut registers: [u16; 8] = [0; 8];
+    for register in registers.iter_mut() {
+        *register = random_value(random);
+    }
+
+    let mode: u16 = random.bits(1) << 15;
+    let priority: u16 = random.bits(3) << 8;
+    let condition_codes: u16 = 1 << random.below(3);
+
+    let mut vectors: Vec<(u16, u16)> = Vec::new();
+    for vector in (0x20..0x26).chain(INTERRUPT_VECTOR_TABLE..INTERRUPT_VECTOR_TABLE + 3) {
+        vectors.push((vector, ORIGIN + random.below(PROGRAM_LENGTH as u16)));
+    }
+
+    Program {
+        registers,
+        psr: mode | priority | condition_codes,
+        vectors,
+        words: (0..PROGRAM_LENGTH)
+            .map(|_| random_instruction(random))
+            .collect(),
+    }
+}
+
+fn sign_extension(bits: u16, bit_count: u32) -> u16 {
+    let shift: u32 = 16 - bit_count;
+    (((bits << shift) as i16) >> shift) as u16
+}
+
+// Straight from the ISA description, trading speed for obviousness
+struct Reference {
+    registers: [u16; 8],
+    pc: u16,
+    psr: u16,
+    saved_user_stack_pointer: u16,
+    saved_supervisor_stack_pointer: u16,
+    memory: Vec<u16>,
+    state: RunState,
+    output: Vec<u8>,
+    // The keyboard was polled, which stops the machine as there is never any input
+    polled: bool,
+}
+
+impl Reference {
+    fn new(program: &Program) -> Self {
+        let mut memory: Vec<u16> = vec![0; 0x10000];
+        memory[MCR as usize] = 0x8000;
+
+        for (address, value) in &program.vectors {
+            memory[*address as usize] = *value;
+        }
+        for (offset, word) in program.words.iter().enumerate() {
+            memory[ORIGIN as usize + offset] = *word;
+        }
+
+        Reference {
+            registers: program.registers,
+            pc: ORIGIN,
+            psr: program.psr,
+            saved_user_stack_pointer: 0x0,
+            saved_supervisor_stack_pointer: 0x3000,
+            memory,
+            state: RunState::Running,
+            output: Vec::new(),
+            polled: false,
+        }
+    }
+
+    fn user_mode(&self) -> bool {
+        self.psr & 0x8000 != 0
+    }
+
+    fn violates_access_control(&self, address: u16) -> bool {
+        self.user_mode() && !(0x3000..0xFE00).contains(&address)
+    }
+
+    // Memory accesses made by the program, checked against the privilege level
+    fn read(&mut self, address: u16) -> Option<u16> {
+        if self.violates_access_control(address) {
+            self.exception(Exception::AccessControlViolation);
+            return None;
+        }
+
+        Some(self.load(address))
+    }
+
+    fn write(&mut self, address: u16, value: u16) {
+        if self.violates_access_control(address) {
+            self.exception(Exception::AccessControlViolation);
+            return;
+        }
+
+        self.store(address, value);
+    }
+
+    // Memory accesses made by the processor itself
+    fn load(&mut self, address: u16) -> u16 {
+        match address {
+            KBSR => {
+                self.memory[KBSR as usize] = 0;
+                self.polled = true;
+            }
+            DSR => self.memory[DSR as usize] = 0x8000,
+            _ => {}
+        }
+
+        self.memory[address as usize]
+    }
+
+    fn store(&mut self, address: u16, value: u16) {
+        if address == DDR {
+            self.output.push(value as u8);
+        }
+
+        self.memory[address as usize] = value;
+    }
+
+    fn push(&mut self, value: u16) {
+        self.registers[6] = self.registers[6].wrapping_sub(1);
+        self.store(self.registers[6], value);
+    }
+
+    fn pop(&mut self) -> u16 {
+        let value: u16 = self.load(self.registers[6]);
+        self.registers[6] = self.registers[6].wrapping_add(1);
+        value
+    }
+
+    fn enter(&mut self, vector_address: u16) {
+        let psr: u16 = self.psr;
+
+        if self.user_mode() {
+            self.saved_user_stack_pointer = self.registers[6];
+            self.registers[6] = self.saved_supervisor_stack_pointer;
+        }
+
+        self.push(psr);
+        self.push(self.pc);
+        self.psr = psr & 0x7FFF;
+        self.pc = self.load(vector_address);
+    }
+
+    fn exception(&mut self, exception: Exception) {
+        self.enter(INTERRUPT_VECTOR_TABLE + exception.vector() as u16);
+    }
+
+    fn set(&mut self, register: u16, value: u16) {
+        self.registers[register as usize] = value;
+        self.psr &= 0xFFF8;
+        self.psr |= match value {
+            0 => 0b010,
+            value if value & 0x8000 != 0 => 0b100,
+            _ => 0b001,
+        };
+    }
+
+    fn step(&mut self) {
+        if self.state != RunState::Running {
+            return;
+        }
+
+        let address: u16 = self.pc;
+        self.pc = self.pc.wrapping_add(1);
+
+        if let Some(word) = self.read(address) {
+            self.execute(word);
+        }
+
+        if self.memory[MCR as usize] & 0x8000 == 0 {
+            self.state = RunState::Halted;
+        } else if self.polled {
+            self.state = RunState::InputExhausted;
+        }
+    }
+
+    fn execute(&mut self, word: u16) {
+        let dr: u16 = (word >> 9) & 0x7;
+        let sr1: u16 = (word >> 6) & 0x7;
+        let pc_offset: u16 = self.pc.wrapping_add(sign_extension(word & 0x1FF, 9));
+        let base_offset: u16 =
+            self.registers[sr1 as usize].wrapping_add(sign_extension(word & 0x3F, 6));
+
+        match word >> 12 {
+            op_code @ (0x1 | 0x5) => {
+                let a: u16 = self.registers[sr1 as usize];
+                let b: u16 = match word & 0x20 {
+                    0 => self.registers[(word & 0x7) as usize],
+                    _ => sign_extension(word & 0x1F, 5),
+                };
+                self.set(
+                    dr,
+                    if op_code == 0x1 {
+                        a.wrapping_add(b)
+                    } else {
+                        a & b
+                    },
+                );
+            }
+            0x9 => self.set(dr, !self.registers[sr1 as usize]),
+            0x2 => {
+                if let Some(value) = self.read(pc_offset) {
+                    self.set(dr, value);
+                }
+            }
+            0xA => {
+                if let Some(pointer) = self.read(pc_offset) {
+                    if let Some(value) = self.read(pointer) {
+                        self.set(dr, value);
+                    }
+                }
+            }
+            0x6 => {
+                if let Some(value) = self.read(base_offset) {
+                    self.set(dr, value);
+                }
+            }
+            0xE => self.set(dr, pc_offset),
+            0x3 => self.write(pc_offset, self.registers[dr as usize]),
+            0xB => {
+                if let Some(pointer) = self.read(pc_offset) {
+                    self.write(pointer, self.registers[dr as usize]);
+                }
+            }
+            0x7 => self.write(base_offset, self.registers[dr as usize]),
+            0x0 => {
+                if dr & self.psr & 0x7 != 0 {
+                    self.pc = pc_offset;
+                }
+            }
+            0xC => self.pc = self.registers[sr1 as usize],
+            0x4 => {
+                let target: u16 = match word & 0x800 {
+                    0 => self.registers[sr1 as usize],
+                    _ => self.pc.wrapping_add(sign_extension(word & 0x7FF, 11)),
+                };
+                self.registers[7] = self.pc;
+                self.pc = target;
+            }
+            0xF if self.user_mode() => self.enter(word & 0xFF),
+            0xF => {
+                self.registers[7] = self.pc;
+                self.pc = self.load(word & 0xFF);
+            }
+            0x8 if self.user_mode() => self.exception(Exception::PrivilegeModeViolation),
+            0x8 => {
+                self.pc = self.pop();
+                self.psr = self.pop();
+
+                if self.user_mode() {
+                    self.saved_supervisor_stack_pointer = self.registers[6];
+                    self.registers[6] = self.saved_user_stack_pointer;
+                }
+            }
+            _ => self.exception(Exception::IllegalOpCode),
+        }
+    }
+}
+
+fn machine(program: &Program, engine: Engine) -> (CPU, Rc<RefCell<Vec<u8>>>) {
+    let console: BufferedConsole = BufferedConsole::new(&[]);
+    let output: Rc<RefCell<Vec<u8>>> = console.output();
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    cpu.set_engine(engine);
+    cpu.native_traps = false;
+    cpu.registers = program.registers;
+    cpu.processor_status_register = program.psr;
+    cpu.program_counter = ORIGIN;
+
+    for (address, value) in &program.vectors {
+        cpu.memory.write(*address, *value);
+    }
+    for (offset, word) in program.words.iter().enumerate() {
+        cpu.memory.write(ORIGIN + offset as u16, *word);
+    }
+
+    (cpu, output)
+}
+
+// Describes the first difference between the two machines, if any
+fn difference(cpu: &CPU, output: &[u8], reference: &Reference) -> Option<String> {
+    if cpu.registers != reference.registers {
+        return Some(format!(
+            "registers {:04X?}, expected {:04X?}",
+            cpu.registers, reference.registers
+        ));
+    }
+    if cpu.program_counter != reference.pc {
+        return Some(format!(
+            "PC x{:04X}, expected x{:04X}",
+            cpu.program_counter, reference.pc
+        ));
+    }
+    if cpu.processor_status_register != reference.psr {
+        return Some(format!(
+            "PSR x{:04X}, expected x{:04X}",
+            cpu.processor_status_register, reference.psr
+        ));
+    }
+    if (
+        cpu.saved_user_stack_pointer,
+        cpu.saved_supervisor_stack_pointer,
+    ) != (
+        reference.saved_user_stack_pointer,
+        reference.saved_supervisor_stack_pointer,
+    ) {
+        return Some(String::from("saved stack pointers"));
+    }
+    if cpu.state != reference.state {
+        return Some(format!(
+            "state {:?}, expected {:?}",
+            cpu.state, reference.state
+        ));
+    }
+    if output != reference.output.as_slice() {
+        return Some(format!(
+            "output {:?}, expected {:?}",
+            output, reference.output
+        ));
+    }
+    // Compared a page at a time, this runs after every step
+    let differs = |(index, words): (usize, &[u16])| match cpu.memory.pages().page(index) {
+        Some(page) => page[..] != *words,
+        None => *words != [0; PAGE_WORDS],
+    };
+    if reference.memory.chunks(PAGE_WORDS).enumerate().any(differs) {
+        let address: u16 = (0..=0xFFFF)
+            .find(|address| cpu.memory.peek(*address) != reference.memory[*address as usize])
+            .unwrap();
+        return Some(format!(
+            "memory x{:04X} is x{:04X}, expected x{:04X}",
+            address,
+            cpu.memory.peek(address),
+            reference.memory[address as usize]
+        ));
+    }
+
+    None
+}
+
+// Step number and description of the first divergence
+fn diverges(program: &Program, engine: Engine) -> Option<(usize, String)> {
+    let (mut cpu, output) = machine(program, engine);
+    let mut reference: Reference = Reference::new(program);
+
+    for step in 1..=STEPS {
+        cpu.step();
+        reference.step();
+
+        if let Some(difference) = difference(&cpu, &output.borrow(), &reference) {
+            return Some((step, difference));
+        }
+    }
+
+    None
+}
+
+// Greedily simplifies a diverging program while it keeps diverging
+fn shrink(mut program: Program, engine: Engine) -> Program {
+    loop {
+        let mut candidates: Vec<Program> = Vec::new();
+
+        for index in 0..program.words.len() {
+            let mut candidate: Program = program.clone();
+            candidate.words.remove(index);
+            candidates.push(candidate);
+
+            if program.words[index] != 0x0000 {
+                let mut candidate: Program = program.clone();
+                candidate.words[index] = 0x0000; // NOP
+                candidates.push(candidate);
+            }
+        }
+        for register in 0..8 {
+            if program.registers[register] != 0 {
+                let mut candidate: Program = program.clone();
+                candidate.registers[register] = 0;
+                candidates.push(candidate);
+            }
+        }
+        if program.psr & 0x0700 != 0 {
+            let mut candidate: Program = program.clone();
+            candidate.psr &= 0xF8FF;
+            candidates.push(candidate);
+        }
+
+        match candidates
+            .into_iter()
+            .find(|candidate| diverges(candidate, engine).is_some())
+        {
+            Some(candidate) => program = candidate,
+            None => return program,
+        }
+    }
+}
+
+fn report(program: &Program, engine: Engine) -> String {
+    let (step, difference) = diverges(program, engine).unwrap();
+    let mut report: String = format!(
+        "{:?} engine diverges from the reference at step {}: {}\nPSR x{:04X}, registers {:04X?}\n",
+        engine, step, difference, program.psr, program.registers
+    );
+
+    for (offset, word) in program.words.iter().enumerate() {
+        let address: u16 = ORIGIN + offset as u16;
+        report.push_str(&format!(
+            "x{:04X}  x{:04X}  {}\n",
+            address,
+            word,
+            disassemble(address, *word)
+        ));
+    }
+
+    report
+}
+
+#[test]
+fn test_random_programs_match_reference() {
+    let mut random: Random = Random(SEED);
+
+    for _ in 0..PROGRAMS {
+        let program: Program = random_program(&mut random);
+
+        for engine in [Engine::Interpreter, Engine::Predecoded] {
+            if diverges(&program, engine).is_some() {
+                panic!("{}", report(&shrink(program, engine), engine));
+            }
+        }
+    }
+}
+
+#[test]
+fn test_random_instructions_are_valid() {
+    let mut random: Random = Random(SEED);
+    let mut op_codes: [bool; 16] = [false; 16];
+
+    for _ in 0..1000 {
+        let operation: u16 = random_instruction(&mut random);
+        op_codes[(operation >> 12) as usize] = true;
+
+        match operation >> 12 {
+            0x1 | 0x5 if operation & 0x20 == 0 => assert_eq!(operation & 0x18, 0),
+            0x9 => assert_eq!(operation & 0x3F, 0x3F),
+            0xC => assert_eq!(operation & 0xE3F, 0),
+            0x8 => assert_eq!(operation, 0x8000),
+            0xF => assert_eq!(operation & 0xF00, 0),
+            _ => {}
+        }
+    }
+
+    assert!(op_codes.iter().all(|seen| *seen));
+}
@@ -0,0 +1,125 @@
+// Turns instruction words back into assembly text
+
+use crate::assembler::SymbolTable;
+
+fn sign_extension(bits: u16, bit_count: usize) -> i16 {
+    ((bits << (16 - bit_count)) as i16) >> (16 - bit_count)
+}
+
+fn register(operation: u16, shift: u16) -> String {
+    format!("R{}", (operation >> shift) & 0x7)
+}
+
+// Name of the first label defined at an address
+pub fn label_at(symbols: &SymbolTable, address: u16) -> Option<&str> {
+    symbols
+        .iter()
+        .find(|(_, symbol)| **symbol == address)
+        .map(|(name, _)| name.as_str())
+}
+
+// PC relative operands are shown as the label or absolute address they resolve to
+fn target(address: u16, operation: u16, bit_count: usize, symbols: &SymbolTable) -> String {
+    let offset: i16 = sign_extension(operation & ((1 << bit_count) - 1), bit_count);
+    let destination: u16 = address.wrapping_add(1).wrapping_add(offset as u16);
+
+    match label_at(symbols, destination) {
+        Some(label) => label.to_string(),
+        None => format!("x{:04X}", destination),
+    }
+}
+
+pub fn disassemble(address: u16, operation: u16) -> String {
+    disassemble_with_symbols(address, operation, &SymbolTable::new())
+}
+
+pub fn disassemble_with_symbols(address: u16, operation: u16, symbols: &SymbolTable) -> String {
+    let op_code: u16 = operation >> 12;
+
+    match op_code {
+        0x1 | 0x5 => {
+            let name: &str = if op_code == 0x1 { "ADD" } else { "AND" };
+            let second: String = match operation & 0x0020 {
+                0 => register(operation, 0),
+                _ => format!("#{}", sign_extension(operation & 0x001F, 5)),
+            };
+
+            format!(
+                "{} {}, {}, {}",
+                name,
+                register(operation, 9),
+                register(operation, 6),
+                second
+            )
+        }
+        0x9 => format!("NOT {}, {}", register(operation, 9), register(operation, 6)),
+        0x0 => {
+            let flags: u16 = (operation >> 9) & 0x7;
+
+            match flags {
+                0 => String::from("NOP"),
+                _ => {
+                    let mut name: String = String::from("BR");
+
+                    for (bit, flag) in [(0x4, 'n'), (0x2, 'z'), (0x1, 'p')] {
+                        if flags & bit != 0 {
+                            name.push(flag);
+                        }
+                    }
+
+                    format!("{} {}", name, target(address, operation, 9, symbols))
+                }
+            }
+        }
+        0x2 | 0x3 | 0xA | 0xB | 0xE => {
+            let name: &str = match op_code {
+                0x2 => "LD",
+                0x3 => "ST",
+                0xA => "LDI",
+                0xB => "STI",
+                _ => "LEA",
+            };
+
+            format!(
+                "{} {}, {}",
+                name,
+                register(operation, 9),
+                target(address, operation, 9, symbols)
+            )
+        }
+        0x6 | 0x7 => {
+            let name: &str = if op_code == 0x6 { "LDR" } else { "STR" };
+
+            format!(
+                "{} {}, {}, #{}",
+                name,
+                register(operation, 9),
+                register(operation, 6),
+                sign_extension(operation & 0x003F, 6)
+            )
+        }
+        0xC => match (operation >> 6) & 0x7 {
+            7 => String::from("RET"),
+            _ => format!("JMP {}", register(operation, 6)),
+        },
+        0x4 => match operation & 0x0800 {
+            0 => format!("JSRR {}", register(operation, 6)),
+            _ => format!("JSR {}", target(address, operation, 11, symbols)),
+        },
+        0x8 => String::from("RTI"),
+        0xF => match operation & 0x00FF {
+            0x20 => String::from("GETC"),
+            0x21 => String::from("OUT"),
+            0x22 => String::from("PUTS"),
+            0x23 => String::from("IN"),
+            0x24 => String::from("PUTSP"),
+            0x25 => String::from("HALT"),
+            vector => format!("TRAP x{:02X}", vector),
+        },
+        _ => format!(".FILL x{:04X}", operation),
+    }
+}
+
+#[cfg(test)]
+#[path = "./disassembler_test.rs"]
+mod disassembler_test;
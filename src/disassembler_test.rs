@@ -0,0 +1,65 @@
+use crate::{
+    assembler::assemble,
+    disassembler::{disassemble, disassemble_with_symbols, label_at},
+};
+
+#[test]
+fn test_disassemble_operate() {
+    assert_eq!(disassemble(0x3000, 0b0001_0010_0000_0010), "ADD R1, R0, R2");
+    assert_eq!(
+        disassemble(0x3000, 0b0001_0010_0011_1111),
+        "ADD R1, R0, #-1"
+    );
+    assert_eq!(
+        disassemble(0x3000, 0b0101_0010_0010_1011),
+        "AND R1, R0, #11"
+    );
+    assert_eq!(disassemble(0x3000, 0b1001_0010_0011_1111), "NOT R1, R0");
+}
+
+#[test]
+fn test_disassemble_pc_relative() {
+    assert_eq!(disassemble(0x3000, 0b0000_1110_0000_1111), "BRnzp x3010");
+    assert_eq!(disassemble(0x30F6, 0b1110_0011_1111_1101), "LEA R1, x30F4");
+    assert_eq!(disassemble(0x3000, 0b0100_1000_0000_0010), "JSR x3003");
+    assert_eq!(disassemble(0x3000, 0b0110_0100_0000_0001), "LDR R2, R0, #1");
+}
+
+#[test]
+fn test_disassemble_control() {
+    assert_eq!(disassemble(0x3000, 0xC1C0), "RET");
+    assert_eq!(disassemble(0x3000, 0xC040), "JMP R1");
+    assert_eq!(disassemble(0x3000, 0x4040), "JSRR R1");
+    assert_eq!(disassemble(0x3000, 0x0000), "NOP");
+    assert_eq!(disassemble(0x3000, 0x8000), "RTI");
+}
+
+#[test]
+fn test_disassemble_traps() {
+    assert_eq!(disassemble(0x3000, 0xF025), "HALT");
+    assert_eq!(disassemble(0x3000, 0xF022), "PUTS");
+    assert_eq!(disassemble(0x3000, 0xF0FF), "TRAP xFF");
+    assert_eq!(disassemble(0x3000, 0xD123), ".FILL xD123");
+}
+
+#[test]
+fn test_disassemble_with_symbols() {
+    let symbols = assemble(".ORIG x3000\nLOOP BR LOOP\nJSR LOOP\n.END")
+        .unwrap()
+        .symbols;
+
+    assert_eq!(
+        disassemble_with_symbols(0x3000, 0x0FFF, &symbols),
+        "BRnzp LOOP"
+    );
+    assert_eq!(
+        disassemble_with_symbols(0x3001, 0x4FFE, &symbols),
+        "JSR LOOP"
+    );
+    assert_eq!(
+        disassemble_with_symbols(0x3001, 0x0E05, &symbols),
+        "BRnzp x3007"
+    );
+    assert_eq!(label_at(&symbols, 0x3000), Some("LOOP"));
+    assert_eq!(label_at(&symbols, 0x3001), None);
+}
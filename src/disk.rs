@@ -0,0 +1,71 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+};
+
+use crate::device::Storage;
+pub use crate::device::{DISK_ERROR, DISK_READ, DISK_READY, DISK_WRITE, SECTOR_WORDS};
+
+// Each word is stored big-endian in the image file
+const SECTOR_BYTES: u64 = SECTOR_WORDS as u64 * 2;
+
+// Block storage backed by a host image file. Sectors past the end of the file read as zeros
+// and writing them grows the file, so an empty file is a blank disk of any size.
+pub struct Disk {
+    file: File,
+}
+
+impl Disk {
+    // Opens an image for reading and writing, creating an empty one if needed
+    pub fn open(path: &str) -> io::Result<Self> {
+        let file: File = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+
+        Ok(Disk { file })
+    }
+
+    pub fn read_sector(&mut self, sector: u16) -> io::Result<[u16; SECTOR_WORDS]> {
+        let mut bytes: Vec<u8> = Vec::with_capacity(SECTOR_BYTES as usize);
+
+        self.file
+            .seek(SeekFrom::Start(sector as u64 * SECTOR_BYTES))?;
+        (&mut self.file)
+            .take(SECTOR_BYTES)
+            .read_to_end(&mut bytes)?;
+        bytes.resize(SECTOR_BYTES as usize, 0);
+
+        let mut words: [u16; SECTOR_WORDS] = [0; SECTOR_WORDS];
+        for (word, chunk) in words.iter_mut().zip(bytes.chunks(2)) {
+            *word = u16::from_be_bytes([chunk[0], chunk[1]]);
+        }
+
+        Ok(words)
+    }
+
+    pub fn write_sector(&mut self, sector: u16, words: &[u16]) -> io::Result<()> {
+        let bytes: Vec<u8> = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+
+        self.file
+            .seek(SeekFrom::Start(sector as u64 * SECTOR_BYTES))?;
+        self.file.write_all(&bytes)?;
+        self.file.flush()
+    }
+}
+
+impl Storage for Disk {
+    fn read_sector(&mut self, sector: u16) -> Option<[u16; SECTOR_WORDS]> {
+        Disk::read_sector(self, sector).ok()
+    }
+
+    fn write_sector(&mut self, sector: u16, words: &[u16; SECTOR_WORDS]) -> bool {
+        Disk::write_sector(self, sector, words).is_ok()
+    }
+}
+
+#[cfg(test)]
+#[path = "./disk_test.rs"]
+mod disk_test;
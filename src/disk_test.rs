@@ -0,0 +1,108 @@
+use crate::{
+    assembler::assemble,
+    cpu::{RunState, CPU},
+    disk::{Disk, DISK_ERROR, DISK_READY, SECTOR_WORDS},
+    memory::{Memory, DKBA, DKCR, DKSN, DKSR},
+};
+
+fn image(name: &str, contents: &[u8]) -> String {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, contents).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+#[test]
+fn test_sectors() {
+    let path: String = image("lc_3_disk_sectors.img", &[0x12, 0x34, 0x56]);
+    let mut disk: Disk = Disk::open(&path).unwrap();
+
+    // A partial sector at the end of the file, then nothing but zeros
+    let sector: [u16; SECTOR_WORDS] = disk.read_sector(0).unwrap();
+    assert_eq!(sector[..3], [0x1234, 0x5600, 0x0000]);
+    assert_eq!(disk.read_sector(1000).unwrap(), [0; SECTOR_WORDS]);
+
+    disk.write_sector(2, &[0xBEEF; SECTOR_WORDS]).unwrap();
+    let data: Vec<u8> = std::fs::read(&path).unwrap();
+    assert_eq!(data.len(), 3 * 512);
+    assert_eq!(data[1024..1026], [0xBE, 0xEF]);
+    assert_eq!(disk.read_sector(2).unwrap(), [0xBEEF; SECTOR_WORDS]);
+}
+
+#[test]
+fn test_registers() {
+    let mut memory: Memory = Memory::new();
+
+    // No disk attached
+    assert_eq!(memory.read(DKSR), 0);
+    memory.write(DKCR, 1);
+    assert_eq!(memory.read(DKSR), DISK_ERROR);
+
+    let path: String = image("lc_3_disk_registers.img", &[]);
+    memory.set_disk(Disk::open(&path).unwrap());
+    assert_eq!(memory.read(DKSR), DISK_READY);
+
+    // The buffer wraps around the end of memory
+    memory.poke(0xFFFF, 0xAAAA);
+    memory.poke(0x0000, 0xBBBB);
+    memory.write(DKSN, 7);
+    memory.write(DKBA, 0xFFFF);
+    memory.write(DKCR, 2);
+    assert_eq!(memory.read(DKSR), DISK_READY);
+
+    memory.write(DKBA, 0x4000);
+    memory.write(DKCR, 1);
+    assert_eq!(memory.peek(0x4000), 0xAAAA);
+    assert_eq!(memory.peek(0x4001), 0xBBBB);
+
+    memory.write(DKCR, 3);
+    assert_eq!(memory.read(DKSR), DISK_READY | DISK_ERROR);
+}
+
+#[test]
+fn test_program_copies_sector() {
+    // Writes a message to sector 5, then reads it back into a second buffer
+    let program = assemble(
+        "
+        .ORIG x3000
+        LD R0, FIVE
+        STI R0, SECTOR
+        LEA R0, MESSAGE
+        STI R0, BUFFER
+        AND R0, R0, #0
+        ADD R0, R0, #2
+        STI R0, COMMAND
+        LD R0, COPY
+        STI R0, BUFFER
+        AND R0, R0, #0
+        ADD R0, R0, #1
+        STI R0, COMMAND
+        LDI R1, STATUS
+        HALT
+FIVE    .FILL #5
+SECTOR  .FILL xFE14
+BUFFER  .FILL xFE16
+COMMAND .FILL xFE12
+STATUS  .FILL xFE10
+COPY    .FILL x5000
+MESSAGE .STRINGZ \"disk\"
+        .END
+        ",
+    )
+    .unwrap();
+
+    let path: String = image("lc_3_disk_program.img", &[]);
+    let mut cpu: CPU = CPU::with_console(Box::new(crate::console::BufferedConsole::new(&[])));
+    cpu.memory_mut().set_disk(Disk::open(&path).unwrap());
+    cpu.load_image(&program.to_object()).unwrap();
+
+    assert_eq!(cpu.run(Some(1000)), RunState::Halted);
+    assert_eq!(cpu.registers()[1], DISK_READY);
+    assert_eq!(
+        cpu.memory().words(0x5000, 0x5004),
+        [0x64, 0x69, 0x73, 0x6B, 0]
+    );
+
+    let data: Vec<u8> = std::fs::read(&path).unwrap();
+    assert_eq!(data.len(), 6 * 512);
+    assert_eq!(data[5 * 512..5 * 512 + 4], [0x00, 0x64, 0x00, 0x69]);
+}
@@ -0,0 +1,105 @@
+use std::path::Path;
+
+use crate::{image, memory::Memory};
+
+const HEX_WORDS_PER_LINE: usize = 8;
+
+// File formats for saving a range of memory and loading it back
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DumpFormat {
+    // Big-endian words only, loading needs to be told where they go
+    Raw,
+    // Big-endian words after their origin, like an assembled program
+    Object,
+    // Lines of an address followed by up to eight words, all in hex
+    Hex,
+}
+
+impl DumpFormat {
+    // Picks the format from a file's extension, anything unknown is raw
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path)
+            .extension()
+            .and_then(|extension| extension.to_str())
+        {
+            Some("obj") => DumpFormat::Object,
+            Some("hex") | Some("txt") => DumpFormat::Hex,
+            _ => DumpFormat::Raw,
+        }
+    }
+}
+
+// The words from `start` to `end` inclusive, without device side effects
+pub fn dump(memory: &Memory, start: u16, end: u16, format: DumpFormat) -> Vec<u8> {
+    let words: Vec<u16> = memory.words(start, end);
+
+    match format {
+        DumpFormat::Raw => words.iter().flat_map(|word| word.to_be_bytes()).collect(),
+        DumpFormat::Object => std::iter::once(start)
+            .chain(words.iter().copied())
+            .flat_map(|word| word.to_be_bytes())
+            .collect(),
+        DumpFormat::Hex => {
+            let mut text: String = String::new();
+
+            for (index, line) in words.chunks(HEX_WORDS_PER_LINE).enumerate() {
+                let address: u16 = start.wrapping_add((index * HEX_WORDS_PER_LINE) as u16);
+                let line: Vec<String> = line.iter().map(|word| format!("{:04X}", word)).collect();
+
+                text.push_str(&format!("x{:04X}: {}\n", address, line.join(" ")));
+            }
+
+            text.into_bytes()
+        }
+    }
+}
+
+// Writes a dump back into memory, raw dumps at `address`. Returns the first address written
+// and the number of words
+pub fn load(
+    memory: &mut Memory,
+    data: &[u8],
+    format: DumpFormat,
+    address: Option<u16>,
+) -> Result<(u16, usize), String> {
+    let (start, words): (u16, Vec<u16>) = match format {
+        DumpFormat::Raw => match address {
+            Some(address) => (address, words(data)?),
+            None => return Err(String::from("raw dumps need a load address")),
+        },
+        DumpFormat::Object => {
+            let mut words: Vec<u16> = words(data)?;
+
+            if words.is_empty() {
+                return Err(String::from("object dump is missing its origin"));
+            }
+            let origin: u16 = words.remove(0);
+            (address.unwrap_or(origin), words)
+        }
+        DumpFormat::Hex => {
+            let (origin, words) = image::parse_hex_dump(&String::from_utf8_lossy(data))?;
+            (address.unwrap_or(origin), words)
+        }
+    };
+
+    for (offset, word) in words.iter().enumerate() {
+        memory.poke(start.wrapping_add(offset as u16), *word);
+    }
+
+    Ok((start, words.len()))
+}
+
+fn words(data: &[u8]) -> Result<Vec<u16>, String> {
+    if !data.len().is_multiple_of(2) {
+        return Err(String::from("dump has an odd number of bytes"));
+    }
+
+    Ok(data
+        .chunks(2)
+        .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+        .collect())
+}
+
+#[cfg(test)]
+#[path = "./dump_test.rs"]
+mod dump_test;
@@ -0,0 +1,111 @@
+use crate::{
+    dump::{dump, load, DumpFormat},
+    memory::{Memory, DDR},
+};
+
+fn memory() -> Memory {
+    let mut memory: Memory = Memory::new();
+
+    for offset in 0..10u16 {
+        memory.poke(0x3000 + offset, 0x1000 + offset);
+    }
+    memory
+}
+
+#[test]
+fn test_format_from_path() {
+    assert_eq!(DumpFormat::from_path("data.obj"), DumpFormat::Object);
+    assert_eq!(DumpFormat::from_path("dir/data.hex"), DumpFormat::Hex);
+    assert_eq!(DumpFormat::from_path("data.bin"), DumpFormat::Raw);
+    assert_eq!(DumpFormat::from_path("data"), DumpFormat::Raw);
+}
+
+#[test]
+fn test_dump_formats() {
+    let memory: Memory = memory();
+
+    assert_eq!(
+        dump(&memory, 0x3000, 0x3001, DumpFormat::Raw),
+        vec![0x10, 0x00, 0x10, 0x01]
+    );
+    assert_eq!(
+        dump(&memory, 0x3001, 0x3001, DumpFormat::Object),
+        vec![0x30, 0x01, 0x10, 0x01]
+    );
+    assert_eq!(
+        String::from_utf8(dump(&memory, 0x3000, 0x3009, DumpFormat::Hex)).unwrap(),
+        "x3000: 1000 1001 1002 1003 1004 1005 1006 1007\nx3008: 1008 1009\n"
+    );
+    assert_eq!(
+        dump(&memory, 0x0000, 0xFFFF, DumpFormat::Raw).len(),
+        0x20000
+    );
+}
+
+#[test]
+fn test_round_trip() {
+    let source: Memory = memory();
+
+    for format in [DumpFormat::Raw, DumpFormat::Object, DumpFormat::Hex] {
+        let data: Vec<u8> = dump(&source, 0x3000, 0x3009, format);
+        let mut memory: Memory = Memory::new();
+
+        assert_eq!(
+            load(&mut memory, &data, format, Some(0x3000)),
+            Ok((0x3000, 10))
+        );
+        assert_eq!(memory.words(0x3000, 0x3009), source.words(0x3000, 0x3009));
+    }
+
+    // Object and hex dumps know where they belong, but can be moved
+    let mut memory: Memory = Memory::new();
+    let data: Vec<u8> = dump(&source, 0x3000, 0x3009, DumpFormat::Hex);
+    assert_eq!(
+        load(&mut memory, &data, DumpFormat::Hex, None),
+        Ok((0x3000, 10))
+    );
+    assert_eq!(
+        load(&mut memory, &data, DumpFormat::Hex, Some(0x4000)),
+        Ok((0x4000, 10))
+    );
+    assert_eq!(memory.peek(0x4009), 0x1009);
+}
+
+#[test]
+fn test_load_skips_devices() {
+    let mut memory: Memory =
+        Memory::with_console(Box::new(crate::console::BufferedConsole::new(&[])));
+
+    load(&mut memory, &[0x00, 0x41], DumpFormat::Raw, Some(DDR)).unwrap();
+    assert_eq!(memory.peek(DDR), 0x41);
+}
+
+#[test]
+fn test_load_errors() {
+    let mut memory: Memory = Memory::new();
+    let hex = |text: &str| load(&mut Memory::new(), text.as_bytes(), DumpFormat::Hex, None);
+
+    assert!(load(&mut memory, &[0x00, 0x01], DumpFormat::Raw, None).is_err());
+    assert!(load(&mut memory, &[0x00], DumpFormat::Raw, Some(0)).is_err());
+    assert!(load(&mut memory, &[], DumpFormat::Object, None).is_err());
+
+    assert_eq!(hex("; nothing\n"), Err(String::from("hex dump is empty")));
+    assert_eq!(
+        hex("x3000: 1234\nx3002: 5678"),
+        Err(String::from(
+            "line 2: x3002 does not follow the previous line"
+        ))
+    );
+    assert_eq!(
+        hex("x3000: 12G4"),
+        Err(String::from("line 1: invalid word '12G4'"))
+    );
+    assert_eq!(
+        hex("3000: 1234"),
+        Err(String::from("line 1: invalid address '3000'"))
+    );
+    assert_eq!(
+        hex("x3000 1234"),
+        Err(String::from("line 1: expected 'xADDR: WORD ...'"))
+    );
+}
@@ -0,0 +1,163 @@
+// Object images come as binary .obj files or as text with one 16-bit word per line, written
+// either as hex digits (.hex) or as a string of sixteen 0s and 1s (.bin). Like in a .obj file
+// the first word is the origin. Hex memory dumps (xADDR: WORD ...) load as images starting at
+// their first address. Text images may contain blank lines and ';' comments.
+
+use alloc::{format, string::String, vec::Vec};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Object,
+    Hex,
+    Binary,
+    // Address-prefixed lines of a hex memory dump
+    HexDump,
+}
+
+// Text images are lines of printable ASCII, which a binary image hardly ever is: its origin
+// alone usually holds a zero byte
+pub fn detect(data: &[u8]) -> ImageFormat {
+    let text: bool = data.contains(&b'\n')
+        && data
+            .iter()
+            .all(|&byte| byte.is_ascii_graphic() || byte.is_ascii_whitespace());
+
+    if !text {
+        return ImageFormat::Object;
+    }
+
+    match String::from_utf8_lossy(data)
+        .lines()
+        .map(strip)
+        .find(|line| !line.is_empty())
+    {
+        Some(line) if line.len() == 16 && line.bytes().all(|byte| byte == b'0' || byte == b'1') => {
+            ImageFormat::Binary
+        }
+        Some(line) if line.contains(':') => ImageFormat::HexDump,
+        _ => ImageFormat::Hex,
+    }
+}
+
+// Converts an image in any of the formats to the binary .obj layout
+pub fn object_image(data: &[u8]) -> Result<Vec<u8>, String> {
+    let format: ImageFormat = detect(data);
+
+    match format {
+        ImageFormat::Object => return Ok(data.to_vec()),
+        ImageFormat::HexDump => {
+            let (origin, words) = parse_hex_dump(&String::from_utf8_lossy(data))?;
+
+            return Ok(core::iter::once(origin)
+                .chain(words)
+                .flat_map(|word| word.to_be_bytes())
+                .collect());
+        }
+        _ => {}
+    }
+
+    let mut image: Vec<u8> = Vec::new();
+
+    for (index, line) in String::from_utf8_lossy(data).lines().enumerate() {
+        let word: &str = strip(line);
+
+        if word.is_empty() {
+            continue;
+        }
+
+        let value: Option<u16> = match format {
+            ImageFormat::Binary if word.len() == 16 => u16::from_str_radix(word, 2).ok(),
+            ImageFormat::Binary => None,
+            _ => {
+                let digits: &str = word
+                    .strip_prefix("0x")
+                    .or_else(|| word.strip_prefix('x'))
+                    .unwrap_or(word);
+
+                match digits.len() {
+                    1..=4 => u16::from_str_radix(digits, 16).ok(),
+                    _ => None,
+                }
+            }
+        };
+
+        match (value, format) {
+            (Some(value), _) => image.extend(value.to_be_bytes()),
+            (None, ImageFormat::Binary) => {
+                return Err(format!(
+                    "line {}: expected 16 binary digits, found '{}'",
+                    index + 1,
+                    word
+                ))
+            }
+            (None, _) => {
+                return Err(format!(
+                    "line {}: expected a word of 1 to 4 hex digits, found '{}'",
+                    index + 1,
+                    word
+                ))
+            }
+        }
+    }
+
+    if image.is_empty() {
+        return Err(String::from("Image is missing its origin"));
+    }
+
+    Ok(image)
+}
+
+// Hex dumps as written by --dump, lines of an address followed by the words stored from there.
+// They list consecutive words, each line has to continue where the previous one ended
+pub fn parse_hex_dump(text: &str) -> Result<(u16, Vec<u16>), String> {
+    let mut origin: Option<u16> = None;
+    let mut words: Vec<u16> = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line: &str = line.split(';').next().unwrap_or("").trim();
+        let error = |message: String| Err(format!("line {}: {}", index + 1, message));
+
+        if line.is_empty() {
+            continue;
+        }
+
+        let Some((address, values)) = line.split_once(':') else {
+            return error(String::from("expected 'xADDR: WORD ...'"));
+        };
+        let address: u16 = match address.trim().strip_prefix('x') {
+            Some(hex) => match u16::from_str_radix(hex, 16) {
+                Ok(address) => address,
+                Err(_) => return error(format!("invalid address '{}'", address.trim())),
+            },
+            None => return error(format!("invalid address '{}'", address.trim())),
+        };
+
+        let origin: u16 = *origin.get_or_insert(address);
+        if address != origin.wrapping_add(words.len() as u16) {
+            return error(format!(
+                "x{:04X} does not follow the previous line",
+                address
+            ));
+        }
+
+        for value in values.split_whitespace() {
+            match u16::from_str_radix(value, 16) {
+                Ok(word) => words.push(word),
+                Err(_) => return error(format!("invalid word '{}'", value)),
+            }
+        }
+    }
+
+    match origin {
+        Some(origin) => Ok((origin, words)),
+        None => Err(String::from("hex dump is empty")),
+    }
+}
+
+fn strip(line: &str) -> &str {
+    line.split(';').next().unwrap_or("").trim()
+}
+
+#[cfg(all(test, feature = "std"))]
+#[path = "./image_test.rs"]
+mod image_test;
@@ -0,0 +1,105 @@
+use crate::{
+    cpu::{RunState, CPU},
+    dump::{dump, DumpFormat},
+    image::{detect, object_image, ImageFormat},
+    memory::Memory,
+};
+
+const HELLO: &[u8] = include_bytes!("../resources/hello-world.obj");
+
+fn hex(image: &[u8]) -> String {
+    image
+        .chunks(2)
+        .map(|word| format!("{:02X}{:02X}\n", word[0], word[1]))
+        .collect()
+}
+
+fn binary(image: &[u8]) -> String {
+    image
+        .chunks(2)
+        .map(|word| format!("{:016b}\n", u16::from_be_bytes([word[0], word[1]])))
+        .collect()
+}
+
+#[test]
+fn test_detect() {
+    assert_eq!(detect(HELLO), ImageFormat::Object);
+    assert_eq!(detect(hex(HELLO).as_bytes()), ImageFormat::Hex);
+    assert_eq!(detect(binary(HELLO).as_bytes()), ImageFormat::Binary);
+    assert_eq!(
+        detect(b"; origin\n\n0011000000000000\n"),
+        ImageFormat::Binary
+    );
+    assert_eq!(detect(b""), ImageFormat::Object);
+    assert_eq!(detect(b"x3000: F025\n"), ImageFormat::HexDump);
+}
+
+#[test]
+fn test_hex_dump_image() {
+    let mut memory: Memory = Memory::new();
+    memory.poke(0x3000, 0xE002);
+    memory.poke(0x3009, 0xF025);
+
+    // What --dump writes to a .hex file runs as a program again
+    let data: Vec<u8> = dump(&memory, 0x3000, 0x3009, DumpFormat::Hex);
+    let image: Vec<u8> = object_image(&data).unwrap();
+    assert_eq!(image.len(), 22);
+    assert_eq!(image[..4], [0x30, 0x00, 0xE0, 0x02]);
+    assert_eq!(image[20..], [0xF0, 0x25]);
+
+    assert_eq!(
+        object_image(b"x3000: F025\nx3002: F025\n"),
+        Err(String::from(
+            "line 2: x3002 does not follow the previous line"
+        ))
+    );
+}
+
+#[test]
+fn test_text_images() {
+    assert_eq!(object_image(HELLO).unwrap(), HELLO);
+    assert_eq!(object_image(hex(HELLO).as_bytes()).unwrap(), HELLO);
+    assert_eq!(object_image(binary(HELLO).as_bytes()).unwrap(), HELLO);
+    assert_eq!(
+        object_image(b"x3000 ; origin\r\n\r\n0xF025\r\n25\n").unwrap(),
+        vec![0x30, 0x00, 0xF0, 0x25, 0x00, 0x25]
+    );
+}
+
+#[test]
+fn test_malformed_lines() {
+    assert_eq!(
+        object_image(b"3000\nF025\nHALT\n"),
+        Err(String::from(
+            "line 3: expected a word of 1 to 4 hex digits, found 'HALT'"
+        ))
+    );
+    assert_eq!(
+        object_image(b"3000\n12345\n"),
+        Err(String::from(
+            "line 2: expected a word of 1 to 4 hex digits, found '12345'"
+        ))
+    );
+    assert_eq!(
+        object_image(b"0011000000000000\n1111000000100101\n0101\n"),
+        Err(String::from(
+            "line 3: expected 16 binary digits, found '0101'"
+        ))
+    );
+    assert_eq!(
+        object_image(b"; nothing here\n"),
+        Err(String::from("Image is missing its origin"))
+    );
+}
+
+#[test]
+fn test_run_text_image() {
+    let mut cpu: CPU = CPU::new();
+
+    assert_eq!(cpu.load_image(binary(HELLO).as_bytes()), Ok(0x3000));
+    assert_eq!(cpu.memory().words(0x3000, 0x3002), [0xE002, 0xF022, 0xF025]);
+
+    let mut cpu: CPU = CPU::with_console(Box::new(crate::console::BufferedConsole::new(&[])));
+    cpu.load_image(hex(HELLO).as_bytes()).unwrap();
+    assert_eq!(cpu.run(Some(100)), RunState::Halted);
+}
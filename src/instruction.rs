@@ -0,0 +1,117 @@
+// Instruction words decoded once into their operands, used by the predecoded execution engine
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    // Operate instructions
+    Add { dst: u8, src1: u8, src2: u8 },
+    AddImmediate { dst: u8, src1: u8, immediate: u16 },
+    And { dst: u8, src1: u8, src2: u8 },
+    AndImmediate { dst: u8, src1: u8, immediate: u16 },
+    Not { dst: u8, src: u8 },
+    // Data Movement instructions, offsets are already sign extended
+    Load { dst: u8, offset: u16 },
+    LoadIndirect { dst: u8, offset: u16 },
+    LoadOffset { dst: u8, base: u8, offset: u16 },
+    LoadImmediate { dst: u8, offset: u16 },
+    Store { src: u8, offset: u16 },
+    StoreIndirect { src: u8, offset: u16 },
+    StoreOffset { src: u8, base: u8, offset: u16 },
+    // Control instructions
+    Branch { condition_codes: u16, offset: u16 },
+    Jump { base: u8 },
+    JumpSubroutine { offset: u16 },
+    JumpSubroutineRegister { base: u8 },
+    Trap { vector: u8 },
+    ReturnFromInterrupt,
+    // Reserved instruction
+    Reserved,
+}
+
+// Widens the low `bit_count` bits to a word, used by both the interpreter and the predecoder
+pub fn sign_extension(mut bits: u16, bit_count: usize) -> u16 {
+    if (bits >> (bit_count - 1)) & 1 == 1 {
+        bits |= 0xFFFF << bit_count;
+    }
+
+    bits
+}
+
+pub fn decode(operation: u16) -> Instruction {
+    let dst: u8 = ((operation & 0x0E00) >> 9) as u8;
+    let src1: u8 = ((operation & 0x01C0) >> 6) as u8;
+    let pc_offset: u16 = sign_extension(operation & 0x01FF, 9);
+    let offset: u16 = sign_extension(operation & 0x003F, 6);
+    let immediate_mode: bool = operation & 0x0020 != 0;
+
+    match operation >> 12 {
+        0x1 if immediate_mode => Instruction::AddImmediate {
+            dst,
+            src1,
+            immediate: sign_extension(operation & 0x001F, 5),
+        },
+        0x1 => Instruction::Add {
+            dst,
+            src1,
+            src2: (operation & 0x0007) as u8,
+        },
+        0x5 if immediate_mode => Instruction::AndImmediate {
+            dst,
+            src1,
+            immediate: sign_extension(operation & 0x001F, 5),
+        },
+        0x5 => Instruction::And {
+            dst,
+            src1,
+            src2: (operation & 0x0007) as u8,
+        },
+        0x9 => Instruction::Not { dst, src: src1 },
+        0x2 => Instruction::Load {
+            dst,
+            offset: pc_offset,
+        },
+        0xA => Instruction::LoadIndirect {
+            dst,
+            offset: pc_offset,
+        },
+        0x6 => Instruction::LoadOffset {
+            dst,
+            base: src1,
+            offset,
+        },
+        0xE => Instruction::LoadImmediate {
+            dst,
+            offset: pc_offset,
+        },
+        0x3 => Instruction::Store {
+            src: dst,
+            offset: pc_offset,
+        },
+        0xB => Instruction::StoreIndirect {
+            src: dst,
+            offset: pc_offset,
+        },
+        0x7 => Instruction::StoreOffset {
+            src: dst,
+            base: src1,
+            offset,
+        },
+        0x0 => Instruction::Branch {
+            condition_codes: dst as u16,
+            offset: pc_offset,
+        },
+        0xC => Instruction::Jump { base: src1 },
+        0x4 if operation & 0x0800 != 0 => Instruction::JumpSubroutine {
+            offset: sign_extension(operation & 0x07FF, 11),
+        },
+        0x4 => Instruction::JumpSubroutineRegister { base: src1 },
+        0xF => Instruction::Trap {
+            vector: (operation & 0x00FF) as u8,
+        },
+        0x8 => Instruction::ReturnFromInterrupt,
+        _ => Instruction::Reserved,
+    }
+}
+
+#[cfg(test)]
+#[path = "./instruction_test.rs"]
+mod instruction_test;
@@ -0,0 +1,86 @@
+use crate::instruction::{decode, sign_extension, Instruction};
+
+#[test]
+fn test_decode_operate() {
+    assert_eq!(
+        decode(0b0001_0010_0000_0010),
+        Instruction::Add {
+            dst: 1,
+            src1: 0,
+            src2: 2
+        }
+    );
+    assert_eq!(
+        decode(0b0001_0010_0011_1111),
+        Instruction::AddImmediate {
+            dst: 1,
+            src1: 0,
+            immediate: 0xFFFF
+        }
+    );
+    assert_eq!(
+        decode(0b0101_0010_0010_1011),
+        Instruction::AndImmediate {
+            dst: 1,
+            src1: 0,
+            immediate: 0x000B
+        }
+    );
+    assert_eq!(
+        decode(0b1001_0010_0011_1111),
+        Instruction::Not { dst: 1, src: 0 }
+    );
+}
+
+#[test]
+fn test_decode_data_movement() {
+    assert_eq!(
+        decode(0b1110_0011_1111_1101),
+        Instruction::LoadImmediate {
+            dst: 1,
+            offset: 0xFFFD
+        }
+    );
+    assert_eq!(
+        decode(0b0111_0100_0011_1111),
+        Instruction::StoreOffset {
+            src: 2,
+            base: 0,
+            offset: 0xFFFF
+        }
+    );
+    assert_eq!(
+        decode(0b1011_0100_0000_0001),
+        Instruction::StoreIndirect { src: 2, offset: 1 }
+    );
+}
+
+#[test]
+fn test_decode_control() {
+    assert_eq!(
+        decode(0b0000_1110_0000_1111),
+        Instruction::Branch {
+            condition_codes: 0x7,
+            offset: 0xF
+        }
+    );
+    assert_eq!(
+        decode(0b0100_1111_1111_1111),
+        Instruction::JumpSubroutine { offset: 0xFFFF }
+    );
+    assert_eq!(
+        decode(0b0100_0000_1100_0000),
+        Instruction::JumpSubroutineRegister { base: 3 }
+    );
+    assert_eq!(decode(0xC1C0), Instruction::Jump { base: 7 });
+    assert_eq!(decode(0xF025), Instruction::Trap { vector: 0x25 });
+    assert_eq!(decode(0x8000), Instruction::ReturnFromInterrupt);
+    assert_eq!(decode(0xD000), Instruction::Reserved);
+}
+
+#[test]
+fn test_sign_extension() {
+    assert_eq!(sign_extension(0b1_0000, 5), 0xFFF0);
+    assert_eq!(sign_extension(0b0_1111, 5), 0x000F);
+    assert_eq!(sign_extension(0x0400, 11), 0xFC00);
+}
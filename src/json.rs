@@ -0,0 +1,280 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+// Just enough JSON for the debug adapter protocol
+#[derive(Clone, Debug, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    // Members keep their order, protocol messages are small enough to search linearly
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    pub fn object<const N: usize>(members: [(&str, Json); N]) -> Self {
+        Json::Object(
+            members
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+    }
+
+    // Member of an object, Null for anything missing
+    pub fn get(&self, key: &str) -> &Json {
+        match self {
+            Json::Object(members) => members
+                .iter()
+                .find(|(name, _)| name == key)
+                .map_or(&Json::Null, |(_, value)| value),
+            _ => &Json::Null,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Json::Number(number) if number.fract() == 0.0 => Some(*number as i64),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> &[Json] {
+        match self {
+            Json::Array(values) => values,
+            _ => &[],
+        }
+    }
+}
+
+impl From<bool> for Json {
+    fn from(value: bool) -> Self {
+        Json::Bool(value)
+    }
+}
+
+impl From<i64> for Json {
+    fn from(value: i64) -> Self {
+        Json::Number(value as f64)
+    }
+}
+
+impl From<&str> for Json {
+    fn from(text: &str) -> Self {
+        Json::String(text.to_string())
+    }
+}
+
+impl From<String> for Json {
+    fn from(text: String) -> Self {
+        Json::String(text)
+    }
+}
+
+impl From<Vec<Json>> for Json {
+    fn from(values: Vec<Json>) -> Self {
+        Json::Array(values)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(text) => write_string(f, text),
+            Json::Array(values) => {
+                write!(f, "[")?;
+                for (index, value) in values.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ":{}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+fn write_string(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for character in text.chars() {
+        match character {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            character if (character as u32) < 0x20 => write!(f, "\\u{:04x}", character as u32)?,
+            character => write!(f, "{}", character)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+pub fn parse(text: &str) -> Result<Json, String> {
+    let mut chars: Peekable<Chars> = text.chars().peekable();
+    let value: Json = parse_value(&mut chars)?;
+
+    skip_whitespace(&mut chars);
+    match chars.next() {
+        None => Ok(value),
+        Some(character) => Err(format!("unexpected '{}' after the value", character)),
+    }
+}
+
+fn skip_whitespace(chars: &mut Peekable<Chars>) {
+    while chars
+        .next_if(|character| character.is_whitespace())
+        .is_some()
+    {}
+}
+
+fn expect(chars: &mut Peekable<Chars>, word: &str, value: Json) -> Result<Json, String> {
+    for expected in word.chars() {
+        if chars.next() != Some(expected) {
+            return Err(format!("expected '{}'", word));
+        }
+    }
+
+    Ok(value)
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Result<Json, String> {
+    skip_whitespace(chars);
+
+    match chars.peek() {
+        Some('n') => expect(chars, "null", Json::Null),
+        Some('t') => expect(chars, "true", Json::Bool(true)),
+        Some('f') => expect(chars, "false", Json::Bool(false)),
+        Some('"') => parse_string(chars).map(Json::String),
+        Some('[') => {
+            chars.next();
+            let mut values: Vec<Json> = Vec::new();
+
+            skip_whitespace(chars);
+            if chars.next_if_eq(&']').is_some() {
+                return Ok(Json::Array(values));
+            }
+
+            loop {
+                values.push(parse_value(chars)?);
+                skip_whitespace(chars);
+
+                match chars.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Json::Array(values)),
+                    _ => return Err(String::from("expected ',' or ']' in array")),
+                }
+            }
+        }
+        Some('{') => {
+            chars.next();
+            let mut members: Vec<(String, Json)> = Vec::new();
+
+            skip_whitespace(chars);
+            if chars.next_if_eq(&'}').is_some() {
+                return Ok(Json::Object(members));
+            }
+
+            loop {
+                skip_whitespace(chars);
+                if chars.peek() != Some(&'"') {
+                    return Err(String::from("expected a member name"));
+                }
+                let key: String = parse_string(chars)?;
+
+                skip_whitespace(chars);
+                if chars.next() != Some(':') {
+                    return Err(format!("expected ':' after \"{}\"", key));
+                }
+                members.push((key, parse_value(chars)?));
+                skip_whitespace(chars);
+
+                match chars.next() {
+                    Some(',') => {}
+                    Some('}') => return Ok(Json::Object(members)),
+                    _ => return Err(String::from("expected ',' or '}' in object")),
+                }
+            }
+        }
+        Some(character) if *character == '-' || character.is_ascii_digit() => {
+            let mut number: String = String::new();
+
+            while let Some(character) = chars.next_if(|character| {
+                character.is_ascii_digit() || matches!(character, '-' | '+' | '.' | 'e' | 'E')
+            }) {
+                number.push(character);
+            }
+
+            number
+                .parse::<f64>()
+                .map(Json::Number)
+                .map_err(|_| format!("invalid number '{}'", number))
+        }
+        Some(character) => Err(format!("unexpected '{}'", character)),
+        None => Err(String::from("unexpected end of input")),
+    }
+}
+
+fn parse_string(chars: &mut Peekable<Chars>) -> Result<String, String> {
+    let mut text: String = String::new();
+
+    chars.next();
+    loop {
+        match chars.next() {
+            Some('"') => return Ok(text),
+            Some('\\') => match chars.next() {
+                Some('n') => text.push('\n'),
+                Some('r') => text.push('\r'),
+                Some('t') => text.push('\t'),
+                Some('b') => text.push('\u{8}'),
+                Some('f') => text.push('\u{c}'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    let code: u32 = u32::from_str_radix(&hex, 16)
+                        .map_err(|_| format!("invalid escape '\\u{}'", hex))?;
+
+                    // Surrogate pairs are not needed for file paths and program text
+                    text.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+                Some(character) => text.push(character),
+                None => return Err(String::from("unterminated string")),
+            },
+            Some(character) => text.push(character),
+            None => return Err(String::from("unterminated string")),
+        }
+    }
+}
+
+#[cfg(test)]
+#[path = "./json_test.rs"]
+mod json_test;
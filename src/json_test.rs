@@ -0,0 +1,60 @@
+use crate::json::{parse, Json};
+
+#[test]
+fn test_parse() {
+    let value: Json = parse(
+        r#" {"seq": 3, "type": "request", "arguments": {"lines": [1, -2.5e1, true, null],
+            "path": "C:\\lc3\\\"a\"\n\u0041"}} "#,
+    )
+    .unwrap();
+
+    assert_eq!(value.get("seq").as_i64(), Some(3));
+    assert_eq!(value.get("type").as_str(), Some("request"));
+    assert_eq!(value.get("missing"), &Json::Null);
+
+    let arguments: &Json = value.get("arguments");
+    assert_eq!(
+        arguments.get("lines").as_array(),
+        [
+            Json::Number(1.0),
+            Json::Number(-25.0),
+            Json::Bool(true),
+            Json::Null
+        ]
+    );
+    assert_eq!(arguments.get("path").as_str(), Some("C:\\lc3\\\"a\"\nA"));
+    assert_eq!(parse("[ ]").unwrap(), Json::Array(Vec::new()));
+    assert_eq!(parse("{}").unwrap(), Json::Object(Vec::new()));
+}
+
+#[test]
+fn test_parse_errors() {
+    for text in [
+        "",
+        "{",
+        "[1,]",
+        "{\"a\" 1}",
+        "\"open",
+        "nul",
+        "1 2",
+        "{1: 2}",
+    ] {
+        assert!(parse(text).is_err(), "{}", text);
+    }
+}
+
+#[test]
+fn test_display() {
+    let value: Json = Json::object([
+        ("seq", 1.into()),
+        ("text", "tab\there \"quoted\"\u{1}".into()),
+        ("list", vec![Json::Null, false.into(), (-7).into()].into()),
+    ]);
+    let text: String = value.to_string();
+
+    assert_eq!(
+        text,
+        r#"{"seq":1,"text":"tab\there \"quoted\"\u0001","list":[null,false,-7]}"#
+    );
+    assert_eq!(parse(&text).unwrap(), value);
+}
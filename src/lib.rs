@@ -0,0 +1,38 @@
+// The emulator core builds without the standard library when the `std` feature is off. Loading
+// files, the host terminal, devices backed by host resources and the tools need it
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+pub mod assembler;
+pub mod console;
+#[cfg(feature = "std")]
+pub mod coverage;
+pub mod cpu;
+pub mod device;
+#[cfg(feature = "std")]
+pub mod disassembler;
+#[cfg(feature = "std")]
+pub mod disk;
+#[cfg(feature = "std")]
+pub mod dump;
+pub mod image;
+pub mod instruction;
+pub mod memory;
+pub mod observer;
+pub mod paging;
+#[cfg(feature = "std")]
+pub mod profiler;
+#[cfg(feature = "std")]
+pub mod screen;
+#[cfg(feature = "std")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod semihost;
+pub mod timer;
+pub mod trap;
+#[cfg(feature = "std")]
+pub mod uart;
+#[cfg(feature = "std")]
+pub mod video;
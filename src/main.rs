@@ -1,34 +1,69 @@
 extern crate termios;
 
-use std::env;
+use std::{env, process::ExitCode};
 use termios::*;
 
-use crate::cpu::CPU;
+use crate::cli::{CliError, Options};
 
-mod cpu;
-mod memory;
+mod cli;
+mod dap;
+mod debugger;
+mod json;
+mod tui;
 
-fn main() {
-    // Collect file path
-    let args: Vec<String> = env::args().collect();
-    let file_path: &String = &args[1];
+// Unix-based os terminal configuration to make it interactive for the VM, restored on drop
+struct RawTerminal {
+    original: Termios,
+}
+
+impl RawTerminal {
+    fn enable() -> Option<Self> {
+        let stdin = 0;
+        let termios = termios::Termios::from_fd(stdin).ok()?;
+
+        // Make mutable and copy
+        let mut new_termios = termios;
+        new_termios.c_iflag &= IGNBRK | BRKINT | PARMRK | ISTRIP | INLCR | IGNCR | ICRNL | IXON;
+        new_termios.c_lflag &= !(ICANON | ECHO); // No echo and canonical mode
+
+        tcsetattr(stdin, TCSANOW, &new_termios).ok()?;
 
-    // Unix-based os terminal configuration to make it interactive for the VM
-    let stdin = 0;
-    let termios = termios::Termios::from_fd(stdin).unwrap();
+        Some(RawTerminal { original: termios })
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        // Reset the stdin to original termios data
+        let _ = tcsetattr(0, TCSANOW, &self.original);
+    }
+}
 
-    // Make mutable and copy
-    let mut new_termios = termios.clone();
-    new_termios.c_iflag &= IGNBRK | BRKINT | PARMRK | ISTRIP | INLCR | IGNCR | ICRNL | IXON;
-    new_termios.c_lflag &= !(ICANON | ECHO); // No echo and canonical mode
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
 
-    tcsetattr(stdin, TCSANOW, &mut new_termios).unwrap();
+    let options: Options = match cli::parse_args(&args) {
+        Ok(options) => options,
+        Err(error) => {
+            eprintln!("lc_3: {}\n\n{}", error, cli::USAGE);
+            return ExitCode::from(error.exit_code());
+        }
+    };
 
-    println!("Attempting to execute program: {}", file_path);
+    let result: Result<(), CliError> = {
+        let _terminal: Option<RawTerminal> = match options.raw_terminal() {
+            true => RawTerminal::enable(),
+            false => None,
+        };
 
-    let mut cpu: CPU = CPU::new();
-    cpu.execute_program(file_path);
+        cli::execute(&options)
+    };
 
-    // Reset the stdin to original termios data
-    tcsetattr(stdin, TCSANOW, &termios).unwrap();
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("lc_3: {}", error);
+            ExitCode::from(error.exit_code())
+        }
+    }
 }
@@ -1,39 +1,376 @@
-use std::io::Read;
+use alloc::{boxed::Box, vec::Vec};
 
-type AddressSpace = [u16; u16::MAX as usize + 1];
+#[cfg(feature = "std")]
+use crate::console::StdConsole;
+use crate::{
+    console::Console,
+    device::{
+        Clock, Recorder, Serial, Storage, DISK_ERROR, DISK_READ, DISK_READY, DISK_WRITE,
+        SECTOR_WORDS,
+    },
+    instruction::{decode, Instruction},
+    paging::{PageTable, PAGE_COUNT, PAGE_WORDS},
+    timer::{Timer, TimerState},
+};
 
-pub struct Memory(AddressSpace);
+// Predecoded instructions of a page, allocated on the first fetch from it
+type DecodedPage = [Option<Instruction>; PAGE_WORDS];
+
+// Memory mapped device registers
+pub const KBSR: u16 = 0xFE00; // Keyboard status
+pub const KBDR: u16 = 0xFE02; // Keyboard data
+pub const DSR: u16 = 0xFE04; // Display status
+pub const DDR: u16 = 0xFE06; // Display data
+pub const TSR: u16 = 0xFE08; // Timer status
+pub const TIR: u16 = 0xFE0A; // Timer interval
+pub const DKSR: u16 = 0xFE10; // Disk status
+pub const DKCR: u16 = 0xFE12; // Disk command
+pub const DKSN: u16 = 0xFE14; // Disk sector number
+pub const DKBA: u16 = 0xFE16; // Disk buffer address
+pub const URSR: u16 = 0xFE18; // Serial receiver status
+pub const URDR: u16 = 0xFE1A; // Serial receiver data
+pub const UTSR: u16 = 0xFE1C; // Serial transmitter status
+pub const UTDR: u16 = 0xFE1E; // Serial transmitter data
+pub const MCR: u16 = 0xFFFE; // Machine control
+
+// Start of the memory mapped I/O page
+pub const IO_PAGE: u16 = 0xFE00;
+
+// Start of the space available to user mode programs, everything below is system space
+pub const USER_SPACE: u16 = 0x3000;
+
+pub struct Memory {
+    cells: PageTable,
+    // Predecoded instructions, dropped whenever their word is written
+    decoded: [Option<Box<DecodedPage>>; PAGE_COUNT],
+    console: Box<dyn Console>,
+    timer: Timer,
+    disk: Option<Box<dyn Storage>>,
+    uart: Option<Box<dyn Serial>>,
+    // Instructions executed so far, lets the console time its input
+    instruction_count: u64,
+    // Set when the keyboard was polled after the console ran out of input for good
+    input_exhausted: bool,
+    // Set once the console could not deliver a written byte
+    output_closed: bool,
+    // Output was written by the instruction being executed
+    output_written: bool,
+    // Told about every key the program consumes, to replay the session
+    recorder: Option<Box<dyn Recorder>>,
+}
 
 impl Memory {
+    #[cfg(feature = "std")]
     pub fn new() -> Self {
-        Memory([0x0; u16::MAX as usize + 1])
+        Memory::with_console(Box::new(StdConsole))
     }
 
+    pub fn with_console(console: Box<dyn Console>) -> Self {
+        let mut cells: PageTable = PageTable::new();
+        cells.write(MCR, 1 << 15); // Clock enabled
+
+        Memory {
+            cells,
+            decoded: [const { None }; PAGE_COUNT],
+            console,
+            timer: Timer::new(),
+            disk: None,
+            uart: None,
+            instruction_count: 0,
+            input_exhausted: false,
+            output_closed: false,
+            output_written: false,
+            recorder: None,
+        }
+    }
+
+    #[inline]
     pub fn read(&mut self, address: u16) -> u16 {
         match address {
-            0xFE00 => {
-                let mut buffer: [u8; 1] = [0; 1];
-                std::io::stdin().read_exact(&mut buffer).unwrap();
-
-                match buffer[0] != 0 {
-                    false => self.write(0xFE00, 0),
-                    _ => {
-                        self.write(0xFE00, 1 << 15);
-                        self.write(0xFE02, buffer[0] as u16)
-                    }
+            KBSR => match self.console.poll_byte(self.instruction_count) {
+                Some(byte) => {
+                    self.cells.write(KBSR, 1 << 15);
+                    self.cells.write(KBDR, byte as u16);
+                    self.record(byte);
+                }
+                _ => {
+                    self.cells.write(KBSR, 0);
+                    self.input_exhausted = self.console.exhausted();
+                }
+            },
+            // The display is always ready to accept a character
+            DSR => self.cells.write(DSR, 1 << 15),
+            TSR => {
+                let status: u16 = self.timer.read_status();
+                self.cells.write(TSR, self.timer.status());
+                return status;
+            }
+            URSR | URDR | UTSR => {
+                if let Some(uart) = &mut self.uart {
+                    let value: u16 = match address {
+                        URSR => uart.status(),
+                        URDR => uart.read_data(),
+                        // The transmitter takes a byte at any time
+                        _ => 1 << 15,
+                    };
+                    self.cells.write(address, value);
                 }
             }
             _ => {}
         }
 
-        return self.0[address as usize];
+        self.cells.read(address)
     }
 
+    #[inline]
     pub fn write(&mut self, address: u16, value: u16) {
-        self.0[address as usize] = value;
+        match address {
+            DDR => self.print(value as u8),
+            TSR => {
+                self.timer.write_status(value, self.instruction_count);
+                self.cells.write(TSR, self.timer.status());
+                return;
+            }
+            TIR => self.timer.write_interval(value, self.instruction_count),
+            DKCR => {
+                self.cells.write(DKCR, value);
+                self.disk_command(value);
+                return;
+            }
+            URSR | UTDR if self.uart.is_some() => {
+                let uart: &mut Box<dyn Serial> = self.uart.as_mut().unwrap();
+
+                match address {
+                    URSR => uart.set_control(value),
+                    _ => uart.write_data(value),
+                }
+            }
+            _ => {}
+        }
+
+        self.cells.write(address, value);
+        self.forget_decoded(address);
+    }
+
+    // Instruction fetch for the predecoded engine, decoding each word only once
+    #[inline]
+    pub fn fetch(&mut self, address: u16) -> Instruction {
+        if address >= IO_PAGE {
+            // Device registers can change underneath us, never cache them
+            return decode(self.read(address));
+        }
+
+        let page: &mut DecodedPage = self.decoded[address as usize / PAGE_WORDS]
+            .get_or_insert_with(|| Box::new([None; PAGE_WORDS]));
+        let offset: usize = address as usize % PAGE_WORDS;
+
+        match page[offset] {
+            Some(instruction) => instruction,
+            None => {
+                let instruction: Instruction = decode(self.cells.read(address));
+                page[offset] = Some(instruction);
+                instruction
+            }
+        }
+    }
+
+    #[inline]
+    fn forget_decoded(&mut self, address: u16) {
+        if let Some(page) = &mut self.decoded[address as usize / PAGE_WORDS] {
+            page[address as usize % PAGE_WORDS] = None;
+        }
+    }
+
+    // Reads a cell without triggering any device side effects
+    #[inline]
+    pub fn peek(&self, address: u16) -> u16 {
+        self.cells.read(address)
+    }
+
+    // Writes a cell without triggering any device side effects
+    pub fn poke(&mut self, address: u16, value: u16) {
+        self.cells.write(address, value);
+        self.forget_decoded(address);
+    }
+
+    // The cells from `start` to `end` inclusive, without device side effects
+    pub fn words(&self, start: u16, end: u16) -> Vec<u16> {
+        (start..=end)
+            .map(|address| self.cells.read(address))
+            .collect()
+    }
+
+    // Saves the contents of memory and the timer. Pages are shared with the snapshot until
+    // either side writes to them, so taking one costs next to nothing
+    pub fn snapshot(&self) -> MemorySnapshot {
+        MemorySnapshot {
+            cells: self.cells.clone(),
+            timer: self.timer.state(),
+        }
+    }
+
+    // Returns to a snapshot, sharing its pages again. Attached devices keep their own state
+    pub fn restore(&mut self, snapshot: &MemorySnapshot) {
+        self.cells = snapshot.cells.clone();
+        self.timer.restore(snapshot.timer);
+        self.decoded = [const { None }; PAGE_COUNT];
+    }
+
+    pub fn pages(&self) -> &PageTable {
+        &self.cells
+    }
+
+    pub fn console(&mut self) -> &mut dyn Console {
+        self.console.as_mut()
+    }
+
+    pub fn set_console(&mut self, console: Box<dyn Console>) {
+        self.console = console;
+        self.output_closed = false;
+    }
+
+    // Writes a byte to the console for the display register and the output trap routines
+    pub fn print(&mut self, byte: u8) {
+        self.console.write_byte(byte);
+        self.output_closed = self.console.closed();
+        self.output_written = true;
+    }
+
+    // Whether output written to the console can no longer be delivered
+    #[inline]
+    pub fn output_closed(&self) -> bool {
+        self.output_closed
+    }
+
+    // Blocking read for the GETC and IN service routines
+    pub fn read_key(&mut self) -> Option<u8> {
+        let key: Option<u8> = self.console.read_byte();
+
+        if let Some(byte) = key {
+            self.record(byte);
+        }
+        key
+    }
+
+    pub fn set_uart(&mut self, uart: impl Serial + 'static) {
+        self.uart = Some(Box::new(uart));
+    }
+
+    pub fn set_disk(&mut self, disk: impl Storage + 'static) {
+        self.disk = Some(Box::new(disk));
+        self.cells.write(DKSR, DISK_READY);
+    }
+
+    // Lets the timer count wall-clock milliseconds where there is no host clock built in
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.timer.set_clock(Box::new(clock));
+    }
+
+    // Transfers a whole sector between the disk and the buffer in memory before the next
+    // instruction runs. Failures, unknown commands and a missing disk set the error bit
+    fn disk_command(&mut self, command: u16) {
+        let sector: u16 = self.cells.read(DKSN);
+        let buffer: u16 = self.cells.read(DKBA);
+        let address = |offset: usize| buffer.wrapping_add(offset as u16);
+
+        let succeeded: bool = match (&mut self.disk, command) {
+            (Some(disk), DISK_READ) => match disk.read_sector(sector) {
+                Some(words) => {
+                    for (offset, word) in words.iter().enumerate() {
+                        self.poke(address(offset), *word);
+                    }
+                    true
+                }
+                None => false,
+            },
+            (Some(disk), DISK_WRITE) => {
+                let words: [u16; SECTOR_WORDS] =
+                    core::array::from_fn(|offset| self.cells.read(address(offset)));
+                disk.write_sector(sector, &words)
+            }
+            _ => false,
+        };
+
+        let status: u16 = match (&self.disk, succeeded) {
+            (None, _) => DISK_ERROR,
+            (Some(_), true) => DISK_READY,
+            (Some(_), false) => DISK_READY | DISK_ERROR,
+        };
+        self.cells.write(DKSR, status);
+    }
+
+    pub fn set_recorder(&mut self, recorder: Box<dyn Recorder>) {
+        self.recorder = Some(recorder);
+    }
+
+    fn record(&mut self, byte: u8) {
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(self.instruction_count, byte);
+        }
+    }
+
+    // Called after every instruction, devices keep time in executed instructions
+    #[inline]
+    pub fn set_instruction_count(&mut self, instruction_count: u64) {
+        self.instruction_count = instruction_count;
+
+        if self.output_written {
+            self.output_written = false;
+            self.console.output_finished();
+        }
+
+        if self.timer.running() {
+            self.timer.tick(instruction_count);
+            self.cells.write(TSR, self.timer.status());
+        }
+
+        if let Some(uart) = &mut self.uart {
+            uart.tick(instruction_count);
+        }
+    }
+
+    // Priority and vector of the highest priority interrupt a device is requesting
+    #[inline]
+    pub fn interrupt(&self) -> Option<(u16, u8)> {
+        let uart: Option<(u16, u8)> = self.uart.as_ref().and_then(|uart| uart.interrupt());
+
+        match (self.timer.interrupt(), uart) {
+            (Some(timer), Some(uart)) if uart.0 > timer.0 => Some(uart),
+            (timer, uart) => timer.or(uart),
+        }
+    }
+
+    // Whether a keyboard poll found no input left since the last call
+    pub fn take_input_exhausted(&mut self) -> bool {
+        core::mem::take(&mut self.input_exhausted)
+    }
+
+    // Clearing the top bit of the machine control register stops the clock
+    #[inline]
+    pub fn clock_enabled(&self) -> bool {
+        self.cells.read(MCR) & (1 << 15) != 0
+    }
+}
+
+// Contents of memory saved by Memory::snapshot
+#[derive(Clone)]
+pub struct MemorySnapshot {
+    cells: PageTable,
+    timer: TimerState,
+}
+
+// System space and the I/O page may only be accessed in supervisor mode
+pub fn privileged(address: u16) -> bool {
+    !(USER_SPACE..IO_PAGE).contains(&address)
+}
+
+#[cfg(feature = "std")]
+impl Default for Memory {
+    fn default() -> Self {
+        Memory::new()
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 #[path = "./memory_test.rs"]
 mod memory_test;
@@ -1,11 +1,13 @@
-use crate::memory::Memory;
+use crate::memory::{privileged, Memory, MemorySnapshot, DDR, DSR, KBDR, KBSR, MCR, TIR, TSR};
+use crate::{console::BufferedConsole, instruction::Instruction, timer::TIMER_READY};
 
 #[test]
 fn test_memory_init() {
     let memory: Memory = Memory::new();
 
-    assert_eq!(memory.0.len(), 65_536);
-    assert_eq!(memory.0[0], 0x0);
+    // Only the page holding the machine control register is allocated
+    assert_eq!(memory.cells.allocated(), 1);
+    assert_eq!(memory.cells.read(0), 0x0);
 }
 
 #[test]
@@ -14,14 +16,108 @@ fn test_memory_write() {
 
     memory.write(0x3000, 0xFFFF);
 
-    assert_eq!(memory.0[0x3000], 0xFFFF);
+    assert_eq!(memory.cells.read(0x3000), 0xFFFF);
 }
 
 #[test]
 fn test_memory_read() {
     let mut memory: Memory = Memory::new();
 
-    memory.0[0x3000] = 0xFFFF;
+    memory.cells.write(0x3000, 0xFFFF);
 
     assert_eq!(memory.read(0x3000), 0xFFFF);
 }
+
+#[test]
+fn test_memory_keyboard_status() {
+    let mut memory: Memory = Memory::with_console(Box::new(BufferedConsole::new(b"k")));
+
+    assert_eq!(memory.read(KBSR), 1 << 15);
+    assert_eq!(memory.read(KBDR), b'k' as u16);
+    assert_eq!(memory.read(KBSR), 0);
+}
+
+#[test]
+fn test_memory_keyboard_nul() {
+    let mut memory: Memory = Memory::with_console(Box::new(BufferedConsole::new(b"\0k")));
+
+    // NUL is a key like any other, not the absence of one
+    assert_eq!(memory.read(KBSR), 1 << 15);
+    assert_eq!(memory.read(KBDR), 0);
+    assert_eq!(memory.read(KBSR), 1 << 15);
+    assert_eq!(memory.read(KBDR), b'k' as u16);
+}
+
+#[test]
+fn test_memory_display_data() {
+    let console: BufferedConsole = BufferedConsole::new(&[]);
+    let output = console.output();
+    let mut memory: Memory = Memory::with_console(Box::new(console));
+
+    assert_eq!(memory.read(DSR), 1 << 15);
+    memory.write(DDR, b'x' as u16);
+
+    assert_eq!(output.borrow().as_slice(), b"x");
+}
+
+#[test]
+fn test_memory_machine_control() {
+    let mut memory: Memory = Memory::new();
+
+    assert!(memory.clock_enabled());
+    memory.write(MCR, 0);
+    assert!(!memory.clock_enabled());
+}
+
+#[test]
+fn test_memory_fetch_invalidation() {
+    let mut memory: Memory = Memory::new();
+
+    memory.write(0x3000, 0xF025);
+    assert_eq!(memory.fetch(0x3000), Instruction::Trap { vector: 0x25 });
+    assert_eq!(
+        memory.decoded[0x30].as_ref().unwrap()[0],
+        Some(Instruction::Trap { vector: 0x25 })
+    );
+
+    memory.write(0x3000, 0xC1C0);
+    assert_eq!(memory.decoded[0x30].as_ref().unwrap()[0], None);
+    assert_eq!(memory.fetch(0x3000), Instruction::Jump { base: 7 });
+}
+
+#[test]
+fn test_memory_snapshot() {
+    let mut memory: Memory = Memory::new();
+    memory.write(0x3000, 0xF025);
+    memory.write(0x4000, 7);
+    memory.write(TIR, 100);
+    assert_eq!(memory.fetch(0x3000), Instruction::Trap { vector: 0x25 });
+
+    let snapshot: MemorySnapshot = memory.snapshot();
+    assert_eq!(memory.pages().shared(), memory.pages().allocated());
+
+    memory.write(0x3000, 0xC1C0);
+    memory.write(TIR, 0);
+    memory.write(0x5000, 1);
+    // Only the written pages were copied
+    assert_eq!(memory.pages().shared(), 2);
+
+    memory.restore(&snapshot);
+    assert_eq!(memory.read(0x4000), 7);
+    assert_eq!(memory.read(0x5000), 0);
+    assert_eq!(memory.fetch(0x3000), Instruction::Trap { vector: 0x25 });
+    memory.set_instruction_count(99);
+    assert_eq!(memory.read(TSR) & TIMER_READY, 0);
+    memory.set_instruction_count(100);
+    assert_eq!(memory.read(TSR) & TIMER_READY, TIMER_READY);
+}
+
+#[test]
+fn test_privileged_addresses() {
+    assert!(privileged(0x0000));
+    assert!(privileged(0x2FFF));
+    assert!(!privileged(0x3000));
+    assert!(!privileged(0xFDFF));
+    assert!(privileged(KBSR));
+    assert!(privileged(0xFFFF));
+}
@@ -0,0 +1,62 @@
+use alloc::rc::Rc;
+use core::cell::RefCell;
+
+use crate::cpu::CPU;
+
+// Callbacks for tools watching a program run, such as tracers, profilers and coverage. Every
+// method does nothing by default, so an observer only implements the events it needs. While
+// no observer is attached the CPU skips all of this.
+pub trait Observer {
+    // The instruction at `address` is about to execute
+    fn before_instruction(&mut self, _cpu: &CPU, _address: u16) {}
+
+    // The instruction at `address` executed, before any interrupt is taken
+    fn after_instruction(&mut self, _cpu: &CPU, _address: u16) {}
+
+    // Memory accessed by a load or store instruction
+    fn memory_read(&mut self, _cpu: &CPU, _address: u16, _value: u16) {}
+    fn memory_write(&mut self, _cpu: &CPU, _address: u16, _value: u16) {}
+
+    // A TRAP instruction starts. Traps run by an OS image return through its own code, the exit
+    // is only reported for traps served in Rust
+    fn trap_entry(&mut self, _cpu: &CPU, _vector: u8) {}
+    fn trap_exit(&mut self, _cpu: &CPU, _vector: u8) {}
+
+    // A device interrupt is taken, the CPU is already in the service routine
+    fn interrupt(&mut self, _cpu: &CPU, _priority: u16, _vector: u8) {}
+}
+
+// Lets the caller keep a handle on an observer to read its results after the run
+impl<T: Observer> Observer for Rc<RefCell<T>> {
+    fn before_instruction(&mut self, cpu: &CPU, address: u16) {
+        self.borrow_mut().before_instruction(cpu, address);
+    }
+
+    fn after_instruction(&mut self, cpu: &CPU, address: u16) {
+        self.borrow_mut().after_instruction(cpu, address);
+    }
+
+    fn memory_read(&mut self, cpu: &CPU, address: u16, value: u16) {
+        self.borrow_mut().memory_read(cpu, address, value);
+    }
+
+    fn memory_write(&mut self, cpu: &CPU, address: u16, value: u16) {
+        self.borrow_mut().memory_write(cpu, address, value);
+    }
+
+    fn trap_entry(&mut self, cpu: &CPU, vector: u8) {
+        self.borrow_mut().trap_entry(cpu, vector);
+    }
+
+    fn trap_exit(&mut self, cpu: &CPU, vector: u8) {
+        self.borrow_mut().trap_exit(cpu, vector);
+    }
+
+    fn interrupt(&mut self, cpu: &CPU, priority: u16, vector: u8) {
+        self.borrow_mut().interrupt(cpu, priority, vector);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+#[path = "./observer_test.rs"]
+mod observer_test;
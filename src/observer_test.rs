@@ -0,0 +1,145 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    cpu::{Engine, RunState, CPU},
+    observer::Observer,
+};
+
+#[derive(Default)]
+struct Recorder {
+    events: Vec<String>,
+}
+
+impl Observer for Recorder {
+    fn before_instruction(&mut self, cpu: &CPU, address: u16) {
+        assert_eq!(cpu.program_counter(), address);
+        self.events.push(format!("before x{:04X}", address));
+    }
+
+    fn after_instruction(&mut self, cpu: &CPU, address: u16) {
+        self.events.push(format!(
+            "after x{:04X} R0=x{:04X}",
+            address,
+            cpu.registers()[0]
+        ));
+    }
+
+    fn memory_read(&mut self, _cpu: &CPU, address: u16, value: u16) {
+        self.events
+            .push(format!("read x{:04X} x{:04X}", address, value));
+    }
+
+    fn memory_write(&mut self, _cpu: &CPU, address: u16, value: u16) {
+        self.events
+            .push(format!("write x{:04X} x{:04X}", address, value));
+    }
+
+    fn trap_entry(&mut self, _cpu: &CPU, vector: u8) {
+        self.events.push(format!("trap x{:02X}", vector));
+    }
+
+    fn trap_exit(&mut self, cpu: &CPU, vector: u8) {
+        self.events
+            .push(format!("trap x{:02X} done {:?}", vector, cpu.state()));
+    }
+
+    fn interrupt(&mut self, cpu: &CPU, priority: u16, vector: u8) {
+        self.events.push(format!(
+            "interrupt x{:02X} PL{} at x{:04X}",
+            vector,
+            priority,
+            cpu.program_counter()
+        ));
+    }
+}
+
+fn program() -> Program {
+    assemble(
+        "
+        .ORIG x3000
+        LD R0, VALUE
+        ST R0, COPY
+        HALT
+VALUE   .FILL x0041
+COPY    .BLKW 1
+        .END
+        ",
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_events() {
+    for engine in [Engine::Interpreter, Engine::Predecoded] {
+        let recorder: Rc<RefCell<Recorder>> = Rc::new(RefCell::new(Recorder::default()));
+        let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(b"")));
+
+        cpu.set_engine(engine);
+        cpu.load_image(&program().to_object()).unwrap();
+        cpu.add_observer(recorder.clone());
+        assert_eq!(cpu.run(None), RunState::Halted);
+
+        // Instruction fetches are not memory reads, whichever engine runs the program
+        assert_eq!(
+            recorder.borrow().events,
+            [
+                "before x3000",
+                "read x3003 x0041",
+                "after x3000 R0=x0041",
+                "before x3001",
+                "write x3004 x0041",
+                "after x3001 R0=x0041",
+                "before x3002",
+                "trap x25",
+                "trap x25 done Halted",
+                "after x3002 R0=x0041",
+            ]
+        );
+    }
+}
+
+#[test]
+fn test_interrupt_and_detach() {
+    let program: Program = assemble(
+        "
+        .ORIG x3000
+        LD R0, HANDLER
+        STI R0, VECTOR
+        LD R0, INTERVAL
+        STI R0, TIR
+        LD R0, CONTROL
+        STI R0, TSR
+LOOP    BRnzp LOOP
+HANDLER .FILL TICK
+VECTOR  .FILL x0181
+INTERVAL .FILL #10
+CONTROL .FILL x4481
+TIR     .FILL xFE0A
+TSR     .FILL xFE08
+TICK    LDI R3, TSR
+        RTI
+        .END
+        ",
+    )
+    .unwrap();
+    let recorder: Rc<RefCell<Recorder>> = Rc::new(RefCell::new(Recorder::default()));
+    let mut cpu: CPU = CPU::new();
+
+    cpu.load_image(&program.to_object()).unwrap();
+    cpu.set_register(6, 0x3000);
+    cpu.add_observer(recorder.clone());
+    cpu.run(Some(20));
+
+    let tick: u16 = program.symbols["TICK"];
+    let entered: String = format!("interrupt x81 PL4 at x{:04X}", tick);
+    let events: Vec<String> = recorder.borrow().events.clone();
+    let position: usize = events.iter().position(|event| *event == entered).unwrap();
+    assert!(events[position - 1].starts_with("after x3006"));
+
+    // Detached observers miss the rest of the run
+    assert_eq!(cpu.take_observers().len(), 1);
+    cpu.run(Some(100));
+    assert_eq!(recorder.borrow().events.len(), events.len());
+}
@@ -0,0 +1,76 @@
+use alloc::rc::Rc;
+
+// Words in a page, the unit memory is allocated, shared and copied in
+pub const PAGE_WORDS: usize = 256;
+pub const PAGE_COUNT: usize = (u16::MAX as usize + 1) / PAGE_WORDS;
+
+type Page = [u16; PAGE_WORDS];
+
+// The address space as pages allocated on their first non-zero write, reading as zeros until
+// then. A clone shares every page with the original and a shared page is only copied once
+// either side writes to it, so cloning costs a pointer per page rather than the whole space.
+#[derive(Clone)]
+pub struct PageTable {
+    pages: [Option<Rc<Page>>; PAGE_COUNT],
+}
+
+impl PageTable {
+    pub fn new() -> Self {
+        PageTable {
+            pages: [const { None }; PAGE_COUNT],
+        }
+    }
+
+    #[inline]
+    pub fn read(&self, address: u16) -> u16 {
+        match &self.pages[address as usize / PAGE_WORDS] {
+            Some(page) => page[address as usize % PAGE_WORDS],
+            None => 0,
+        }
+    }
+
+    #[inline]
+    pub fn write(&mut self, address: u16, value: u16) {
+        let offset: usize = address as usize % PAGE_WORDS;
+
+        match &mut self.pages[address as usize / PAGE_WORDS] {
+            Some(page) => Rc::make_mut(page)[offset] = value,
+            // Zeros are what an unallocated page holds already
+            None if value == 0 => {}
+            page => {
+                let mut words: Page = [0; PAGE_WORDS];
+                words[offset] = value;
+                *page = Some(Rc::new(words));
+            }
+        }
+    }
+
+    // Words of a page, None while it was never written
+    pub fn page(&self, index: usize) -> Option<&[u16; PAGE_WORDS]> {
+        self.pages[index].as_deref()
+    }
+
+    // Pages holding storage of their own or shared with a clone
+    pub fn allocated(&self) -> usize {
+        self.pages.iter().flatten().count()
+    }
+
+    // Pages whose storage is shared with a clone and would be copied by a write
+    pub fn shared(&self) -> usize {
+        self.pages
+            .iter()
+            .flatten()
+            .filter(|page| Rc::strong_count(page) > 1)
+            .count()
+    }
+}
+
+impl Default for PageTable {
+    fn default() -> Self {
+        PageTable::new()
+    }
+}
+
+#[cfg(test)]
+#[path = "./paging_test.rs"]
+mod paging_test;
@@ -0,0 +1,49 @@
+use crate::paging::{PageTable, PAGE_WORDS};
+
+#[test]
+fn test_sparse() {
+    let mut table: PageTable = PageTable::new();
+
+    assert_eq!(table.read(0x3000), 0);
+    table.write(0x3000, 0);
+    assert_eq!(table.allocated(), 0);
+
+    table.write(0x3001, 0xBEEF);
+    table.write(0x30FF, 0x1234);
+    assert_eq!(table.allocated(), 1);
+    assert_eq!(table.read(0x3001), 0xBEEF);
+    assert_eq!(table.read(0x30FF), 0x1234);
+    assert_eq!(table.read(0x3100), 0);
+
+    table.write(0xFFFF, 1);
+    assert_eq!(table.allocated(), 2);
+}
+
+#[test]
+fn test_copy_on_write() {
+    let mut table: PageTable = PageTable::new();
+    table.write(0x3000, 1);
+    table.write(0x4000, 2);
+
+    let mut clone: PageTable = table.clone();
+    assert_eq!(table.shared(), 2);
+
+    // Only the written page is copied, the other stays shared
+    clone.write(0x3000, 10);
+    assert_eq!(table.shared(), 1);
+    assert_eq!(clone.shared(), 1);
+    assert_eq!(table.read(0x3000), 1);
+    assert_eq!(clone.read(0x3000), 10);
+
+    // Writing the original leaves the clone alone as well
+    table.write(0x4000 + PAGE_WORDS as u16 - 1, 3);
+    assert_eq!(table.shared(), 0);
+    assert_eq!(clone.read(0x4000 + PAGE_WORDS as u16 - 1), 0);
+    assert_eq!(clone.read(0x4000), 2);
+
+    // New pages belong to one side only
+    clone.write(0x5000, 4);
+    assert_eq!(table.read(0x5000), 0);
+    assert_eq!(table.allocated(), 2);
+    assert_eq!(clone.allocated(), 3);
+}
@@ -0,0 +1,244 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{
+    assembler::SymbolTable,
+    cpu::{RunState, CPU},
+    disassembler::{disassemble_with_symbols, label_at},
+    memory::Memory,
+};
+
+const OP_CODE_NAMES: [&str; 16] = [
+    "BR", "ADD", "LD", "ST", "JSR", "AND", "LDR", "STR", "RTI", "NOT", "LDI", "STI", "JMP", "RES",
+    "LEA", "TRAP",
+];
+
+const TRAP_NAMES: [&str; 6] = ["GETC", "OUT", "PUTS", "IN", "PUTSP", "HALT"];
+
+// Number of hottest addresses listed in the report
+const HOT_SPOT_COUNT: usize = 20;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SubroutineStats {
+    pub calls: u64,
+    // Instructions executed between the call and its return, callees included
+    pub total: u64,
+    // Instructions executed by the subroutine itself
+    pub own: u64,
+}
+
+#[derive(Clone, Copy)]
+struct Frame {
+    entry: u16,
+    return_address: u16,
+    start: u64,
+    callee_instructions: u64,
+}
+
+// Counts executions per address and op code, subroutine costs and trap usage
+#[derive(Default)]
+pub struct Profiler {
+    total: u64,
+    addresses: HashMap<u16, u64>,
+    op_codes: [u64; 16],
+    traps: BTreeMap<u8, u64>,
+    subroutines: HashMap<u16, SubroutineStats>,
+    stack: Vec<Frame>,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Profiler::default()
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    pub fn count_at(&self, address: u16) -> u64 {
+        self.addresses.get(&address).copied().unwrap_or(0)
+    }
+
+    pub fn op_code_count(&self, op_code: u16) -> u64 {
+        self.op_codes[(op_code & 0xF) as usize]
+    }
+
+    pub fn trap_count(&self, vector: u8) -> u64 {
+        self.traps.get(&vector).copied().unwrap_or(0)
+    }
+
+    // Executes one instruction and records it
+    pub fn step(&mut self, cpu: &mut CPU) -> RunState {
+        let address: u16 = cpu.program_counter();
+        let operation: u16 = cpu.memory().peek(address);
+        let state: RunState = cpu.step();
+
+        self.record(
+            address,
+            operation,
+            cpu.program_counter(),
+            cpu.registers()[7],
+        );
+        state
+    }
+
+    pub fn run(&mut self, cpu: &mut CPU, limit: Option<u64>) -> RunState {
+        let mut executed: u64 = 0;
+
+        while cpu.state() == RunState::Running && limit.is_none_or(|limit| executed < limit) {
+            self.step(cpu);
+            executed += 1;
+        }
+
+        cpu.memory_mut().console().flush();
+        cpu.state()
+    }
+
+    // `next_pc` and `link` are the program counter and R7 after the instruction executed
+    pub fn record(&mut self, address: u16, operation: u16, next_pc: u16, link: u16) {
+        let op_code: u16 = operation >> 12;
+
+        self.total += 1;
+        *self.addresses.entry(address).or_insert(0) += 1;
+        self.op_codes[op_code as usize] += 1;
+
+        match op_code {
+            // JSR and JSRR, or a TRAP routed into an OS service routine
+            0x4 | 0xF if link == address.wrapping_add(1) && next_pc != link => {
+                if op_code == 0xF {
+                    *self.traps.entry(operation as u8).or_insert(0) += 1;
+                }
+
+                self.stack.push(Frame {
+                    entry: next_pc,
+                    return_address: link,
+                    start: self.total,
+                    callee_instructions: 0,
+                });
+            }
+            0xF => *self.traps.entry(operation as u8).or_insert(0) += 1,
+            // RET, unwinding any frames skipped by non-local jumps
+            0xC if (operation >> 6) & 0x7 == 7 => {
+                if let Some(depth) = self
+                    .stack
+                    .iter()
+                    .rposition(|frame| frame.return_address == next_pc)
+                {
+                    while self.stack.len() > depth {
+                        self.close_frame();
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn close_frame(&mut self) {
+        let Some(frame) = self.stack.pop() else {
+            return;
+        };
+
+        let total: u64 = self.total - frame.start;
+        let stats: &mut SubroutineStats = self.subroutines.entry(frame.entry).or_default();
+
+        stats.calls += 1;
+        stats.total += total;
+        stats.own += total - frame.callee_instructions;
+
+        if let Some(parent) = self.stack.last_mut() {
+            parent.callee_instructions += total;
+        }
+    }
+
+    // Subroutine statistics, counting calls still in progress up to now
+    pub fn subroutines(&self) -> HashMap<u16, SubroutineStats> {
+        let mut profiler: Profiler = Profiler {
+            total: self.total,
+            subroutines: self.subroutines.clone(),
+            stack: self.stack.clone(),
+            ..Profiler::default()
+        };
+
+        while !profiler.stack.is_empty() {
+            profiler.close_frame();
+        }
+
+        profiler.subroutines
+    }
+
+    pub fn report(&self, memory: &Memory, symbols: &SymbolTable) -> String {
+        let mut report: String = format!("Instructions executed: {}\n", self.total);
+        let percent = |count: u64| count as f64 * 100.0 / self.total.max(1) as f64;
+
+        let mut addresses: Vec<(&u16, &u64)> = self.addresses.iter().collect();
+        addresses.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+        report.push_str("\nHot spots:\n  count       %    address  label         instruction\n");
+        for (address, count) in addresses.into_iter().take(HOT_SPOT_COUNT) {
+            report.push_str(&format!(
+                "  {:<10} {:>5.1}  x{:04X}    {:<12}  {}\n",
+                count,
+                percent(*count),
+                address,
+                label_at(symbols, *address).unwrap_or(""),
+                disassemble_with_symbols(*address, memory.peek(*address), symbols)
+            ));
+        }
+
+        let mut op_codes: Vec<(usize, &u64)> = self
+            .op_codes
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .collect();
+        op_codes.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(&b.0)));
+
+        report.push_str("\nOp codes:\n");
+        for (op_code, count) in op_codes {
+            report.push_str(&format!(
+                "  {:<5} {:<10} {:>5.1}%\n",
+                OP_CODE_NAMES[op_code],
+                count,
+                percent(*count)
+            ));
+        }
+
+        let subroutines: HashMap<u16, SubroutineStats> = self.subroutines();
+        let mut subroutines: Vec<(&u16, &SubroutineStats)> = subroutines.iter().collect();
+        subroutines.sort_by(|a, b| b.1.total.cmp(&a.1.total).then(a.0.cmp(b.0)));
+
+        if !subroutines.is_empty() {
+            report.push_str(
+                "\nSubroutines (instructions):\n  entry  label         calls      total      own        avg\n",
+            );
+        }
+        for (entry, stats) in subroutines {
+            report.push_str(&format!(
+                "  x{:04X}  {:<12}  {:<10} {:<10} {:<10} {:.1}\n",
+                entry,
+                label_at(symbols, *entry).unwrap_or(""),
+                stats.calls,
+                stats.total,
+                stats.own,
+                stats.total as f64 / stats.calls as f64
+            ));
+        }
+
+        if !self.traps.is_empty() {
+            report.push_str("\nTraps:\n");
+        }
+        for (vector, count) in &self.traps {
+            let name: String = match vector {
+                0x20..=0x25 => TRAP_NAMES[(vector - 0x20) as usize].to_string(),
+                _ => format!("TRAP x{:02X}", vector),
+            };
+
+            report.push_str(&format!("  x{:02X}  {:<8} {}\n", vector, name, count));
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+#[path = "./profiler_test.rs"]
+mod profiler_test;
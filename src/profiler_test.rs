@@ -0,0 +1,96 @@
+use crate::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    cpu::{RunState, CPU},
+    profiler::Profiler,
+};
+
+const PROGRAM: &str = "
+        .ORIG x3000
+        AND R1, R1, #0
+        ADD R1, R1, #3
+LOOP    JSR DOUBLE
+        ADD R1, R1, #-1
+        BRp LOOP
+        LEA R0, DONE
+        PUTS
+        HALT
+DOUBLE  ADD R2, R1, R1
+        ST R7, SAVE
+        JSR NESTED
+        LD R7, SAVE
+        RET
+NESTED  ADD R3, R3, #1
+        RET
+SAVE    .BLKW 1
+DONE    .STRINGZ \"!\"
+        .END
+";
+
+fn profile(source: &str) -> (Profiler, CPU, Program) {
+    let program: Program = assemble(source).unwrap();
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(&[])));
+    let mut profiler: Profiler = Profiler::new();
+
+    cpu.load_image(&program.to_object()).unwrap();
+    assert_eq!(profiler.run(&mut cpu, Some(1000)), RunState::Halted);
+
+    (profiler, cpu, program)
+}
+
+#[test]
+fn test_profiler_counts() {
+    let (profiler, cpu, _) = profile(PROGRAM);
+
+    assert_eq!(profiler.total(), cpu.instruction_count());
+    assert_eq!(profiler.count_at(0x3000), 1);
+    assert_eq!(profiler.count_at(0x3002), 3);
+    assert_eq!(profiler.count_at(0x300D), 3);
+    assert_eq!(profiler.op_code_count(0x4), 6);
+    assert_eq!(profiler.op_code_count(0xC), 6);
+    assert_eq!(profiler.trap_count(0x22), 1);
+    assert_eq!(profiler.trap_count(0x25), 1);
+}
+
+#[test]
+fn test_profiler_subroutines() {
+    let (profiler, _, program) = profile(PROGRAM);
+    let subroutines = profiler.subroutines();
+
+    let double = subroutines[&program.symbols["DOUBLE"]];
+    let nested = subroutines[&program.symbols["NESTED"]];
+
+    assert_eq!(nested.calls, 3);
+    assert_eq!(nested.total, 6);
+    assert_eq!(nested.own, 6);
+    assert_eq!(double.calls, 3);
+    assert_eq!(double.total, 21);
+    assert_eq!(double.own, 15);
+}
+
+#[test]
+fn test_profiler_open_frames() {
+    let mut profiler: Profiler = Profiler::new();
+
+    // JSR into x3010 which never returns
+    profiler.record(0x3000, 0x480F, 0x3010, 0x3001);
+    profiler.record(0x3010, 0x1021, 0x3011, 0x3001);
+    profiler.record(0x3011, 0xF025, 0x3012, 0x3001);
+
+    let stats = profiler.subroutines()[&0x3010];
+
+    assert_eq!(stats.calls, 1);
+    assert_eq!(stats.total, 2);
+}
+
+#[test]
+fn test_profiler_report() {
+    let (profiler, cpu, program) = profile(PROGRAM);
+    let report: String = profiler.report(cpu.memory(), &program.symbols);
+
+    assert!(report.starts_with(&format!("Instructions executed: {}", profiler.total())));
+    assert!(report.contains("JSR DOUBLE"));
+    assert!(report.contains("NESTED"));
+    assert!(report.contains("PUTS"));
+    assert!(report.find("Hot spots").unwrap() < report.find("Op codes").unwrap());
+}
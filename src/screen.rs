@@ -0,0 +1,331 @@
+use std::{cell::RefCell, io::Write, rc::Rc};
+
+use crate::console::Console;
+
+// Size of the virtual terminal
+pub const COLUMNS: usize = 80;
+pub const ROWS: usize = 25;
+
+const ESCAPE: u8 = 0x1B;
+
+// Progress through an escape sequence
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Escape {
+    None,
+    // Seen ESC
+    Start,
+    // Inside a control sequence (ESC [), collecting its parameters
+    Control { parameters: Vec<u16>, private: bool },
+}
+
+// 80x25 text screen interpreting the control characters and ANSI sequences LC-3 programs emit.
+// Colors and other attributes are ignored, only the characters are kept.
+pub struct Screen {
+    cells: Vec<[u8; COLUMNS]>,
+    row: usize,
+    // COLUMNS when a character was just written to the last column, the line wraps on the next one
+    column: usize,
+    saved_cursor: (usize, usize),
+    escape: Escape,
+}
+
+impl Screen {
+    pub fn new() -> Self {
+        Screen {
+            cells: vec![[b' '; COLUMNS]; ROWS],
+            row: 0,
+            column: 0,
+            saved_cursor: (0, 0),
+            escape: Escape::None,
+        }
+    }
+
+    pub fn write_byte(&mut self, byte: u8) {
+        match std::mem::replace(&mut self.escape, Escape::None) {
+            Escape::None => self.write_character(byte),
+            Escape::Start => match byte {
+                b'[' => {
+                    self.escape = Escape::Control {
+                        parameters: Vec::new(),
+                        private: false,
+                    }
+                }
+                b'c' => *self = Screen::new(),
+                b'7' => self.saved_cursor = (self.row, self.column),
+                b'8' => (self.row, self.column) = self.saved_cursor,
+                // Anything else is a two byte sequence we have no use for
+                _ => {}
+            },
+            Escape::Control {
+                mut parameters,
+                mut private,
+            } => match byte {
+                b'0'..=b'9' => {
+                    if parameters.is_empty() {
+                        parameters.push(0);
+                    }
+                    let last: &mut u16 = parameters.last_mut().unwrap();
+                    *last = last.saturating_mul(10).saturating_add((byte - b'0') as u16);
+                    self.escape = Escape::Control {
+                        parameters,
+                        private,
+                    };
+                }
+                b';' => {
+                    if parameters.is_empty() {
+                        parameters.push(0);
+                    }
+                    parameters.push(0);
+                    self.escape = Escape::Control {
+                        parameters,
+                        private,
+                    };
+                }
+                b'?' | b'>' | b'=' => {
+                    private = true;
+                    self.escape = Escape::Control {
+                        parameters,
+                        private,
+                    };
+                }
+                // Final byte of the sequence
+                0x40..=0x7E if !private => self.control(byte, &parameters),
+                0x40..=0x7E => {}
+                // Malformed sequence, drop it
+                _ => {}
+            },
+        }
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_byte(byte);
+        }
+    }
+
+    // Position of the cursor as (row, column), both starting at 0
+    pub fn cursor(&self) -> (usize, usize) {
+        (self.row, self.column.min(COLUMNS - 1))
+    }
+
+    // Text of a row without trailing blanks
+    pub fn row(&self, row: usize) -> String {
+        String::from_utf8_lossy(&self.cells[row])
+            .trim_end()
+            .to_string()
+    }
+
+    pub fn contains(&self, text: &str) -> bool {
+        (0..ROWS).any(|row| self.row(row).contains(text))
+    }
+
+    // The whole screen as text, one line per row
+    pub fn text(&self) -> String {
+        let rows: Vec<String> = (0..ROWS).map(|row| self.row(row)).collect();
+        rows.join("\n").trim_end().to_string()
+    }
+
+    // The screen inside a border, as ScreenConsole dumps it after each output
+    pub fn frame(&self) -> String {
+        let border: String = format!("+{}+\n", "-".repeat(COLUMNS));
+        let mut frame: String = border.clone();
+
+        for row in &self.cells {
+            frame.push('|');
+            frame.push_str(&String::from_utf8_lossy(row));
+            frame.push_str("|\n");
+        }
+
+        frame.push_str(&border);
+        frame
+    }
+
+    fn write_character(&mut self, byte: u8) {
+        match byte {
+            ESCAPE => self.escape = Escape::Start,
+            // Output goes through the host's line discipline, which turns LF into CR LF
+            b'\n' => {
+                self.column = 0;
+                self.line_feed();
+            }
+            b'\r' => self.column = 0,
+            0x08 => self.column = self.column.min(COLUMNS - 1).saturating_sub(1),
+            b'\t' => self.column = ((self.column / 8 + 1) * 8).min(COLUMNS - 1),
+            // Form feed clears the screen
+            0x0C => {
+                self.clear(0, ROWS * COLUMNS);
+                (self.row, self.column) = (0, 0);
+            }
+            0x20..=0x7E => {
+                if self.column == COLUMNS {
+                    self.column = 0;
+                    self.line_feed();
+                }
+
+                self.cells[self.row][self.column] = byte;
+                self.column += 1;
+            }
+            // Bell and the remaining control characters do not show up on screen
+            _ => {}
+        }
+    }
+
+    fn line_feed(&mut self) {
+        if self.row + 1 < ROWS {
+            self.row += 1;
+        } else {
+            self.cells.remove(0);
+            self.cells.push([b' '; COLUMNS]);
+        }
+    }
+
+    // Blanks the cells from `start` up to `end`, counted row by row from the top left
+    fn clear(&mut self, start: usize, end: usize) {
+        for position in start..end.min(ROWS * COLUMNS) {
+            self.cells[position / COLUMNS][position % COLUMNS] = b' ';
+        }
+    }
+
+    fn control(&mut self, command: u8, parameters: &[u16]) {
+        // Missing or zero parameters mean 1 for movements and positions
+        let count = |index: usize| -> usize {
+            match parameters.get(index) {
+                Some(&value) if value > 0 => value as usize,
+                _ => 1,
+            }
+        };
+        let mode: u16 = parameters.first().copied().unwrap_or(0);
+        let column: usize = self.column.min(COLUMNS - 1);
+        let cursor: usize = self.row * COLUMNS + column;
+
+        match command {
+            b'A' => self.row = self.row.saturating_sub(count(0)),
+            b'B' => self.row = (self.row + count(0)).min(ROWS - 1),
+            b'C' => self.column = (column + count(0)).min(COLUMNS - 1),
+            b'D' => self.column = column.saturating_sub(count(0)),
+            b'E' => (self.row, self.column) = ((self.row + count(0)).min(ROWS - 1), 0),
+            b'F' => (self.row, self.column) = (self.row.saturating_sub(count(0)), 0),
+            b'G' => self.column = count(0).min(COLUMNS) - 1,
+            b'd' => self.row = count(0).min(ROWS) - 1,
+            b'H' | b'f' => {
+                self.row = count(0).min(ROWS) - 1;
+                self.column = count(1).min(COLUMNS) - 1;
+            }
+            b'J' => match mode {
+                0 => self.clear(cursor, ROWS * COLUMNS),
+                1 => self.clear(0, cursor + 1),
+                // 3 also drops the scrollback, which we do not keep
+                _ => self.clear(0, ROWS * COLUMNS),
+            },
+            b'K' => {
+                let line: usize = self.row * COLUMNS;
+
+                match mode {
+                    0 => self.clear(cursor, line + COLUMNS),
+                    1 => self.clear(line, cursor + 1),
+                    _ => self.clear(line, line + COLUMNS),
+                }
+            }
+            b's' => self.saved_cursor = (self.row, self.column),
+            b'u' => (self.row, self.column) = self.saved_cursor,
+            // Colors, attributes, modes and anything else leave the text alone
+            _ => {}
+        }
+    }
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen::new()
+    }
+}
+
+// Mirrors everything written to another console onto a virtual screen, optionally dumping a
+// frame after each instruction that produced output
+pub struct ScreenConsole {
+    console: Box<dyn Console>,
+    screen: Rc<RefCell<Screen>>,
+    frames: Option<Box<dyn Write>>,
+    // Output was written since the last frame
+    changed: bool,
+    frame_count: u64,
+}
+
+impl ScreenConsole {
+    pub fn new(console: Box<dyn Console>) -> Self {
+        ScreenConsole {
+            console,
+            screen: Rc::new(RefCell::new(Screen::new())),
+            frames: None,
+            changed: false,
+            frame_count: 0,
+        }
+    }
+
+    pub fn with_frames(mut self, frames: Box<dyn Write>) -> Self {
+        self.frames = Some(frames);
+        self
+    }
+
+    // Shared handle to the screen that stays valid after the console is moved into memory
+    pub fn screen(&self) -> Rc<RefCell<Screen>> {
+        Rc::clone(&self.screen)
+    }
+
+    fn dump_frame(&mut self) {
+        if !self.changed {
+            return;
+        }
+        self.changed = false;
+
+        if let Some(frames) = &mut self.frames {
+            self.frame_count += 1;
+            // Like recordings, a failing dump must not stop the program
+            let _ = write!(
+                frames,
+                "Frame {}\n{}",
+                self.frame_count,
+                self.screen.borrow().frame()
+            );
+            let _ = frames.flush();
+        }
+    }
+}
+
+impl Console for ScreenConsole {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.console.read_byte()
+    }
+
+    fn poll_byte(&mut self, instructions: u64) -> Option<u8> {
+        self.console.poll_byte(instructions)
+    }
+
+    fn exhausted(&self) -> bool {
+        self.console.exhausted()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        self.screen.borrow_mut().write_byte(byte);
+        self.changed = true;
+        self.console.write_byte(byte);
+    }
+
+    fn flush(&mut self) {
+        self.dump_frame();
+        self.console.flush();
+    }
+
+    fn output_finished(&mut self) {
+        self.dump_frame();
+        self.console.output_finished();
+    }
+
+    fn closed(&self) -> bool {
+        self.console.closed()
+    }
+}
+
+#[cfg(test)]
+#[path = "./screen_test.rs"]
+mod screen_test;
@@ -0,0 +1,130 @@
+use crate::{
+    console::BufferedConsole,
+    cpu::{RunState, CPU},
+    screen::{Screen, ScreenConsole, COLUMNS, ROWS},
+    script::{parse_script, ScriptedConsole},
+};
+
+fn screen(output: &[u8]) -> Screen {
+    let mut screen: Screen = Screen::new();
+    screen.write_bytes(output);
+    screen
+}
+
+#[test]
+fn test_control_characters() {
+    let screen: Screen = screen(b"Hello\nWorld\rw\tx\x08y\x07");
+
+    assert_eq!(screen.row(0), "Hello");
+    assert_eq!(screen.row(1), "world   y");
+    assert_eq!(screen.cursor(), (1, 9));
+    assert!(screen.contains("llo"));
+    assert!(!screen.contains("Hello World"));
+}
+
+#[test]
+fn test_wrap_and_scroll() {
+    let mut screen: Screen = screen(&[b'a'; COLUMNS]);
+    // A full line leaves the cursor on it until the next character
+    assert_eq!(screen.cursor(), (0, COLUMNS - 1));
+    screen.write_bytes(b"b");
+    assert_eq!(screen.row(1), "b");
+
+    for line in 0..ROWS {
+        screen.write_bytes(format!("\nline {}", line).as_bytes());
+    }
+
+    assert_eq!(screen.row(0), "line 0");
+    assert_eq!(screen.row(ROWS - 1), format!("line {}", ROWS - 1));
+    assert!(!screen.contains("b"));
+}
+
+#[test]
+fn test_ansi_sequences() {
+    let mut screen: Screen = screen(b"junk\n\x1B[2J\x1B[H\x1B[3J+--+\n|  |");
+    assert_eq!(screen.text(), "+--+\n|  |");
+
+    // Cursor positioning is one based, colors are ignored
+    screen.write_bytes(b"\x1B[2;2H\x1B[1;31m42\x1B[0m\x1B[?25l");
+    assert_eq!(screen.row(1), "|42|");
+
+    screen.write_bytes(b"\x1B[A\x1B[2D\x1B[K");
+    assert_eq!(screen.row(0), "+");
+
+    screen.write_bytes(b"\x1B[s\x1B[10;5Hx\x1B[uy\x1B[5G\x1B[1K");
+    assert_eq!(screen.row(0), "");
+    assert_eq!(screen.row(9), "    x");
+
+    screen.write_bytes(b"\x1B[2;1H\x1B[J");
+    assert_eq!(screen.text(), "");
+}
+
+#[test]
+fn test_frame() {
+    let frame: String = screen(b"hi").frame();
+    let lines: Vec<&str> = frame.lines().collect();
+
+    assert_eq!(lines.len(), ROWS + 2);
+    assert_eq!(lines[0].len(), COLUMNS + 2);
+    assert_eq!(lines[1], format!("|hi{}|", " ".repeat(COLUMNS - 2)));
+}
+
+#[test]
+fn test_screen_console() {
+    let console: BufferedConsole = BufferedConsole::new(b"");
+    let output = console.output();
+    let console: ScreenConsole = ScreenConsole::new(Box::new(console));
+    let screen = console.screen();
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    cpu.load_image(&std::fs::read("resources/hello-world.obj").unwrap())
+        .unwrap();
+
+    assert_eq!(cpu.run(Some(1000)), RunState::Halted);
+    assert!(screen.borrow().contains("Hello World!"));
+    // The wrapped console still sees all output
+    assert_eq!(output.borrow().as_slice(), b"Hello World!\nHALT\n");
+}
+
+#[test]
+fn test_game_frames() {
+    let frames = std::env::temp_dir().join("lc_3_screen_frames.txt");
+    let console: ScriptedConsole = ScriptedConsole::new(parse_script("\"y\"\n\"w\"").unwrap());
+    let console: ScreenConsole = ScreenConsole::new(Box::new(console))
+        .with_frames(Box::new(std::fs::File::create(&frames).unwrap()));
+    let screen = console.screen();
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    cpu.load_image(&std::fs::read("resources/2048.obj").unwrap())
+        .unwrap();
+
+    assert_eq!(cpu.run(Some(1_000_000)), RunState::InputExhausted);
+
+    // The board is redrawn from the top left after clearing the screen
+    assert_eq!(screen.borrow().row(0), "+--------------------------+");
+    assert!(!screen.borrow().contains("ANSI terminal"));
+
+    let text: String = std::fs::read_to_string(&frames).unwrap();
+    assert!(text.starts_with("Frame 1\n"));
+    assert!(text.contains("|Are you on an ANSI terminal (y/n)? "));
+    // The last frame shows the screen as the program left it
+    assert!(text.ends_with(&screen.borrow().frame()));
+}
+
+#[test]
+fn test_frames_without_input() {
+    let frames = std::env::temp_dir().join("lc_3_screen_output_frames.txt");
+    let console: ScreenConsole = ScreenConsole::new(Box::new(BufferedConsole::new(b"")))
+        .with_frames(Box::new(std::fs::File::create(&frames).unwrap()));
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    cpu.load_image(&std::fs::read("resources/hello-world.obj").unwrap())
+        .unwrap();
+
+    // Stepping never flushes the console, the frames come from the output alone
+    while cpu.step() == RunState::Running {}
+
+    let text: String = std::fs::read_to_string(&frames).unwrap();
+    let dumped: Vec<&str> = text.split("Frame ").skip(1).collect();
+    assert_eq!(dumped.len(), 2);
+    assert!(dumped[0].contains("|Hello World!"));
+    assert!(!dumped[0].contains("|HALT"));
+    assert!(dumped[1].contains("|HALT"));
+}
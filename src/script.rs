@@ -0,0 +1,302 @@
+use std::{cell::RefCell, collections::VecDeque, fmt, io::Write, rc::Rc};
+
+use crate::{
+    assembler::parse_number,
+    console::{Console, StdConsole},
+    device::Recorder,
+};
+
+// Input scripts give keystrokes and the moment they become available to the keyboard.
+// Every line holds an optional timing followed by one or more keys:
+//
+//   @2000 "w"       once 2000 instructions have executed
+//   +1500 "as"      1500 instructions after the previous line's keys became available
+//   polls 3 x0A     on the third keyboard status poll after the previous key was read
+//   "q"             no timing, right after the previous line
+//
+// Keys are quoted strings (with \n, \r, \t, \e, \0, \\ and \" escapes) or byte values such
+// as x1B or #27. Everything after a ';' is a comment.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Timing {
+    At(u64),
+    After(u64),
+    Polls(u64),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub timing: Timing,
+    pub keys: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct ScriptError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+fn error<T>(line: usize, message: String) -> Result<T, ScriptError> {
+    Err(ScriptError { line, message })
+}
+
+fn parse_count(line: usize, word: &str) -> Result<u64, ScriptError> {
+    word.parse::<u64>()
+        .or_else(|_| error(line, format!("invalid count '{}'", word)))
+}
+
+// Splits a line into words and the contents of quoted strings, which keep a leading '"'
+fn tokenize(line: usize, text: &str) -> Result<Vec<String>, ScriptError> {
+    let mut tokens: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => break,
+            c if c.is_whitespace() => {}
+            '"' => {
+                let mut value: String = String::from('"');
+
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => value.push('\n'),
+                            Some('t') => value.push('\t'),
+                            Some('r') => value.push('\r'),
+                            Some('e') => value.push('\x1B'),
+                            Some('0') => value.push('\0'),
+                            Some(other) => value.push(other),
+                            None => return error(line, String::from("unterminated string")),
+                        },
+                        Some(other) => value.push(other),
+                        None => return error(line, String::from("unterminated string")),
+                    }
+                }
+
+                tokens.push(value);
+            }
+            _ => {
+                let mut word: String = c.to_string();
+
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || next == ';' || next == '"' {
+                        break;
+                    }
+                    word.push(next);
+                    chars.next();
+                }
+
+                tokens.push(word);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+pub fn parse_script(text: &str) -> Result<Vec<KeyEvent>, ScriptError> {
+    let mut events: Vec<KeyEvent> = Vec::new();
+
+    for (index, source) in text.lines().enumerate() {
+        let line: usize = index + 1;
+        let mut tokens = tokenize(line, source)?.into_iter().peekable();
+
+        let timing: Timing = match tokens.peek().map(String::as_str) {
+            None => continue,
+            Some(word) if word.starts_with('@') => {
+                let timing: Timing = Timing::At(parse_count(line, &word[1..])?);
+                tokens.next();
+                timing
+            }
+            Some(word) if word.starts_with('+') => {
+                let timing: Timing = Timing::After(parse_count(line, &word[1..])?);
+                tokens.next();
+                timing
+            }
+            Some("polls") => {
+                tokens.next();
+                match tokens.next() {
+                    Some(count) => Timing::Polls(parse_count(line, &count)?),
+                    None => return error(line, String::from("polls expects a count")),
+                }
+            }
+            Some(_) => Timing::After(0),
+        };
+
+        let mut keys: Vec<u8> = Vec::new();
+
+        for token in tokens {
+            if let Some(text) = token.strip_prefix('"') {
+                if !text.is_ascii() {
+                    return error(line, format!("non-ASCII key in \"{}\"", text));
+                }
+                keys.extend(text.bytes());
+                continue;
+            }
+
+            match parse_number(&token) {
+                Some(value) if (0..=0xFF).contains(&value) => keys.push(value as u8),
+                _ => return error(line, format!("invalid key '{}'", token)),
+            }
+        }
+
+        if keys.is_empty() {
+            return error(line, String::from("expected at least one key"));
+        }
+
+        events.push(KeyEvent { timing, keys });
+    }
+
+    Ok(events)
+}
+
+// A script line making a key available at exactly the given instruction count, which
+// replays a recorded key on the same poll that originally read it
+pub fn format_key(instructions: u64, byte: u8) -> String {
+    match byte {
+        b'"' | b'\\' => format!("@{} \"\\{}\"", instructions, byte as char),
+        0x20..=0x7E => format!("@{} \"{}\"", instructions, byte as char),
+        _ => format!("@{} x{:02X}", instructions, byte),
+    }
+}
+
+// Records keys as input script lines to a writer, such as a file to replay the session from
+pub struct ScriptRecorder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> ScriptRecorder<W> {
+    pub fn new(writer: W) -> Self {
+        ScriptRecorder { writer }
+    }
+}
+
+impl<W: Write> Recorder for ScriptRecorder<W> {
+    fn record(&mut self, instruction_count: u64, byte: u8) {
+        // Flushed per key so the recording survives the session being killed. A failing
+        // recorder must not take the running program down with it.
+        let _ = writeln!(self.writer, "{}", format_key(instruction_count, byte));
+        let _ = self.writer.flush();
+    }
+}
+
+// Console whose keyboard input follows an input script, recording everything written unless
+// it is echoed instead
+pub struct ScriptedConsole {
+    events: VecDeque<KeyEvent>,
+    // Keys that became available but were not read yet
+    ready: VecDeque<u8>,
+    // Instruction count at which the last event was released
+    released_at: u64,
+    // Keyboard polls since a key was last read
+    polls: u64,
+    output: Rc<RefCell<Vec<u8>>>,
+    echo: bool,
+}
+
+impl ScriptedConsole {
+    pub fn new(events: Vec<KeyEvent>) -> Self {
+        ScriptedConsole {
+            events: events.into(),
+            ready: VecDeque::new(),
+            released_at: 0,
+            polls: 0,
+            output: Rc::new(RefCell::new(Vec::new())),
+            echo: false,
+        }
+    }
+
+    // Copy output to stdout as it is produced instead of recording it, so long sessions don't
+    // pile up everything they ever printed
+    pub fn with_echo(mut self) -> Self {
+        self.echo = true;
+        self
+    }
+
+    pub fn output(&self) -> Rc<RefCell<Vec<u8>>> {
+        Rc::clone(&self.output)
+    }
+
+    fn release(&mut self, released_at: u64) {
+        if let Some(event) = self.events.pop_front() {
+            self.released_at = released_at;
+            self.ready.extend(event.keys);
+        }
+    }
+
+    fn take_key(&mut self) -> Option<u8> {
+        let key: Option<u8> = self.ready.pop_front();
+
+        if key.is_some() {
+            self.polls = 0;
+        }
+        key
+    }
+}
+
+impl Console for ScriptedConsole {
+    // GETC and IN wait for the next key, skipping ahead to the moment it arrives
+    fn read_byte(&mut self) -> Option<u8> {
+        if self.ready.is_empty() {
+            let released_at: u64 = match self.events.front()?.timing {
+                Timing::At(count) => count.max(self.released_at),
+                Timing::After(count) => self.released_at + count,
+                Timing::Polls(_) => self.released_at,
+            };
+            self.release(released_at);
+        }
+
+        self.take_key()
+    }
+
+    fn poll_byte(&mut self, instructions: u64) -> Option<u8> {
+        self.polls += 1;
+
+        while let Some(event) = self.events.front() {
+            let due: bool = match event.timing {
+                Timing::At(count) => instructions >= count,
+                Timing::After(count) => instructions >= self.released_at + count,
+                Timing::Polls(count) => self.polls >= count,
+            };
+
+            if !due {
+                break;
+            }
+            self.release(instructions);
+        }
+
+        self.take_key()
+    }
+
+    fn exhausted(&self) -> bool {
+        self.events.is_empty() && self.ready.is_empty()
+    }
+
+    fn write_byte(&mut self, byte: u8) {
+        match self.echo {
+            true => StdConsole.write_byte(byte),
+            false => self.output.borrow_mut().push(byte),
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.echo {
+            StdConsole.flush();
+        }
+    }
+
+    fn closed(&self) -> bool {
+        self.echo && StdConsole.closed()
+    }
+}
+
+#[cfg(test)]
+#[path = "./script_test.rs"]
+mod script_test;
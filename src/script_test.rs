@@ -0,0 +1,219 @@
+use crate::{
+    assembler::assemble,
+    console::Console,
+    cpu::{RunState, CPU},
+    memory::{Memory, KBDR, KBSR},
+    script::{
+        format_key, parse_script, KeyEvent, ScriptError, ScriptRecorder, ScriptedConsole, Timing,
+    },
+};
+
+#[test]
+fn test_parse_script() {
+    let events: Vec<KeyEvent> = parse_script(
+        "
+        ; Opening moves
+        @2000 \"w\"   ; first key
+        +1500 \"a\" x0A
+        polls 3 #27
+        \"q\\n\"
+        ",
+    )
+    .unwrap();
+
+    assert_eq!(
+        events,
+        vec![
+            KeyEvent {
+                timing: Timing::At(2000),
+                keys: b"w".to_vec()
+            },
+            KeyEvent {
+                timing: Timing::After(1500),
+                keys: b"a\n".to_vec()
+            },
+            KeyEvent {
+                timing: Timing::Polls(3),
+                keys: vec![0x1B]
+            },
+            KeyEvent {
+                timing: Timing::After(0),
+                keys: b"q\n".to_vec()
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_parse_script_errors() {
+    let line = |text: &str| parse_script(text).map_err(|error: ScriptError| error.line);
+
+    assert_eq!(line("\n@ten \"a\""), Err(2));
+    assert_eq!(line("@10"), Err(1));
+    assert_eq!(line("polls"), Err(1));
+    assert_eq!(line("\"a\"\nx100"), Err(2));
+    assert_eq!(line("\"unterminated"), Err(1));
+}
+
+#[test]
+fn test_instruction_timing() {
+    let mut console: ScriptedConsole =
+        ScriptedConsole::new(parse_script("@100 \"a\"\n+50 \"b\"").unwrap());
+
+    assert_eq!(console.poll_byte(99), None);
+    assert_eq!(console.poll_byte(120), Some(b'a'));
+    assert_eq!(console.poll_byte(169), None);
+    assert_eq!(console.poll_byte(170), Some(b'b'));
+    assert!(console.exhausted());
+}
+
+#[test]
+fn test_poll_timing() {
+    let mut console: ScriptedConsole =
+        ScriptedConsole::new(parse_script("polls 2 \"ab\"\npolls 3 \"c\"").unwrap());
+
+    assert_eq!(console.poll_byte(0), None);
+    assert_eq!(console.poll_byte(0), Some(b'a'));
+    // Keys already available are handed out on the next poll
+    assert_eq!(console.poll_byte(0), Some(b'b'));
+    assert_eq!(console.poll_byte(0), None);
+    assert_eq!(console.poll_byte(0), None);
+    assert!(!console.exhausted());
+    assert_eq!(console.poll_byte(0), Some(b'c'));
+}
+
+#[test]
+fn test_nul_key() {
+    let mut memory: Memory = Memory::with_console(Box::new(ScriptedConsole::new(
+        parse_script("\"\\0\" x00 \"a\"").unwrap(),
+    )));
+
+    // Both NULs reach the program and the key after them keeps its place
+    for key in [0, 0, b'a' as u16] {
+        assert_eq!(memory.read(KBSR), 1 << 15);
+        assert_eq!(memory.read(KBDR), key);
+    }
+}
+
+#[test]
+fn test_read_waits_for_next_key() {
+    let mut console: ScriptedConsole =
+        ScriptedConsole::new(parse_script("@100 \"a\"\n+50 \"b\"").unwrap());
+
+    assert_eq!(console.read_byte(), Some(b'a'));
+    // Time skipped ahead to instruction 100, so the second key is due at 150
+    assert_eq!(console.poll_byte(149), None);
+    assert_eq!(console.poll_byte(150), Some(b'b'));
+    assert_eq!(console.read_byte(), None);
+}
+
+#[test]
+fn test_scripted_keyboard_program() {
+    // Echoes every key read through the keyboard registers until a 'q'
+    let program = assemble(
+        "
+        .ORIG x3000
+POLL    LDI R0, KBSR
+        BRzp POLL
+        LDI R0, KBDR
+        OUT
+        LD R1, QUIT
+        ADD R1, R0, R1
+        BRnp POLL
+        HALT
+KBSR    .FILL xFE00
+KBDR    .FILL xFE02
+QUIT    .FILL #-113
+        .END
+        ",
+    )
+    .unwrap();
+
+    let console: ScriptedConsole =
+        ScriptedConsole::new(parse_script("@1000 \"h\"\n+1000 \"i\"\npolls 5 \"q\"").unwrap());
+    let output = console.output();
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    cpu.load_image(&program.to_object()).unwrap();
+
+    assert_eq!(cpu.run(Some(100_000)), RunState::Halted);
+    assert_eq!(output.borrow().as_slice(), b"hiq\nHALT\n");
+    // Two instructions per poll while waiting, the keys arrive on schedule
+    assert!((2000..2100).contains(&cpu.instruction_count()));
+
+    // Polling after the script ran out stops the machine instead of spinning forever
+    let console: ScriptedConsole = ScriptedConsole::new(parse_script("\"x\"").unwrap());
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    cpu.load_image(&program.to_object()).unwrap();
+
+    assert_eq!(cpu.run(Some(100_000)), RunState::InputExhausted);
+}
+
+#[test]
+fn test_format_key() {
+    assert_eq!(format_key(12, b'w'), "@12 \"w\"");
+    assert_eq!(format_key(0, b'"'), "@0 \"\\\"\"");
+    assert_eq!(format_key(7, 0x1B), "@7 x1B");
+
+    let events: Vec<KeyEvent> = (0..=0xFF)
+        .map(|byte| {
+            parse_script(&format_key(byte as u64, byte))
+                .unwrap()
+                .remove(0)
+        })
+        .collect();
+
+    for (byte, event) in events.iter().enumerate() {
+        assert_eq!(event.timing, Timing::At(byte as u64));
+        assert_eq!(event.keys, vec![byte as u8]);
+    }
+}
+
+#[test]
+fn test_record_and_replay_session() {
+    // Counts the polls spent waiting for each key, so any timing drift shows up in R2
+    let program = assemble(
+        "
+        .ORIG x3000
+POLL    ADD R2, R2, #1
+        LDI R1, KBSR
+        BRzp POLL
+        LDI R0, KBDR
+        OUT
+        GETC
+        OUT
+        LD R1, QUIT
+        ADD R1, R0, R1
+        BRnp POLL
+        HALT
+KBSR    .FILL xFE00
+KBDR    .FILL xFE02
+QUIT    .FILL #-113
+        .END
+        ",
+    )
+    .unwrap();
+
+    let session = std::env::temp_dir().join("lc_3_script_session.txt");
+    let run = |script: &str, record: bool| {
+        let console: ScriptedConsole = ScriptedConsole::new(parse_script(script).unwrap());
+        let output = console.output();
+        let mut cpu: CPU = CPU::with_console(Box::new(console));
+        cpu.load_image(&program.to_object()).unwrap();
+
+        if record {
+            let file = std::fs::File::create(&session).unwrap();
+            cpu.memory_mut()
+                .set_recorder(Box::new(ScriptRecorder::new(file)));
+        }
+
+        assert_eq!(cpu.run(Some(100_000)), RunState::Halted);
+        let output: Vec<u8> = output.borrow().clone();
+        (output, cpu.instruction_count(), *cpu.registers())
+    };
+
+    let live = run("@500 \"a\"\n+300 \"b\"\npolls 4 \"\\\"\"\n\"q\"", true);
+    let recording: String = std::fs::read_to_string(&session).unwrap();
+
+    assert_eq!(recording.lines().count(), 4);
+    assert_eq!(run(&recording, false), live);
+}
@@ -0,0 +1,199 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::memory::{self, Memory};
+
+// Extended trap vectors serviced by the host once semihosting is enabled. Arguments and results
+// are passed in registers, strings and buffers hold one character per word like for PUTS.
+pub const TRAP_OPEN: u8 = 0x30; // R0 path, R1 mode -> R0 handle
+pub const TRAP_CLOSE: u8 = 0x31; // R0 handle -> R0 zero
+pub const TRAP_READ: u8 = 0x32; // R0 handle, R1 buffer, R2 count -> R0 characters read
+pub const TRAP_WRITE: u8 = 0x33; // R0 handle, R1 buffer, R2 count -> R0 characters written
+pub const TRAP_TIME: u8 = 0x34; // -> R1:R0 seconds since 1970, R2 milliseconds
+pub const TRAP_EXIT: u8 = 0x35; // R0 status, stops the machine
+
+// File modes for TRAP_OPEN
+pub const OPEN_READ: u16 = 0;
+pub const OPEN_WRITE: u16 = 1; // Created or truncated
+pub const OPEN_APPEND: u16 = 2; // Created if missing
+
+// R0 after a failed call
+pub const SEMIHOST_ERROR: u16 = 0xFFFF;
+
+pub fn handles(vector: u8) -> bool {
+    (TRAP_OPEN..=TRAP_EXIT).contains(&vector)
+}
+
+// Host services for test programs. Files are confined to a sandbox directory: paths are taken
+// relative to it and may not climb out of it with '..'.
+pub struct Semihost {
+    root: PathBuf,
+    // Open files indexed by handle, closed ones leave a slot for reuse
+    files: Vec<Option<File>>,
+    exit_status: Option<u16>,
+}
+
+impl Semihost {
+    pub fn new(root: &str) -> Self {
+        Semihost {
+            root: PathBuf::from(root),
+            files: Vec::new(),
+            exit_status: None,
+        }
+    }
+
+    // Status passed to TRAP_EXIT, if the program exited through it
+    pub fn exit_status(&self) -> Option<u16> {
+        self.exit_status
+    }
+
+    // Services a call. A `restricted` caller runs in user mode under access control, calls
+    // fail on strings and buffers reaching into system space or the I/O page then
+    pub fn call(
+        &mut self,
+        vector: u8,
+        registers: &mut [u16; 8],
+        memory: &mut Memory,
+        restricted: bool,
+    ) {
+        registers[0] = match vector {
+            TRAP_OPEN => read_string(memory, registers[0], restricted)
+                .and_then(|name| self.open(&name, registers[1]))
+                .unwrap_or(SEMIHOST_ERROR),
+            TRAP_CLOSE => match self.files.get_mut(registers[0] as usize) {
+                Some(file @ Some(_)) => {
+                    *file = None;
+                    0
+                }
+                _ => SEMIHOST_ERROR,
+            },
+            TRAP_READ | TRAP_WRITE if restricted && !accessible(registers[1], registers[2]) => {
+                SEMIHOST_ERROR
+            }
+            TRAP_READ => self
+                .read(registers[0], registers[1], registers[2], memory)
+                .unwrap_or(SEMIHOST_ERROR),
+            TRAP_WRITE => self
+                .write(registers[0], registers[1], registers[2], memory)
+                .unwrap_or(SEMIHOST_ERROR),
+            TRAP_TIME => {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                let seconds: u32 = now.as_secs() as u32;
+
+                registers[1] = (seconds >> 16) as u16;
+                registers[2] = now.subsec_millis() as u16;
+                seconds as u16
+            }
+            TRAP_EXIT => {
+                self.exit_status = Some(registers[0]);
+                registers[0]
+            }
+            _ => unreachable!("Invalid semihosting vector."),
+        };
+    }
+
+    fn open(&mut self, name: &str, mode: u16) -> Option<u16> {
+        let path: PathBuf = self.sandboxed(name)?;
+        let mut options: OpenOptions = OpenOptions::new();
+
+        match mode {
+            OPEN_READ => options.read(true),
+            OPEN_WRITE => options.write(true).create(true).truncate(true),
+            OPEN_APPEND => options.append(true).create(true),
+            _ => return None,
+        };
+        let file: File = options.open(path).ok()?;
+
+        let handle: usize = match self.files.iter().position(Option::is_none) {
+            Some(handle) => handle,
+            None => {
+                self.files.push(None);
+                self.files.len() - 1
+            }
+        };
+        self.files[handle] = Some(file);
+
+        u16::try_from(handle)
+            .ok()
+            .filter(|&handle| handle != SEMIHOST_ERROR)
+    }
+
+    fn sandboxed(&self, name: &str) -> Option<PathBuf> {
+        let path: &Path = Path::new(name);
+        let contained: bool = path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+
+        match contained && !name.is_empty() {
+            true => Some(self.root.join(path)),
+            false => None,
+        }
+    }
+
+    fn file(&mut self, handle: u16) -> Option<&mut File> {
+        self.files.get_mut(handle as usize)?.as_mut()
+    }
+
+    fn read(&mut self, handle: u16, buffer: u16, count: u16, memory: &mut Memory) -> Option<u16> {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        self.file(handle)?
+            .take(count as u64)
+            .read_to_end(&mut bytes)
+            .ok()?;
+
+        for (offset, byte) in bytes.iter().enumerate() {
+            memory.poke(buffer.wrapping_add(offset as u16), *byte as u16);
+        }
+
+        Some(bytes.len() as u16)
+    }
+
+    fn write(&mut self, handle: u16, buffer: u16, count: u16, memory: &Memory) -> Option<u16> {
+        let bytes: Vec<u8> = (0..count)
+            .map(|offset| memory.peek(buffer.wrapping_add(offset)) as u8)
+            .collect();
+
+        let file: &mut File = self.file(handle)?;
+        file.write_all(&bytes).ok()?;
+        file.flush().ok()?;
+
+        Some(count)
+    }
+}
+
+// Characters up to the terminating zero, one per word. None if a restricted caller's string
+// runs into privileged memory before it ends
+fn read_string(memory: &Memory, address: u16, restricted: bool) -> Option<String> {
+    let mut text: String = String::new();
+
+    for offset in 0..u16::MAX {
+        let address: u16 = address.wrapping_add(offset);
+
+        if restricted && memory::privileged(address) {
+            return None;
+        }
+
+        match memory.peek(address) as u8 {
+            0 => break,
+            byte => text.push(char::from(byte)),
+        }
+    }
+
+    Some(text)
+}
+
+// Whether a buffer of `count` words stays clear of privileged memory
+fn accessible(buffer: u16, count: u16) -> bool {
+    (0..count).all(|offset| !memory::privileged(buffer.wrapping_add(offset)))
+}
+
+#[cfg(test)]
+#[path = "./semihost_test.rs"]
+mod semihost_test;
@@ -0,0 +1,204 @@
+use crate::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    cpu::{RunState, CPU},
+    memory::{Memory, DDR, KBSR},
+    semihost::{
+        Semihost, OPEN_APPEND, OPEN_READ, OPEN_WRITE, SEMIHOST_ERROR, TRAP_CLOSE, TRAP_OPEN,
+        TRAP_READ, TRAP_TIME, TRAP_WRITE,
+    },
+};
+
+fn sandbox(name: &str) -> String {
+    let path = std::env::temp_dir().join(name);
+    std::fs::create_dir_all(&path).unwrap();
+    path.to_string_lossy().into_owned()
+}
+
+fn string(memory: &mut Memory, address: u16, text: &str) {
+    for (offset, byte) in text.bytes().chain([0]).enumerate() {
+        memory.poke(address + offset as u16, byte as u16);
+    }
+}
+
+#[test]
+fn test_files() {
+    let root: String = sandbox("lc_3_semihost_files");
+    std::fs::write(format!("{}/input.txt", root), "fixture").unwrap();
+
+    let mut semihost: Semihost = Semihost::new(&root);
+    let mut memory: Memory = Memory::new();
+    let mut registers: [u16; 8] = [0; 8];
+
+    string(&mut memory, 0x4000, "input.txt");
+    registers[..2].copy_from_slice(&[0x4000, OPEN_READ]);
+    semihost.call(TRAP_OPEN, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], 0);
+
+    // Short reads at the end of the file
+    registers[..3].copy_from_slice(&[0, 0x5000, 100]);
+    semihost.call(TRAP_READ, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], 7);
+    assert_eq!(memory.peek(0x5000), b'f' as u16);
+    assert_eq!(memory.peek(0x5006), b'e' as u16);
+
+    string(&mut memory, 0x4000, "./output.txt");
+    registers[..2].copy_from_slice(&[0x4000, OPEN_WRITE]);
+    semihost.call(TRAP_OPEN, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], 1);
+    registers[..3].copy_from_slice(&[1, 0x5000, 4]);
+    semihost.call(TRAP_WRITE, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], 4);
+
+    // Closed handles are reused
+    for handle in [0, 1] {
+        registers[0] = handle;
+        semihost.call(TRAP_CLOSE, &mut registers, &mut memory, false);
+        assert_eq!(registers[0], 0);
+    }
+    registers[0] = 1;
+    semihost.call(TRAP_CLOSE, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], SEMIHOST_ERROR);
+
+    registers[..2].copy_from_slice(&[0x4000, OPEN_APPEND]);
+    semihost.call(TRAP_OPEN, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], 0);
+    registers[..3].copy_from_slice(&[0, 0x5004, 3]);
+    semihost.call(TRAP_WRITE, &mut registers, &mut memory, false);
+    assert_eq!(
+        std::fs::read_to_string(format!("{}/output.txt", root)).unwrap(),
+        "fixture"
+    );
+
+    // Nothing to read through a file opened for appending only
+    registers[..3].copy_from_slice(&[0, 0x5000, 1]);
+    semihost.call(TRAP_READ, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], SEMIHOST_ERROR);
+}
+
+#[test]
+fn test_sandbox() {
+    let root: String = sandbox("lc_3_semihost_sandbox");
+    let mut semihost: Semihost = Semihost::new(&root);
+    let mut memory: Memory = Memory::new();
+    let mut registers: [u16; 8] = [0; 8];
+
+    for path in [
+        "",
+        "/etc/passwd",
+        "../escape.txt",
+        "a/../../escape.txt",
+        "missing.txt",
+    ] {
+        string(&mut memory, 0x4000, path);
+        registers[..2].copy_from_slice(&[0x4000, OPEN_READ]);
+        semihost.call(TRAP_OPEN, &mut registers, &mut memory, false);
+        assert_eq!(registers[0], SEMIHOST_ERROR, "{}", path);
+    }
+
+    // Unknown modes and handles
+    string(&mut memory, 0x4000, "new.txt");
+    registers[..2].copy_from_slice(&[0x4000, 7]);
+    semihost.call(TRAP_OPEN, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], SEMIHOST_ERROR);
+    registers[..3].copy_from_slice(&[3, 0x5000, 1]);
+    semihost.call(TRAP_WRITE, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], SEMIHOST_ERROR);
+}
+
+#[test]
+fn test_privileged_buffers() {
+    let root: String = sandbox("lc_3_semihost_privileged");
+    std::fs::write(format!("{}/input.txt", root), "fixture").unwrap();
+
+    let console: BufferedConsole = BufferedConsole::new(b"k");
+    let output = console.output();
+    let mut semihost: Semihost = Semihost::new(&root);
+    let mut memory: Memory = Memory::with_console(Box::new(console));
+    let mut registers: [u16; 8] = [0; 8];
+
+    // A user mode caller may not name a file with a string from system space
+    string(&mut memory, 0x2FFC, "input.txt");
+    registers[..2].copy_from_slice(&[0x2FFC, OPEN_READ]);
+    semihost.call(TRAP_OPEN, &mut registers, &mut memory, true);
+    assert_eq!(registers[0], SEMIHOST_ERROR);
+    string(&mut memory, 0x4000, "input.txt");
+    registers[..2].copy_from_slice(&[0x4000, OPEN_READ]);
+    semihost.call(TRAP_OPEN, &mut registers, &mut memory, true);
+    assert_eq!(registers[0], 0);
+
+    // Nor move data between a file and system space or the device registers
+    for (vector, buffer) in [(TRAP_READ, 0x2F00), (TRAP_READ, DDR), (TRAP_WRITE, KBSR)] {
+        registers[..3].copy_from_slice(&[0, buffer, 2]);
+        semihost.call(vector, &mut registers, &mut memory, true);
+        assert_eq!(registers[0], SEMIHOST_ERROR);
+    }
+    assert_eq!(memory.peek(0x2F00), 0);
+
+    // Supervisor buffers reach the cells without touching the devices behind them
+    registers[..3].copy_from_slice(&[0, DDR, 1]);
+    semihost.call(TRAP_READ, &mut registers, &mut memory, false);
+    assert_eq!(registers[0], 1);
+    assert_eq!(memory.peek(DDR), b'f' as u16);
+    assert!(output.borrow().is_empty());
+    assert_eq!(memory.read(KBSR), 1 << 15);
+}
+
+#[test]
+fn test_time() {
+    let mut semihost: Semihost = Semihost::new(".");
+    let mut registers: [u16; 8] = [0; 8];
+
+    semihost.call(TRAP_TIME, &mut registers, &mut Memory::new(), false);
+
+    let seconds: u64 = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let reported: u64 = ((registers[1] as u64) << 16) | registers[0] as u64;
+    assert!((seconds as u32 as u64).abs_diff(reported) <= 1);
+    assert!(registers[2] < 1000);
+}
+
+#[test]
+fn test_program_exit() {
+    let program: Program = assemble(
+        "
+        .ORIG x3000
+        LEA R0, NAME
+        AND R1, R1, #0      ; Read the fixture
+        TRAP x30
+        ADD R3, R0, #0
+        LEA R1, BUFFER
+        AND R2, R2, #0
+        ADD R2, R2, #8
+        TRAP x32
+        LD R0, STATUS       ; A failed test reports through its exit status
+        TRAP x35
+        HALT
+NAME    .STRINGZ \"expected.txt\"
+STATUS  .FILL #3
+BUFFER  .BLKW 8
+        .END
+        ",
+    )
+    .unwrap();
+    let root: String = sandbox("lc_3_semihost_program");
+    std::fs::write(format!("{}/expected.txt", root), "42").unwrap();
+
+    let mut cpu: CPU = CPU::new();
+    cpu.load_image(&program.to_object()).unwrap();
+    cpu.set_semihost(Semihost::new(&root));
+
+    assert_eq!(cpu.run(None), RunState::Halted);
+    assert_eq!(cpu.exit_status(), Some(3));
+    assert_eq!(cpu.registers()[3], 0);
+    assert_eq!(
+        cpu.memory().peek(program.symbols["BUFFER"] + 1),
+        b'2' as u16
+    );
+    assert_eq!(cpu.instruction_count(), 10);
+
+    // Without semihosting the machine does not know an exit status
+    assert_eq!(CPU::new().exit_status(), None);
+}
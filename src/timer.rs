@@ -0,0 +1,216 @@
+use alloc::boxed::Box;
+
+use crate::device::Clock;
+
+// Timer status register layout
+pub const TIMER_READY: u16 = 1 << 15; // Set when the interval expired, cleared by accessing TSR
+pub const TIMER_INTERRUPT_ENABLE: u16 = 1 << 14;
+pub const TIMER_MILLISECONDS: u16 = 1 << 13; // Count wall-clock milliseconds instead of instructions
+pub const TIMER_PRIORITY: u16 = 0x0700; // Priority level of the interrupt
+pub const TIMER_VECTOR: u16 = 0x00FF; // Interrupt vector
+
+// Used while the priority or vector field is zero, like for the UART: a priority 0 interrupt
+// could never be taken and vector x00 belongs to an exception
+pub const TIMER_DEFAULT_PRIORITY: u16 = 4;
+pub const TIMER_DEFAULT_VECTOR: u8 = 0x81;
+
+// Wall-clock deadlines are only checked this often, reading the clock is slow next to an instruction
+const CLOCK_CHECK_INTERVAL: u64 = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Deadline {
+    Stopped,
+    Instructions(u64),
+    // Clock reading in milliseconds
+    Time(u64),
+}
+
+// Registers and deadline of a timer, saved with memory snapshots
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TimerState {
+    status: u16,
+    interval: u16,
+    deadline: Deadline,
+}
+
+// Periodic timer behind the TSR/TIR register pair. Writing a non-zero interval to TIR starts
+// it, every time the interval passes the ready bit is set and the count starts over.
+pub struct Timer {
+    status: u16,
+    interval: u16,
+    deadline: Deadline,
+    // Without a clock the millisecond mode never expires
+    clock: Option<Box<dyn Clock>>,
+}
+
+impl Timer {
+    pub fn new() -> Self {
+        Timer {
+            status: 0,
+            interval: 0,
+            deadline: Deadline::Stopped,
+            clock: host_clock(),
+        }
+    }
+
+    pub fn set_clock(&mut self, clock: Box<dyn Clock>) {
+        self.clock = Some(clock);
+    }
+
+    pub fn state(&self) -> TimerState {
+        TimerState {
+            status: self.status,
+            interval: self.interval,
+            deadline: self.deadline,
+        }
+    }
+
+    pub fn restore(&mut self, state: TimerState) {
+        self.status = state.status;
+        self.interval = state.interval;
+        self.deadline = state.deadline;
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+
+    pub fn interval(&self) -> u16 {
+        self.interval
+    }
+
+    // Reading the status acknowledges an expiry
+    pub fn read_status(&mut self) -> u16 {
+        let status: u16 = self.status;
+        self.status &= !TIMER_READY;
+        status
+    }
+
+    // Configures mode, interrupt enable, priority and vector, acknowledging any expiry
+    pub fn write_status(&mut self, value: u16, instruction_count: u64) {
+        let mode_changed: bool = (self.status ^ value) & TIMER_MILLISECONDS != 0;
+        self.status = value & !TIMER_READY;
+
+        if mode_changed {
+            self.restart(instruction_count);
+        }
+    }
+
+    pub fn write_interval(&mut self, interval: u16, instruction_count: u64) {
+        self.interval = interval;
+        self.restart(instruction_count);
+    }
+
+    #[inline]
+    pub fn running(&self) -> bool {
+        self.deadline != Deadline::Stopped
+    }
+
+    // Called after every instruction while running
+    pub fn tick(&mut self, instruction_count: u64) {
+        match self.deadline {
+            Deadline::Stopped => {}
+            Deadline::Instructions(deadline) => {
+                if instruction_count >= deadline {
+                    self.status |= TIMER_READY;
+                    self.deadline =
+                        Deadline::Instructions(instruction_count + self.interval as u64);
+                }
+            }
+            Deadline::Time(deadline) => {
+                if instruction_count.is_multiple_of(CLOCK_CHECK_INTERVAL) {
+                    let Some(now) = self.now() else {
+                        return;
+                    };
+
+                    if now >= deadline {
+                        self.status |= TIMER_READY;
+                        self.deadline = Deadline::Time(now + self.interval as u64);
+                    }
+                }
+            }
+        }
+    }
+
+    // Priority and vector of the interrupt the timer is requesting, if any
+    #[inline]
+    pub fn interrupt(&self) -> Option<(u16, u8)> {
+        if self.status & (TIMER_READY | TIMER_INTERRUPT_ENABLE) != 0xC000 {
+            return None;
+        }
+
+        let priority: u16 = match (self.status & TIMER_PRIORITY) >> 8 {
+            0 => TIMER_DEFAULT_PRIORITY,
+            priority => priority,
+        };
+        let vector: u8 = match (self.status & TIMER_VECTOR) as u8 {
+            0 => TIMER_DEFAULT_VECTOR,
+            vector => vector,
+        };
+        Some((priority, vector))
+    }
+
+    fn now(&mut self) -> Option<u64> {
+        self.clock.as_mut().map(|clock| clock.milliseconds())
+    }
+
+    fn restart(&mut self, instruction_count: u64) {
+        self.deadline = match (self.interval, self.status & TIMER_MILLISECONDS) {
+            (0, _) => Deadline::Stopped,
+            (interval, 0) => Deadline::Instructions(instruction_count + interval as u64),
+            (interval, _) => match self.now() {
+                Some(now) => Deadline::Time(now + interval as u64),
+                None => Deadline::Time(u64::MAX),
+            },
+        };
+    }
+}
+
+// Milliseconds since the clock was created, read from the host's monotonic clock
+#[cfg(feature = "std")]
+pub struct HostClock {
+    start: std::time::Instant,
+}
+
+#[cfg(feature = "std")]
+impl HostClock {
+    pub fn new() -> Self {
+        HostClock {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for HostClock {
+    fn default() -> Self {
+        HostClock::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for HostClock {
+    fn milliseconds(&mut self) -> u64 {
+        self.start.elapsed().as_millis() as u64
+    }
+}
+
+#[cfg(feature = "std")]
+fn host_clock() -> Option<Box<dyn Clock>> {
+    Some(Box::new(HostClock::new()))
+}
+
+#[cfg(not(feature = "std"))]
+fn host_clock() -> Option<Box<dyn Clock>> {
+    None
+}
+
+impl Default for Timer {
+    fn default() -> Self {
+        Timer::new()
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+#[path = "./timer_test.rs"]
+mod timer_test;
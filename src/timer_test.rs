@@ -0,0 +1,95 @@
+use std::{thread, time::Duration};
+
+use crate::{
+    console::BufferedConsole,
+    cpu::{RunState, CPU},
+    memory::{TIR, TSR},
+    timer::{
+        Timer, TIMER_DEFAULT_PRIORITY, TIMER_DEFAULT_VECTOR, TIMER_INTERRUPT_ENABLE,
+        TIMER_MILLISECONDS, TIMER_READY,
+    },
+};
+
+#[test]
+fn test_instruction_interval() {
+    let mut timer: Timer = Timer::new();
+    assert!(!timer.running());
+
+    timer.write_interval(100, 50);
+    assert!(timer.running());
+
+    timer.tick(149);
+    assert_eq!(timer.status() & TIMER_READY, 0);
+    timer.tick(150);
+    assert_eq!(timer.read_status() & TIMER_READY, TIMER_READY);
+    // Reading the status acknowledged the expiry, the next one comes a period later
+    assert_eq!(timer.status() & TIMER_READY, 0);
+    timer.tick(249);
+    assert_eq!(timer.status() & TIMER_READY, 0);
+    timer.tick(250);
+    assert_eq!(timer.status() & TIMER_READY, TIMER_READY);
+
+    timer.write_interval(0, 300);
+    assert!(!timer.running());
+}
+
+#[test]
+fn test_interrupt_request() {
+    let mut timer: Timer = Timer::new();
+    timer.write_status(TIMER_INTERRUPT_ENABLE | 0x0400 | 0x81, 0);
+    timer.write_interval(10, 0);
+
+    assert_eq!(timer.interrupt(), None);
+    timer.tick(10);
+    assert_eq!(timer.interrupt(), Some((4, 0x81)));
+
+    // Writing the status acknowledges as well
+    timer.write_status(0x0400 | 0x81, 10);
+    assert_eq!(timer.interrupt(), None);
+    timer.tick(20);
+    assert_eq!(timer.status() & TIMER_READY, TIMER_READY);
+    assert_eq!(timer.interrupt(), None);
+
+    // Zero fields stand for the defaults, as a PL0 interrupt could never be taken
+    timer.write_status(TIMER_INTERRUPT_ENABLE, 20);
+    timer.tick(30);
+    assert_eq!(
+        timer.interrupt(),
+        Some((TIMER_DEFAULT_PRIORITY, TIMER_DEFAULT_VECTOR))
+    );
+}
+
+#[test]
+fn test_no_interrupt_after_halt() {
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(b"")));
+    cpu.load_image(&[0x30, 0x00, 0xF0, 0x25]).unwrap();
+
+    // Expires on the HALT instruction itself
+    cpu.memory_mut()
+        .write(TSR, TIMER_INTERRUPT_ENABLE | 0x0400 | 0x81);
+    cpu.memory_mut().write(TIR, 1);
+
+    assert_eq!(cpu.step(), RunState::Halted);
+    assert_eq!(cpu.memory().peek(TSR) & TIMER_READY, TIMER_READY);
+    assert_eq!(cpu.program_counter(), 0x3001);
+    assert_eq!(cpu.registers()[6], 0);
+    assert_eq!(cpu.processor_status_register() & 0x0700, 0);
+    assert_eq!(cpu.memory().words(0x2FFE, 0x2FFF), [0, 0]);
+}
+
+#[test]
+fn test_millisecond_interval() {
+    let mut timer: Timer = Timer::new();
+    timer.write_status(TIMER_MILLISECONDS, 0);
+    timer.write_interval(5, 0);
+
+    timer.tick(0);
+    assert_eq!(timer.status() & TIMER_READY, 0);
+    thread::sleep(Duration::from_millis(10));
+
+    // The clock is only consulted every so many instructions
+    timer.tick(1);
+    assert_eq!(timer.status() & TIMER_READY, 0);
+    timer.tick(1024);
+    assert_eq!(timer.status() & TIMER_READY, TIMER_READY);
+}
@@ -0,0 +1,183 @@
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{cpu::RunState, memory::Memory};
+
+// Standard trap vectors
+pub const TRAP_GETC: u8 = 0x20;
+pub const TRAP_OUT: u8 = 0x21;
+pub const TRAP_PUTS: u8 = 0x22;
+pub const TRAP_IN: u8 = 0x23;
+pub const TRAP_PUTSP: u8 = 0x24;
+pub const TRAP_HALT: u8 = 0x25;
+
+// The parts of the machine a trap handler works on. The program counter already points past
+// the TRAP instruction
+pub struct TrapContext<'a> {
+    pub registers: &'a mut [u16; 8],
+    pub memory: &'a mut Memory,
+    pub program_counter: &'a mut u16,
+    state: &'a mut RunState,
+}
+
+impl<'a> TrapContext<'a> {
+    pub fn new(
+        registers: &'a mut [u16; 8],
+        memory: &'a mut Memory,
+        program_counter: &'a mut u16,
+        state: &'a mut RunState,
+    ) -> Self {
+        TrapContext {
+            registers,
+            memory,
+            program_counter,
+            state,
+        }
+    }
+
+    pub fn print(&mut self, byte: u8) {
+        self.memory.print(byte);
+    }
+
+    pub fn print_str(&mut self, text: &str) {
+        for byte in text.bytes() {
+            self.print(byte);
+        }
+    }
+
+    pub fn halt(&mut self) {
+        *self.state = RunState::Halted;
+    }
+
+    // Stops for lack of input, the trap runs again if the machine is resumed after more arrives
+    pub fn input_exhausted(&mut self) {
+        *self.program_counter = self.program_counter.wrapping_sub(1);
+        *self.state = RunState::InputExhausted;
+    }
+}
+
+// Code run in Rust for a TRAP instruction. Closures taking a `&mut TrapContext` are handlers too
+pub trait TrapHandler {
+    fn call(&mut self, context: &mut TrapContext);
+}
+
+impl<F: FnMut(&mut TrapContext)> TrapHandler for F {
+    fn call(&mut self, context: &mut TrapContext) {
+        self(context)
+    }
+}
+
+// Handlers for the 256 trap vectors, serving TRAP instructions while no OS image is loaded
+pub struct TrapTable {
+    handlers: Vec<Option<Box<dyn TrapHandler>>>,
+}
+
+impl TrapTable {
+    // A table with the standard routines installed
+    pub fn new() -> Self {
+        let mut table: TrapTable = TrapTable::empty();
+
+        table.register(TRAP_GETC, getc);
+        table.register(TRAP_OUT, out);
+        table.register(TRAP_PUTS, puts);
+        table.register(TRAP_IN, input);
+        table.register(TRAP_PUTSP, putsp);
+        table.register(TRAP_HALT, halt);
+        table
+    }
+
+    pub fn empty() -> Self {
+        TrapTable {
+            handlers: (0..256).map(|_| None).collect(),
+        }
+    }
+
+    // Installs a handler, returning the one it replaces. The CPU only consults the table while
+    // no OS image is loaded, and semihosting takes x30 to x35 first when enabled, see
+    // CPU::register_trap
+    pub fn register(
+        &mut self,
+        vector: u8,
+        handler: impl TrapHandler + 'static,
+    ) -> Option<Box<dyn TrapHandler>> {
+        self.handlers[vector as usize].replace(Box::new(handler))
+    }
+
+    pub fn unregister(&mut self, vector: u8) -> Option<Box<dyn TrapHandler>> {
+        self.handlers[vector as usize].take()
+    }
+
+    pub fn handles(&self, vector: u8) -> bool {
+        self.handlers[vector as usize].is_some()
+    }
+
+    // Runs the handler for a vector, false if there is none
+    pub fn call(&mut self, vector: u8, context: &mut TrapContext) -> bool {
+        match &mut self.handlers[vector as usize] {
+            Some(handler) => {
+                handler.call(context);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for TrapTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+pub fn getc(context: &mut TrapContext) {
+    match context.memory.read_key() {
+        Some(byte) => context.registers[0] = byte as u16,
+        None => context.input_exhausted(),
+    }
+}
+
+pub fn out(context: &mut TrapContext) {
+    context.print(context.registers[0] as u8);
+}
+
+pub fn puts(context: &mut TrapContext) {
+    let mut index = context.registers[0];
+    let mut char_value: u16 = context.memory.read(index);
+
+    while char_value != 0 {
+        context.print(char_value as u8);
+        index = index.wrapping_add(1);
+        char_value = context.memory.read(index);
+    }
+}
+
+pub fn input(context: &mut TrapContext) {
+    context.print_str("Please enter a character.");
+    getc(context);
+}
+
+pub fn putsp(context: &mut TrapContext) {
+    let mut index: u16 = context.registers[0];
+    let mut char_bytes: u16 = context.memory.read(index);
+
+    while char_bytes != 0 {
+        let low_char: u8 = (char_bytes & 0x00FF) as u8;
+        context.print(low_char);
+        let high_char: u8 = (char_bytes >> 8) as u8;
+
+        if high_char != 0 {
+            context.print(high_char);
+        }
+
+        index = index.wrapping_add(1);
+        char_bytes = context.memory.read(index);
+    }
+}
+
+pub fn halt(context: &mut TrapContext) {
+    context.print_str("\nHALT\n");
+    context.halt();
+}
+
+#[cfg(all(test, feature = "std"))]
+#[path = "./trap_test.rs"]
+mod trap_test;
@@ -0,0 +1,131 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    cpu::{Exception, RunState, CPU},
+    semihost::{Semihost, TRAP_EXIT},
+    trap::{TrapContext, TrapHandler, TrapTable, TRAP_HALT, TRAP_OUT},
+};
+
+fn program() -> Program {
+    assemble(
+        "
+        .ORIG x3000
+        LD R0, CHAR
+        OUT
+        TRAP x40
+        TRAP x40
+        OUT
+        HALT
+CHAR    .FILL x61
+        .END
+        ",
+    )
+    .unwrap()
+}
+
+// Counts its calls and stores the count where R0 points
+struct Counter {
+    calls: Rc<RefCell<u16>>,
+}
+
+impl TrapHandler for Counter {
+    fn call(&mut self, context: &mut TrapContext) {
+        *self.calls.borrow_mut() += 1;
+        context.memory.write(0x4000, *self.calls.borrow());
+        context.registers[0] += 1;
+    }
+}
+
+#[test]
+fn test_table() {
+    let mut table: TrapTable = TrapTable::new();
+
+    assert!((0x20..=0x25).all(|vector| table.handles(vector)));
+    assert!(!table.handles(0x26));
+    assert!(!TrapTable::empty().handles(TRAP_HALT));
+
+    assert!(table.register(0x40, |_: &mut TrapContext| {}).is_none());
+    assert!(table.register(0x40, |_: &mut TrapContext| {}).is_some());
+    assert!(table.unregister(0x40).is_some());
+    assert!(!table.handles(0x40));
+}
+
+#[test]
+fn test_custom_handlers() {
+    let console: BufferedConsole = BufferedConsole::new(b"");
+    let output = console.output();
+    let mut cpu: CPU = CPU::with_console(Box::new(console));
+    let calls: Rc<RefCell<u16>> = Rc::new(RefCell::new(0));
+
+    cpu.load_image(&program().to_object()).unwrap();
+    cpu.register_trap(
+        0x40,
+        Counter {
+            calls: calls.clone(),
+        },
+    )
+    .unwrap();
+    // Standard routines can be replaced and called from the replacement
+    cpu.register_trap(TRAP_OUT, |context: &mut TrapContext| {
+        context.print(b'[');
+        crate::trap::out(context);
+        context.print(b']');
+    })
+    .unwrap();
+    cpu.register_trap(TRAP_HALT, |context: &mut TrapContext| context.halt())
+        .unwrap();
+
+    assert_eq!(cpu.run(None), RunState::Halted);
+    assert_eq!(*calls.borrow(), 2);
+    assert_eq!(cpu.memory().peek(0x4000), 2);
+    assert_eq!(cpu.registers()[0], 0x63);
+    assert_eq!(*output.borrow(), b"[a][c]");
+}
+
+#[test]
+fn test_shadowed_by_semihosting() {
+    let mut cpu: CPU = CPU::new();
+    cpu.set_semihost(Semihost::new("."));
+
+    assert_eq!(
+        cpu.register_trap(TRAP_EXIT, |context: &mut TrapContext| context.halt())
+            .err(),
+        Some(String::from("trap x35 is served by semihosting"))
+    );
+    assert!(!cpu.traps_mut().handles(TRAP_EXIT));
+    // Vectors outside the semihosting range are still free
+    assert!(cpu
+        .register_trap(0x40, |context: &mut TrapContext| context.halt())
+        .is_ok());
+}
+
+#[test]
+fn test_shadowed_by_os_image() {
+    let mut cpu: CPU = CPU::new();
+    cpu.load_os_image(&[0x00, 0x25, 0x04, 0x00]).unwrap();
+
+    assert_eq!(
+        cpu.register_trap(0x40, |context: &mut TrapContext| context.halt())
+            .err(),
+        Some(String::from("trap x40 is served by the loaded OS image"))
+    );
+    assert!(!cpu.traps_mut().handles(0x40));
+}
+
+#[test]
+fn test_unregistered_vector() {
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(b"")));
+
+    cpu.load_image(&program().to_object()).unwrap();
+    cpu.traps_mut().unregister(TRAP_OUT);
+
+    // The machine stops on the TRAP instruction instead of taking the host down
+    assert_eq!(
+        cpu.run(None),
+        RunState::Fault(Exception::UnknownTrap(TRAP_OUT))
+    );
+    assert_eq!(cpu.program_counter(), 0x3001);
+    assert_eq!(cpu.instruction_count(), 2);
+}
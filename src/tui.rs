@@ -0,0 +1,441 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    io::{self, Read, Write},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+use termios::{tcsetattr, Termios, TCSANOW, VMIN, VTIME};
+
+use lc_3::{
+    assembler::SymbolTable,
+    console::Console,
+    cpu::{RunState, CPU},
+    screen::{Screen, ScreenConsole},
+};
+
+use crate::debugger::{parse_value, Debugger, Flow};
+
+// Size of the full-screen layout, which fits a standard terminal
+pub const WIDTH: usize = 80;
+pub const HEIGHT: usize = 24;
+
+// Width of the disassembly and memory panes, the register and stack panes fill the rest
+const LEFT: usize = 50;
+const DISASSEMBLY_LINES: usize = 11;
+const MEMORY_LINES: usize = 4;
+const CONSOLE_LINES: usize = 4;
+
+// Instructions executed between checks for key presses while the program runs
+const SLICE: u64 = 20_000;
+const REDRAW: Duration = Duration::from_millis(50);
+
+const PAUSE: u8 = 0x10; // Ctrl-P
+const ESCAPE: u8 = 0x1B;
+
+const HELP: &str =
+    "s step  c continue  b break  j/k move  . pc  g goto  x mem  i input  : cmd  q quit";
+
+// Keys typed for the program, handed out in order
+struct Keyboard {
+    keys: Rc<RefCell<VecDeque<u8>>>,
+}
+
+impl Console for Keyboard {
+    fn read_byte(&mut self) -> Option<u8> {
+        self.keys.borrow_mut().pop_front()
+    }
+
+    // Program output ends up on the virtual screen instead
+    fn write_byte(&mut self, _byte: u8) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Prompt {
+    Command,
+    Input,
+    Goto,
+    Memory,
+}
+
+pub struct Tui {
+    pub debugger: Debugger,
+    title: String,
+    screen: Rc<RefCell<Screen>>,
+    keyboard: Rc<RefCell<VecDeque<u8>>>,
+    // Selected line of the disassembly pane
+    cursor: u16,
+    // First word of the memory pane
+    memory_address: u16,
+    // The program runs until a breakpoint, a stop or Ctrl-P
+    running: bool,
+    prompt: Option<(Prompt, String)>,
+    message: String,
+}
+
+impl Tui {
+    pub fn new(mut cpu: CPU, symbols: SymbolTable, title: &str) -> Self {
+        let keyboard: Rc<RefCell<VecDeque<u8>>> = Rc::new(RefCell::new(VecDeque::new()));
+        let console: ScreenConsole = ScreenConsole::new(Box::new(Keyboard {
+            keys: Rc::clone(&keyboard),
+        }));
+        let screen: Rc<RefCell<Screen>> = console.screen();
+        cpu.memory_mut().set_console(Box::new(console));
+
+        let program_counter: u16 = cpu.program_counter();
+
+        Tui {
+            debugger: Debugger::new(cpu, symbols),
+            title: title.to_string(),
+            screen,
+            keyboard,
+            cursor: program_counter,
+            memory_address: program_counter,
+            running: false,
+            prompt: None,
+            message: String::new(),
+        }
+    }
+
+    // Whether the program should keep executing without waiting for a key
+    pub fn busy(&self) -> bool {
+        self.running && self.debugger.cpu.state() == RunState::Running
+    }
+
+    pub fn handle_key(&mut self, key: u8) -> Flow {
+        if let Some((prompt, mut text)) = self.prompt.take() {
+            match key {
+                b'\r' | b'\n' => return self.submit(prompt, &text),
+                ESCAPE => {}
+                0x08 | 0x7F => {
+                    text.pop();
+                    self.prompt = Some((prompt, text));
+                }
+                0x20..=0x7E => {
+                    text.push(key as char);
+                    self.prompt = Some((prompt, text));
+                }
+                _ => self.prompt = Some((prompt, text)),
+            }
+            return Flow::Continue;
+        }
+
+        if self.running {
+            match key {
+                PAUSE => {
+                    self.running = false;
+                    self.message = String::from("Paused");
+                    self.cursor = self.debugger.cpu.program_counter();
+                }
+                // Everything else is typed into the program
+                _ => self.type_keys(&[key]),
+            }
+            return Flow::Continue;
+        }
+
+        self.message.clear();
+
+        match key {
+            b's' => {
+                self.debugger.cpu.step();
+                self.debugger.cpu.memory_mut().console().flush();
+                self.cursor = self.debugger.cpu.program_counter();
+                self.report_stop();
+            }
+            b'c' => match self.debugger.cpu.state() {
+                RunState::Running => self.running = true,
+                _ => self.report_stop(),
+            },
+            b'b' => {
+                let state: &str = match self.debugger.toggle_breakpoint(self.cursor) {
+                    true => "set",
+                    false => "removed",
+                };
+                self.message = format!("Breakpoint {} at x{:04X}", state, self.cursor);
+            }
+            b'j' => self.cursor = self.cursor.wrapping_add(1),
+            b'k' => self.cursor = self.cursor.wrapping_sub(1),
+            b'.' => self.cursor = self.debugger.cpu.program_counter(),
+            b'[' => self.memory_address = self.memory_address.wrapping_sub(8),
+            b']' => self.memory_address = self.memory_address.wrapping_add(8),
+            b':' => self.prompt = Some((Prompt::Command, String::new())),
+            b'i' => self.prompt = Some((Prompt::Input, String::new())),
+            b'g' => self.prompt = Some((Prompt::Goto, String::new())),
+            b'x' => self.prompt = Some((Prompt::Memory, String::new())),
+            b'q' => return Flow::Quit,
+            _ => self.message = String::from(HELP),
+        }
+
+        Flow::Continue
+    }
+
+    // Executes one slice of a continued run
+    pub fn run_slice(&mut self) {
+        for _ in 0..SLICE {
+            let state: RunState = self.debugger.cpu.step();
+            let program_counter: u16 = self.debugger.cpu.program_counter();
+
+            if state == RunState::InputExhausted {
+                // Keeps running as soon as a key is typed
+                break;
+            }
+
+            if state != RunState::Running {
+                self.running = false;
+                self.report_stop();
+                break;
+            }
+
+            if self.debugger.breakpoints().contains(&program_counter) {
+                self.running = false;
+                self.message = format!("Breakpoint reached at x{:04X}", program_counter);
+                break;
+            }
+        }
+
+        self.debugger.cpu.memory_mut().console().flush();
+        self.cursor = self.debugger.cpu.program_counter();
+    }
+
+    // The whole layout, HEIGHT lines of exactly WIDTH characters
+    pub fn render(&self) -> Vec<String> {
+        let cpu: &CPU = &self.debugger.cpu;
+        let mut lines: Vec<String> = vec![self.title_line()];
+
+        let registers: Vec<String> = self.register_lines();
+        lines.push(columns(" Disassembly", " Registers"));
+
+        for row in 0..DISASSEMBLY_LINES {
+            let address: u16 = self
+                .cursor
+                .wrapping_sub(DISASSEMBLY_LINES as u16 / 2)
+                .wrapping_add(row as u16);
+            let selected: char = if address == self.cursor { '>' } else { ' ' };
+            let line: String = format!("{}{}", selected, self.debugger.describe(address));
+
+            lines.push(columns(
+                &line,
+                registers.get(row).map_or("", String::as_str),
+            ));
+        }
+
+        lines.push(columns(" Memory", " Stack"));
+
+        for row in 0..MEMORY_LINES {
+            let start: u16 = self.memory_address.wrapping_add(row as u16 * 8);
+            let words: Vec<String> = (0..8u16)
+                .map(|offset| format!("{:04X}", cpu.memory().peek(start.wrapping_add(offset))))
+                .collect();
+
+            // The stack grows down, so older entries sit at higher addresses
+            let stack_pointer: u16 = cpu.registers()[6];
+            let address: u16 = stack_pointer.wrapping_add(2).wrapping_sub(row as u16);
+            let marker: &str = if address == stack_pointer {
+                "R6>"
+            } else {
+                "   "
+            };
+            let stack: String = format!(
+                " {} x{:04X}  x{:04X}",
+                marker,
+                address,
+                cpu.memory().peek(address)
+            );
+
+            lines.push(columns(
+                &format!(" x{:04X}  {}", start, words.join(" ")),
+                &stack,
+            ));
+        }
+
+        lines.push(String::from(" Console"));
+
+        // The screen rows leading up to the cursor, where the latest output is
+        let screen = self.screen.borrow();
+        let last: usize = screen.cursor().0 + 1;
+
+        for row in last.saturating_sub(CONSOLE_LINES)..last.max(CONSOLE_LINES) {
+            lines.push(screen.row(row));
+        }
+
+        lines.push(match &self.prompt {
+            Some((Prompt::Command, text)) => format!(":{}", text),
+            Some((Prompt::Input, text)) => format!("Input for the program: {}", text),
+            Some((Prompt::Goto, text)) => format!("Disassemble at: {}", text),
+            Some((Prompt::Memory, text)) => format!("Memory at: {}", text),
+            None if self.message.is_empty() => String::from(HELP),
+            None => self.message.clone(),
+        });
+
+        debug_assert_eq!(lines.len(), HEIGHT);
+        lines.iter().map(|line| fit(line, WIDTH)).collect()
+    }
+
+    // Takes over the terminal until the user quits or stdin closes
+    pub fn session(&mut self) -> io::Result<()> {
+        let mut out = io::stdout().lock();
+        write!(out, "\x1B[?1049h\x1B[?25l")?;
+
+        let result: io::Result<()> = self.event_loop(&mut io::stdin().lock(), &mut out);
+
+        write!(out, "\x1B[?25h\x1B[?1049l")?;
+        out.flush()?;
+        result
+    }
+
+    fn event_loop(&mut self, input: &mut dyn Read, out: &mut dyn Write) -> io::Result<()> {
+        let mut drawn: Option<Instant> = None;
+
+        loop {
+            let busy: bool = self.busy();
+
+            // While running, redraw only every so often to keep the program fast
+            if !busy || drawn.is_none_or(|drawn| drawn.elapsed() >= REDRAW) {
+                self.draw(out)?;
+                drawn = Some(Instant::now());
+            }
+
+            set_blocking(!busy);
+
+            let mut buffer: [u8; 64] = [0; 64];
+            let count: usize = input.read(&mut buffer)?;
+
+            if count == 0 && !busy {
+                return Ok(());
+            }
+
+            for &key in &buffer[..count] {
+                if self.handle_key(key) == Flow::Quit {
+                    return Ok(());
+                }
+            }
+
+            if self.busy() {
+                self.run_slice();
+            }
+        }
+    }
+
+    fn draw(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "\x1B[H{}", self.render().join("\r\n"))?;
+        out.flush()
+    }
+
+    fn submit(&mut self, prompt: Prompt, text: &str) -> Flow {
+        let value: Option<u16> = parse_value(text.trim(), &self.debugger.symbols);
+
+        match (prompt, value) {
+            (Prompt::Command, _) => {
+                let mut out: Vec<u8> = Vec::new();
+                let flow: Flow = self
+                    .debugger
+                    .execute(text, &mut out)
+                    .unwrap_or(Flow::Continue);
+                let output: String = String::from_utf8_lossy(&out).into_owned();
+
+                self.message = output.lines().collect::<Vec<&str>>().join("  ");
+                self.cursor = self.debugger.cpu.program_counter();
+                return flow;
+            }
+            (Prompt::Input, _) => {
+                let mut keys: Vec<u8> = text.as_bytes().to_vec();
+                keys.push(b'\n');
+                self.type_keys(&keys);
+            }
+            (Prompt::Goto, Some(address)) => self.cursor = address,
+            (Prompt::Memory, Some(address)) => self.memory_address = address,
+            (_, None) => self.message = format!("Invalid address '{}'", text.trim()),
+        }
+
+        Flow::Continue
+    }
+
+    fn type_keys(&mut self, keys: &[u8]) {
+        self.keyboard.borrow_mut().extend(keys);
+        self.debugger.cpu.resume();
+    }
+
+    fn report_stop(&mut self) {
+        self.message = match self.debugger.cpu.state() {
+            RunState::Running => return,
+            RunState::Halted => String::from("Program halted"),
+            RunState::InputExhausted => {
+                String::from("Program is waiting for input, press i to type some")
+            }
+            RunState::Fault(exception) => format!("Program stopped on {}", exception),
+        };
+    }
+
+    fn title_line(&self) -> String {
+        let cpu: &CPU = &self.debugger.cpu;
+        let state: String = match cpu.state() {
+            RunState::Running if self.running => String::from("running"),
+            RunState::Running => String::from("paused"),
+            RunState::Halted => String::from("halted"),
+            RunState::InputExhausted => String::from("waiting for input"),
+            RunState::Fault(exception) => exception.to_string(),
+        };
+
+        format!(
+            " lc_3 {} | {} | {} instructions",
+            self.title,
+            state,
+            cpu.instruction_count()
+        )
+    }
+
+    fn register_lines(&self) -> Vec<String> {
+        let cpu: &CPU = &self.debugger.cpu;
+        let mut lines: Vec<String> = cpu
+            .registers()
+            .iter()
+            .enumerate()
+            .map(|(index, &value)| format!(" R{}  x{:04X}  #{}", index, value, value as i16))
+            .collect();
+
+        let psr: u16 = cpu.processor_status_register();
+        let mode: &str = if psr & (1 << 15) != 0 {
+            "user"
+        } else {
+            "supervisor"
+        };
+
+        lines.push(format!(" PC  x{:04X}", cpu.program_counter()));
+        lines.push(format!(
+            " PSR x{:04X}  {} PL{}",
+            psr,
+            mode,
+            (psr >> 8) & 0x7
+        ));
+        lines.push(format!(
+            " CC  N={} Z={} P={}",
+            (psr >> 2) & 1,
+            (psr >> 1) & 1,
+            psr & 1
+        ));
+        lines
+    }
+}
+
+// Left pane padded to its width followed by the right pane
+fn columns(left: &str, right: &str) -> String {
+    format!("{}{}", fit(left, LEFT), right)
+}
+
+fn fit(text: &str, width: usize) -> String {
+    format!("{:<width$.width$}", text, width = width)
+}
+
+// Blocking reads wait for a key, non-blocking ones return nothing when no key is pending
+fn set_blocking(blocking: bool) {
+    if let Ok(mut termios) = Termios::from_fd(0) {
+        termios.c_cc[VMIN] = blocking as u8;
+        termios.c_cc[VTIME] = 0;
+        let _ = tcsetattr(0, TCSANOW, &termios);
+    }
+}
+
+#[cfg(test)]
+#[path = "./tui_test.rs"]
+mod tui_test;
@@ -0,0 +1,126 @@
+use lc_3::{
+    assembler::{assemble, Program},
+    console::BufferedConsole,
+    cpu::{RunState, CPU},
+};
+
+use crate::{
+    debugger::Flow,
+    tui::{Tui, HEIGHT, WIDTH},
+};
+
+const ECHO: &str = "
+        .ORIG x3000
+        LD R6, STACK
+LOOP    GETC
+        OUT
+        ADD R1, R0, #-10
+        BRnp LOOP
+        HALT
+STACK   .FILL xFE00
+        .END
+";
+
+fn tui(source: &str) -> Tui {
+    let program: Program = assemble(source).unwrap();
+    let mut cpu: CPU = CPU::with_console(Box::new(BufferedConsole::new(&[])));
+
+    cpu.load_image(&program.to_object()).unwrap();
+    Tui::new(cpu, program.symbols, "echo.asm")
+}
+
+fn keys(tui: &mut Tui, keys: &str) {
+    for key in keys.bytes() {
+        assert_eq!(tui.handle_key(key), Flow::Continue);
+    }
+}
+
+fn screen(tui: &Tui) -> String {
+    tui.render().join("\n")
+}
+
+#[test]
+fn test_layout() {
+    let tui: Tui = tui(ECHO);
+    let lines: Vec<String> = tui.render();
+
+    assert_eq!(lines.len(), HEIGHT);
+    assert!(lines.iter().all(|line| line.chars().count() == WIDTH));
+    assert!(lines[0].contains("echo.asm | paused | 0 instructions"));
+    // The disassembly pane is centered on the PC, registers sit beside it
+    assert!(lines[7].starts_with("> => x3000"));
+    assert!(lines[7].contains("LD R6, STACK"));
+    assert!(lines[2].contains(" R0  x0000  #0"));
+    assert!(lines[11].contains(" PSR x0000  supervisor PL0"));
+    assert!(lines[12].contains(" CC  N=0 Z=0 P=0"));
+    assert!(lines[14].starts_with(" x3000  2C05 F020"));
+}
+
+#[test]
+fn test_step_and_breakpoints() {
+    let mut tui: Tui = tui(ECHO);
+
+    keys(&mut tui, "s");
+    assert_eq!(tui.debugger.cpu.registers()[6], 0xFE00);
+    assert!(screen(&tui).contains("R6> xFE00"));
+
+    // Move the cursor down to OUT and break there
+    keys(&mut tui, "jjb");
+    assert!(tui.debugger.breakpoints().contains(&0x3003));
+    assert!(screen(&tui).contains(">*   x3003"));
+
+    keys(&mut tui, "kb");
+    assert!(tui.debugger.breakpoints().contains(&0x3002));
+    keys(&mut tui, "b");
+    assert!(!tui.debugger.breakpoints().contains(&0x3002));
+
+    // Continuing stops at GETC as nothing was typed yet
+    keys(&mut tui, "c");
+    tui.run_slice();
+    assert_eq!(tui.debugger.cpu.state(), RunState::InputExhausted);
+    assert!(screen(&tui).contains("waiting for input"));
+
+    // Typed keys go to the program, which runs on to the breakpoint
+    keys(&mut tui, "h");
+    assert!(tui.busy());
+    tui.run_slice();
+    assert_eq!(tui.debugger.cpu.program_counter(), 0x3003);
+    assert!(screen(&tui).contains("Breakpoint reached at x3003"));
+}
+
+#[test]
+fn test_program_console() {
+    let mut tui: Tui = tui(ECHO);
+
+    keys(&mut tui, "ihi\r");
+    keys(&mut tui, "c");
+    tui.run_slice();
+
+    assert_eq!(tui.debugger.cpu.state(), RunState::Halted);
+    let lines: Vec<String> = tui.render();
+    assert!(lines[19].starts_with("hi"));
+    assert!(lines[21].starts_with("HALT"));
+    assert!(lines[23].starts_with("Program halted"));
+}
+
+#[test]
+fn test_prompts() {
+    let mut tui: Tui = tui(ECHO);
+
+    keys(&mut tui, "gLOOP\r");
+    assert!(screen(&tui).contains(">    x3001"));
+
+    keys(&mut tui, "xx3004\r");
+    assert!(tui.render()[14].starts_with(" x3004  0BFC F025"));
+
+    keys(&mut tui, ":set R3 x00\x7F42\r");
+    assert_eq!(tui.debugger.cpu.registers()[3], 0x42);
+
+    keys(&mut tui, "gnowhere\r");
+    assert!(screen(&tui).contains("Invalid address 'nowhere'"));
+
+    keys(&mut tui, ":regs\r");
+    assert!(tui.render()[23].starts_with("R0=x0000"));
+
+    assert_eq!(tui.handle_key(b'q'), Flow::Quit);
+}
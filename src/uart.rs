@@ -0,0 +1,173 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Write},
+    os::{
+        fd::AsRawFd,
+        unix::net::{UnixListener, UnixStream},
+    },
+};
+
+use crate::device::Serial;
+
+// Receiver status register layout, the control bits follow the timer's TSR
+pub const UART_READY: u16 = 1 << 15; // A received byte is waiting in URDR
+pub const UART_INTERRUPT_ENABLE: u16 = 1 << 14;
+pub const UART_PRIORITY: u16 = 0x0700; // Priority level of the interrupt
+pub const UART_VECTOR: u16 = 0x00FF; // Interrupt vector
+
+// Used while the priority or vector field is zero: a priority 0 interrupt could never be taken
+// and vector x00 belongs to an exception. This is the keyboard's priority and vector x82
+pub const UART_DEFAULT_PRIORITY: u16 = 4;
+pub const UART_DEFAULT_VECTOR: u8 = 0x82;
+
+// The line is only checked for incoming bytes this often while interrupts are enabled
+const POLL_INTERVAL: u64 = 1024;
+
+trait Line: Read + Write {}
+
+impl<T: Read + Write> Line for T {}
+
+// Serial port whose other end is a host side Unix socket or pseudo-terminal. The line never
+// blocks the VM: reads find no byte yet, and bytes the host cannot take right away are dropped
+// like on a line nobody listens to.
+pub struct Uart {
+    line: Box<dyn Line>,
+    received: Option<u8>,
+    control: u16,
+    // The host closed its end
+    closed: bool,
+}
+
+impl Uart {
+    pub fn new(stream: UnixStream) -> io::Result<Self> {
+        stream.set_nonblocking(true)?;
+        Ok(Uart::with_line(Box::new(stream)))
+    }
+
+    pub fn connect(path: &str) -> io::Result<Self> {
+        Uart::new(UnixStream::connect(path)?)
+    }
+
+    // Waits for one peer to connect to a new socket at `path`
+    pub fn listen(path: &str) -> io::Result<Self> {
+        let _ = std::fs::remove_file(path);
+        let (stream, _) = UnixListener::bind(path)?.accept()?;
+        Uart::new(stream)
+    }
+
+    // Opens a pseudo-terminal, returning the UART on its master side and the path of the
+    // terminal for host tools to open
+    pub fn pty() -> io::Result<(Self, String)> {
+        let master: File = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/ptmx")?;
+        let fd = master.as_raw_fd();
+
+        // SAFETY: plain calls on a descriptor we own, ptsname's buffer is copied right away
+        let path: String = unsafe {
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let name = libc::ptsname(fd);
+            if name.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags < 0 || libc::fcntl(fd, libc::F_SETFL, flags | libc::O_NONBLOCK) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            std::ffi::CStr::from_ptr(name)
+                .to_string_lossy()
+                .into_owned()
+        };
+
+        Ok((Uart::with_line(Box::new(master)), path))
+    }
+
+    fn with_line(line: Box<dyn Line>) -> Self {
+        Uart {
+            line,
+            received: None,
+            control: 0,
+            closed: false,
+        }
+    }
+
+    fn receive(&mut self) {
+        if self.received.is_some() || self.closed {
+            return;
+        }
+
+        let mut buffer: [u8; 1] = [0; 1];
+
+        // Errors count as no byte yet, a pty reports one until a host tool opens the terminal
+        match self.line.read(&mut buffer) {
+            Ok(1) => self.received = Some(buffer[0]),
+            Ok(_) => self.closed = true,
+            Err(_) => {}
+        }
+    }
+}
+
+impl Serial for Uart {
+    fn status(&mut self) -> u16 {
+        self.receive();
+        self.control
+            | if self.received.is_some() {
+                UART_READY
+            } else {
+                0
+            }
+    }
+
+    fn set_control(&mut self, value: u16) {
+        self.control = value & (UART_INTERRUPT_ENABLE | UART_PRIORITY | UART_VECTOR);
+    }
+
+    fn read_data(&mut self) -> u16 {
+        self.receive();
+        self.received.take().unwrap_or(0) as u16
+    }
+
+    fn write_data(&mut self, value: u16) {
+        if !self.closed {
+            let _ = self.line.write_all(&[value as u8]);
+        }
+    }
+
+    // Called after every instruction, looks for incoming bytes every so often while
+    // interrupts are enabled
+    #[inline]
+    fn tick(&mut self, instruction_count: u64) {
+        if self.control & UART_INTERRUPT_ENABLE != 0
+            && instruction_count.is_multiple_of(POLL_INTERVAL)
+        {
+            self.receive();
+        }
+    }
+
+    #[inline]
+    fn interrupt(&self) -> Option<(u16, u8)> {
+        if self.control & UART_INTERRUPT_ENABLE == 0 || self.received.is_none() {
+            return None;
+        }
+
+        let priority: u16 = match (self.control & UART_PRIORITY) >> 8 {
+            0 => UART_DEFAULT_PRIORITY,
+            priority => priority,
+        };
+        let vector: u8 = match (self.control & UART_VECTOR) as u8 {
+            0 => UART_DEFAULT_VECTOR,
+            vector => vector,
+        };
+        Some((priority, vector))
+    }
+}
+
+#[cfg(test)]
+#[path = "./uart_test.rs"]
+mod uart_test;
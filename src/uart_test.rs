@@ -0,0 +1,125 @@
+use std::{
+    io::{Read, Write},
+    os::unix::net::UnixStream,
+};
+
+use crate::{
+    assembler::assemble,
+    cpu::{RunState, CPU},
+    device::Serial,
+    memory::{Memory, URDR, URSR, UTDR, UTSR},
+    uart::{
+        Uart, UART_DEFAULT_PRIORITY, UART_DEFAULT_VECTOR, UART_INTERRUPT_ENABLE, UART_PRIORITY,
+        UART_READY, UART_VECTOR,
+    },
+};
+
+fn pair() -> (Uart, UnixStream) {
+    let (device, host) = UnixStream::pair().unwrap();
+    (Uart::new(device).unwrap(), host)
+}
+
+#[test]
+fn test_registers() {
+    let mut memory: Memory = Memory::new();
+
+    // No serial port attached
+    assert_eq!(memory.read(URSR), 0);
+    assert_eq!(memory.read(UTSR), 0);
+
+    let (uart, mut host) = pair();
+    memory.set_uart(uart);
+    assert_eq!(memory.read(URSR), 0);
+    assert_eq!(memory.read(URDR), 0);
+    assert_eq!(memory.read(UTSR), 0x8000);
+
+    host.write_all(b"ok").unwrap();
+    assert_eq!(memory.read(URSR), UART_READY);
+    assert_eq!(memory.read(URSR), UART_READY);
+    assert_eq!(memory.read(URDR), b'o' as u16);
+    assert_eq!(memory.read(URDR), b'k' as u16);
+    assert_eq!(memory.read(URSR), 0);
+
+    memory.write(URSR, 0xFFFF);
+    assert_eq!(
+        memory.read(URSR),
+        UART_INTERRUPT_ENABLE | UART_PRIORITY | UART_VECTOR
+    );
+
+    memory.write(UTDR, b'!' as u16);
+    let mut byte: [u8; 1] = [0; 1];
+    host.read_exact(&mut byte).unwrap();
+    assert_eq!(&byte, b"!");
+}
+
+#[test]
+fn test_interrupt_configuration() {
+    let (mut uart, mut host) = pair();
+    host.write_all(b"x").unwrap();
+
+    uart.set_control(UART_INTERRUPT_ENABLE);
+    assert_eq!(uart.status(), UART_READY | UART_INTERRUPT_ENABLE);
+    assert_eq!(
+        uart.interrupt(),
+        Some((UART_DEFAULT_PRIORITY, UART_DEFAULT_VECTOR))
+    );
+
+    // Priority and vector are set like the timer's
+    uart.set_control(UART_INTERRUPT_ENABLE | 0x0690);
+    assert_eq!(uart.interrupt(), Some((6, 0x90)));
+
+    uart.set_control(0x0690);
+    assert_eq!(uart.interrupt(), None);
+}
+
+#[test]
+fn test_closed_line() {
+    let (mut uart, host) = pair();
+
+    drop(host);
+    assert_eq!(uart.status(), 0);
+    assert_eq!(uart.read_data(), 0);
+    uart.write_data(b'x' as u16);
+}
+
+#[test]
+fn test_receive_interrupt() {
+    let program = assemble(
+        "
+        .ORIG x3000
+        LD R0, HANDLER      ; Install the handler at vector x82
+        STI R0, VECTOR
+        LD R0, CONTROL
+        STI R0, URSR
+LOOP    ADD R1, R1, #1
+        BRnzp LOOP
+HANDLER .FILL ECHO
+VECTOR  .FILL x0182
+CONTROL .FILL x4000
+URSR    .FILL xFE18
+URDR    .FILL xFE1A
+UTDR    .FILL xFE1E
+ECHO    ADD R2, R2, #1
+        LDI R0, URDR
+        STI R0, UTDR
+        RTI
+        .END
+        ",
+    )
+    .unwrap();
+
+    let (uart, mut host) = pair();
+    let mut cpu: CPU = CPU::new();
+    cpu.load_image(&program.to_object()).unwrap();
+    cpu.memory_mut().set_uart(uart);
+    cpu.set_register(6, 0x3000);
+
+    host.write_all(b"hi").unwrap();
+    assert_eq!(cpu.run(Some(5000)), RunState::Running);
+
+    // Each received byte interrupts the loop once and gets echoed back
+    assert_eq!(cpu.registers()[2], 2);
+    let mut echo: [u8; 2] = [0; 2];
+    host.read_exact(&mut echo).unwrap();
+    assert_eq!(&echo, b"hi");
+}
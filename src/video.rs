@@ -0,0 +1,127 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    cpu::{RunState, CPU},
+    memory::Memory,
+};
+
+// PennSim video memory, one word per pixel in rows of 128 starting at the top left
+pub const VIDEO_MEMORY: u16 = 0xC000;
+pub const VIDEO_WIDTH: usize = 128;
+pub const VIDEO_HEIGHT: usize = 124;
+
+// A snapshot of the framebuffer. Pixels are 15-bit RGB, five bits per channel with red in
+// bits 14 to 10, green in 9 to 5 and blue in 4 to 0
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    pixels: Vec<u16>,
+}
+
+impl Frame {
+    pub fn capture(memory: &Memory) -> Self {
+        let end: u16 = VIDEO_MEMORY + (VIDEO_WIDTH * VIDEO_HEIGHT) as u16 - 1;
+
+        Frame {
+            pixels: memory.words(VIDEO_MEMORY, end),
+        }
+    }
+
+    // Color of a pixel scaled to 8 bits per channel
+    pub fn rgb(&self, x: usize, y: usize) -> [u8; 3] {
+        let pixel: u16 = self.pixels[y * VIDEO_WIDTH + x];
+        let channel = |shift: u16| -> u8 {
+            let value: u8 = ((pixel >> shift) & 0x1F) as u8;
+            (value << 3) | (value >> 2)
+        };
+
+        [channel(10), channel(5), channel(0)]
+    }
+
+    // Binary PPM image of the frame
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut image: Vec<u8> =
+            format!("P6\n{} {}\n255\n", VIDEO_WIDTH, VIDEO_HEIGHT).into_bytes();
+
+        for y in 0..VIDEO_HEIGHT {
+            for x in 0..VIDEO_WIDTH {
+                image.extend(self.rgb(x, y));
+            }
+        }
+
+        image
+    }
+}
+
+// Runs a program while saving numbered frames of the framebuffer every so many instructions,
+// plus one when the program stops
+pub struct Video {
+    path: PathBuf,
+    every: Option<u64>,
+    frames: u64,
+}
+
+impl Video {
+    // `path` names the final frame, periodic frames get their number inserted before the
+    // extension: video.ppm, video-0001.ppm, video-0002.ppm, ...
+    pub fn new(path: &str, every: Option<u64>) -> Self {
+        Video {
+            path: PathBuf::from(path),
+            every,
+            frames: 0,
+        }
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+
+    pub fn run(&mut self, cpu: &mut CPU, limit: Option<u64>) -> io::Result<RunState> {
+        let mut executed: u64 = 0;
+
+        while cpu.state() == RunState::Running && limit.is_none_or(|limit| executed < limit) {
+            let slice: u64 = match (self.every, limit) {
+                (Some(every), Some(limit)) => every.min(limit - executed),
+                (Some(every), None) => every,
+                (None, Some(limit)) => limit - executed,
+                (None, None) => u64::MAX,
+            };
+            let start: u64 = cpu.instruction_count();
+
+            cpu.run(Some(slice));
+            executed += cpu.instruction_count() - start;
+
+            // Runs cut short by the limit end with the final frame only
+            if self.every == Some(slice) && cpu.state() == RunState::Running {
+                self.frames += 1;
+                self.save(cpu, &self.frame_path(self.frames))?;
+            }
+        }
+
+        self.save(cpu, &self.path)?;
+        Ok(cpu.state())
+    }
+
+    fn save(&self, cpu: &CPU, path: &Path) -> io::Result<()> {
+        fs::write(path, Frame::capture(cpu.memory()).to_ppm())
+    }
+
+    fn frame_path(&self, frame: u64) -> PathBuf {
+        let stem: String = self
+            .path
+            .file_stem()
+            .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+        let name: String = match self.path.extension() {
+            Some(extension) => format!("{}-{:04}.{}", stem, frame, extension.to_string_lossy()),
+            None => format!("{}-{:04}", stem, frame),
+        };
+
+        self.path.with_file_name(name)
+    }
+}
+
+#[cfg(test)]
+#[path = "./video_test.rs"]
+mod video_test;
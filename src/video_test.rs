@@ -0,0 +1,89 @@
+use crate::{
+    assembler::assemble,
+    cpu::{RunState, CPU},
+    video::{Frame, Video, VIDEO_HEIGHT, VIDEO_MEMORY, VIDEO_WIDTH},
+};
+
+// Paints the top row red one pixel per loop iteration, then a white pixel at (5, 2)
+const PAINT: &str = "
+        .ORIG x3000
+        LD R0, SCREEN
+        LD R1, RED
+        LD R2, WIDTH
+ROW     STR R1, R0, #0
+        ADD R0, R0, #1
+        ADD R2, R2, #-1
+        BRp ROW
+        LD R0, PIXEL
+        LD R1, WHITE
+        STR R1, R0, #0
+        HALT
+SCREEN  .FILL xC000
+RED     .FILL x7C00
+WIDTH   .FILL #128
+PIXEL   .FILL xC105
+WHITE   .FILL x7FFF
+        .END
+";
+
+fn painter() -> CPU {
+    let mut cpu: CPU = CPU::with_console(Box::new(crate::console::BufferedConsole::new(&[])));
+    cpu.load_image(&assemble(PAINT).unwrap().to_object())
+        .unwrap();
+    cpu
+}
+
+#[test]
+fn test_capture() {
+    let mut cpu: CPU = painter();
+    cpu.memory_mut().poke(VIDEO_MEMORY + 1, 0b00001_10000_11111);
+
+    let frame: Frame = Frame::capture(cpu.memory());
+    assert_eq!(frame.rgb(0, 0), [0, 0, 0]);
+    assert_eq!(frame.rgb(1, 0), [0x08, 0x84, 0xFF]);
+
+    assert_eq!(cpu.run(Some(10_000)), RunState::Halted);
+
+    let frame: Frame = Frame::capture(cpu.memory());
+    assert_eq!(frame.rgb(127, 0), [0xFF, 0, 0]);
+    assert_eq!(frame.rgb(0, 1), [0, 0, 0]);
+    assert_eq!(frame.rgb(5, 2), [0xFF, 0xFF, 0xFF]);
+    assert_eq!(frame.rgb(VIDEO_WIDTH - 1, VIDEO_HEIGHT - 1), [0, 0, 0]);
+}
+
+#[test]
+fn test_ppm() {
+    let mut cpu: CPU = painter();
+    cpu.run(Some(10_000));
+
+    let image: Vec<u8> = Frame::capture(cpu.memory()).to_ppm();
+    let header: &[u8] = b"P6\n128 124\n255\n";
+
+    assert!(image.starts_with(header));
+    assert_eq!(image.len(), header.len() + VIDEO_WIDTH * VIDEO_HEIGHT * 3);
+    assert_eq!(image[header.len()..header.len() + 3], [0xFF, 0, 0]);
+}
+
+#[test]
+fn test_periodic_frames() {
+    let directory = std::env::temp_dir();
+    let path = directory.join("lc_3_video.ppm");
+    let mut cpu: CPU = painter();
+    let mut video: Video = Video::new(&path.to_string_lossy(), Some(100));
+
+    assert_eq!(video.run(&mut cpu, None).unwrap(), RunState::Halted);
+
+    // The row takes 3 + 4 * 128 instructions, the last frames show it growing
+    assert_eq!(video.frames(), 5);
+    let frame = std::fs::read(directory.join("lc_3_video-0005.ppm")).unwrap();
+    let last = std::fs::read(&path).unwrap();
+    assert_eq!(frame.len(), last.len());
+    assert_ne!(frame, last);
+
+    // A limit stops the run like it does without video
+    let mut cpu: CPU = painter();
+    let mut video: Video = Video::new(&path.to_string_lossy(), Some(100));
+    assert_eq!(video.run(&mut cpu, Some(250)).unwrap(), RunState::Running);
+    assert_eq!(cpu.instruction_count(), 250);
+    assert_eq!(video.frames(), 2);
+}